@@ -0,0 +1,76 @@
+use crate::cameras::{Camera, Shutter};
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+use std::f64::consts::PI;
+
+/// A 360x180 degree panorama camera, e.g. for rendering environment probes.
+///
+/// The horizontal image axis maps to the longitude, starting and ending behind the camera,
+/// and the vertical axis maps to the latitude from straight up to straight down.
+/// Images should therefore have an aspect ratio of 2:1.
+pub struct Equirectangular {
+    pub viewpoint: Vector,
+    forward: Vector,
+    right: Vector,
+    up: Vector,
+    shutter: Shutter,
+}
+
+impl Equirectangular {
+    pub fn new(viewpoint: Vector, direction: Vector, up: Vector) -> Self {
+        let forward = direction.normalize();
+        let right = forward.cross(&up).normalize();
+        Self {
+            viewpoint: viewpoint,
+            forward: forward,
+            right: right,
+            up: right.cross(&forward),
+            shutter: Shutter::instant(),
+        }
+    }
+
+    /// Shoots every ray at a random point in time within the shutter interval
+    pub fn set_shutter(&mut self, shutter: Shutter) {
+        self.shutter = shutter;
+    }
+}
+
+impl Camera for Equirectangular {
+    fn get_ray(&self, x: f64, y: f64) -> Ray {
+        let longitude = (x * 2f64 - 1f64) * PI;
+        let latitude = (0.5f64 - y) * PI;
+        Ray::new_at_time(
+            self.viewpoint,
+            (self.right * (latitude.cos() * longitude.sin()))
+                + (self.up * latitude.sin())
+                + (self.forward * (latitude.cos() * longitude.cos())),
+            self.shutter.sample(),
+        )
+    }
+
+    fn shutter(&self) -> Shutter {
+        self.shutter
+    }
+}
+
+#[test]
+fn test_directions() {
+    let camera = Equirectangular::new(
+        Vector::new(1f64, 2f64, 3f64),
+        Vector::new(0f64, 0f64, -2f64),
+        Vector::new(0f64, 1f64, 0f64),
+    );
+    let looks = |x: f64, y: f64, expected: Vector| {
+        let ray = camera.get_ray(x, y);
+        assert_eq!(ray.origin, Vector::new(1f64, 2f64, 3f64));
+        assert!((ray.direction.normalize() - expected).length() < 1e-9);
+    };
+
+    looks(0.5, 0.5, Vector::new(0f64, 0f64, -1f64));
+    looks(0.75, 0.5, Vector::new(1f64, 0f64, 0f64));
+    looks(0.5, 0f64, Vector::new(0f64, 1f64, 0f64));
+    // The left and right border of the image meet behind the camera
+    looks(0f64, 0.5, Vector::new(0f64, 0f64, 1f64));
+    looks(1f64, 0.5, Vector::new(0f64, 0f64, 1f64));
+}
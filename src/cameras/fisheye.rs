@@ -0,0 +1,112 @@
+use crate::cameras::{Camera, Shutter};
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+use std::f64::consts::PI;
+
+/// How the angle between a ray and the optical axis maps to the distance from the image center
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FisheyeProjection {
+    /// The distance from the center is proportional to the angle
+    Equidistant,
+    /// Equal solid angles cover equal areas of the image
+    Equisolid,
+}
+
+/// A fisheye camera whose image circle touches the borders of the image.
+///
+/// Points outside of the image circle continue the projection up to the direction
+/// straight behind the camera.
+pub struct Fisheye {
+    pub viewpoint: Vector,
+    forward: Vector,
+    right: Vector,
+    up: Vector,
+    field_of_view: f64,
+    projection: FisheyeProjection,
+    shutter: Shutter,
+}
+
+impl Fisheye {
+    /// `field_of_view` is the full opening angle of the image circle in radians
+    pub fn new(
+        viewpoint: Vector,
+        direction: Vector,
+        up: Vector,
+        field_of_view: f64,
+        projection: FisheyeProjection,
+    ) -> Self {
+        let forward = direction.normalize();
+        let right = forward.cross(&up).normalize();
+        Self {
+            viewpoint: viewpoint,
+            forward: forward,
+            right: right,
+            up: right.cross(&forward),
+            field_of_view: field_of_view,
+            projection: projection,
+            shutter: Shutter::instant(),
+        }
+    }
+
+    /// Shoots every ray at a random point in time within the shutter interval
+    pub fn set_shutter(&mut self, shutter: Shutter) {
+        self.shutter = shutter;
+    }
+
+    /// The angle to the optical axis of a point at distance `radius` from the image center
+    fn angle(&self, radius: f64) -> f64 {
+        let half_fov = self.field_of_view / 2f64;
+        let angle = match self.projection {
+            FisheyeProjection::Equidistant => radius * half_fov,
+            FisheyeProjection::Equisolid => {
+                2f64 * (radius * (half_fov / 2f64).sin()).min(1f64).asin()
+            }
+        };
+        angle.min(PI)
+    }
+}
+
+impl Camera for Fisheye {
+    fn get_ray(&self, x: f64, y: f64) -> Ray {
+        let image_x = x * 2f64 - 1f64;
+        let image_y = 1f64 - y * 2f64;
+        let radius = (image_x * image_x + image_y * image_y).sqrt();
+        if radius == 0f64 {
+            return Ray::new_at_time(self.viewpoint, self.forward, self.shutter.sample());
+        }
+
+        let angle = self.angle(radius);
+        let sideways = (self.right * (image_x / radius)) + (self.up * (image_y / radius));
+        Ray::new_at_time(
+            self.viewpoint,
+            (self.forward * angle.cos()) + (sideways * angle.sin()),
+            self.shutter.sample(),
+        )
+    }
+
+    fn shutter(&self) -> Shutter {
+        self.shutter
+    }
+}
+
+#[test]
+fn test_image_circle() {
+    for projection in [FisheyeProjection::Equidistant, FisheyeProjection::Equisolid].iter() {
+        let camera = Fisheye::new(
+            Vector::new(0f64, 0f64, 0f64),
+            Vector::new(0f64, 0f64, -1f64),
+            Vector::new(0f64, 1f64, 0f64),
+            PI,
+            *projection,
+        );
+
+        let center = camera.get_ray(0.5, 0.5);
+        assert_eq!(center.direction, Vector::new(0f64, 0f64, -1f64));
+
+        // The border of the image circle is at half the field of view
+        let border = camera.get_ray(1f64, 0.5).direction.normalize();
+        assert!((border.dot(&Vector::new(0f64, 0f64, -1f64))).abs() < 1e-9);
+        assert!((border.x() - 1f64).abs() < 1e-9);
+    }
+}
@@ -1,8 +1,72 @@
 use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+use crate::primitives::sampler;
+
+use rand::Rng;
 
 pub mod pinhole;
 pub mod thin_lense;
+pub mod orthographic;
+pub mod fisheye;
+pub mod equirectangular;
 
 pub trait Camera {
     fn get_ray(&self, x: f64, y: f64) -> Ray;
+
+    /// Where light travelling from `point` towards the camera lands on the film, for integrators
+    /// that trace paths from the lights to the camera.
+    ///
+    /// Returns `None` if the light misses the film or the camera does not support it.
+    fn project(&self, _point: Vector) -> Option<Projection> {
+        None
+    }
+
+    /// The interval in which the shutter is open, for integrators that start paths at the lights
+    /// and have to pick the time of the path themselves
+    fn shutter(&self) -> Shutter {
+        Shutter::instant()
+    }
+}
+
+/// The film position that light from a point lands on, see [`Camera::project`]
+#[derive(Debug, Clone, Copy)]
+pub struct Projection {
+    /// The film coordinates, as passed to [`Camera::get_ray`]
+    pub x: f64,
+    pub y: f64,
+    /// The point of the camera that the light passes through
+    pub origin: Vector,
+    /// The probability density, per solid angle, with which rays through uniformly distributed
+    /// film coordinates point towards the projected point
+    pub pdf: f64,
+}
+
+/// The interval of time in which the shutter of a camera is open
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shutter {
+    pub open: f64,
+    pub close: f64,
+}
+
+impl Shutter {
+    pub fn new(open: f64, close: f64) -> Self {
+        Self {
+            open: open,
+            close: close,
+        }
+    }
+
+    /// A shutter that only opens at time zero, i.e. no motion blur
+    pub fn instant() -> Self {
+        Self::new(0f64, 0f64)
+    }
+
+    /// A random point in time while the shutter is open
+    pub fn sample(&self) -> f64 {
+        if self.open == self.close {
+            self.open
+        } else {
+            self.open + sampler::rng().gen::<f64>() * (self.close - self.open)
+        }
+    }
 }
\ No newline at end of file
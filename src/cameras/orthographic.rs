@@ -0,0 +1,60 @@
+use crate::cameras::{Camera, Shutter};
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+/// A camera with parallel rays, as used for technical and architectural views.
+///
+/// Every ray starts on the view plane and points along `viewplane_right x viewplane_down`,
+/// so objects keep their size regardless of their distance to the camera.
+pub struct Orthographic {
+    viewplane_top_left: Vector,
+    viewplane_down: Vector,
+    viewplane_right: Vector,
+    direction: Vector,
+    shutter: Shutter,
+}
+
+impl Orthographic {
+    pub fn new(viewplane_top_left: Vector, viewplane_down: Vector, viewplane_right: Vector) -> Self {
+        Self {
+            viewplane_top_left: viewplane_top_left,
+            viewplane_down: viewplane_down,
+            viewplane_right: viewplane_right,
+            direction: viewplane_right.cross(&viewplane_down).normalize(),
+            shutter: Shutter::instant(),
+        }
+    }
+
+    /// Shoots every ray at a random point in time within the shutter interval
+    pub fn set_shutter(&mut self, shutter: Shutter) {
+        self.shutter = shutter;
+    }
+}
+
+impl Camera for Orthographic {
+    fn get_ray(&self, x: f64, y: f64) -> Ray {
+        let viewplane_point =
+            self.viewplane_top_left + (self.viewplane_down * y) + (self.viewplane_right * x);
+        Ray::new_at_time(viewplane_point, self.direction, self.shutter.sample())
+    }
+
+    fn shutter(&self) -> Shutter {
+        self.shutter
+    }
+}
+
+#[test]
+fn test_parallel_rays() {
+    let camera = Orthographic::new(
+        Vector::new(-1f64, 1f64, -1f64),
+        Vector::new(0f64, -2f64, 0f64),
+        Vector::new(2f64, 0f64, 0f64),
+    );
+
+    // All rays point along the normal of the view plane and start on it
+    for &(x, y) in [(0f64, 0f64), (0.25, 0.75), (1f64, 0.5)].iter() {
+        let ray = camera.get_ray(x, y);
+        assert!((ray.direction.normalize() - Vector::new(0f64, 0f64, -1f64)).length() < 1e-9);
+        assert_eq!(ray.origin, Vector::new(-1f64 + 2f64 * x, 1f64 - 2f64 * y, -1f64));
+    }
+}
@@ -1,12 +1,13 @@
 use crate::primitives::ray::Ray;
 use crate::primitives::vec::Vector;
-use crate::cameras::Camera;
+use crate::cameras::{Camera, Projection, Shutter};
 
 pub struct Pinhole {
     pub viewpoint: Vector,
     viewplane_top_left: Vector,
     viewplane_down: Vector,
     viewplane_right: Vector,
+    shutter: Shutter,
 }
 
 impl Pinhole {
@@ -21,13 +22,89 @@ impl Pinhole {
             viewplane_top_left: viewplane_top_left,
             viewplane_down: viewplane_down,
             viewplane_right: viewplane_right,
+            shutter: Shutter::instant(),
         }
     }
+
+    /// Shoots every ray at a random point in time within the shutter interval
+    pub fn set_shutter(&mut self, shutter: Shutter) {
+        self.shutter = shutter;
+    }
 }
 impl Camera for Pinhole {
     fn get_ray(&self, x: f64, y: f64) -> Ray {
         let viewplane_point =
             self.viewplane_top_left + (self.viewplane_down * y) + (self.viewplane_right * x);
-        Ray::new(self.viewpoint, viewplane_point - self.viewpoint)
+        Ray::new_at_time(
+            self.viewpoint,
+            viewplane_point - self.viewpoint,
+            self.shutter.sample(),
+        )
+    }
+
+    fn project(&self, point: Vector) -> Option<Projection> {
+        let direction = point - self.viewpoint;
+        let normal = self.viewplane_right.cross(&self.viewplane_down);
+        let denominator = direction.dot(&normal);
+        if denominator == 0f64 {
+            return None;
+        }
+        let t = (self.viewplane_top_left - self.viewpoint).dot(&normal) / denominator;
+        if t <= 0f64 {
+            return None;
+        }
+        // Solve for the film coordinates, which also works for a sheared viewplane
+        let offset = self.viewpoint + direction * t - self.viewplane_top_left;
+        let (right, down) = (self.viewplane_right, self.viewplane_down);
+        let (rr, rd, dd) = (right.dot(&right), right.dot(&down), down.dot(&down));
+        let (ro, od) = (right.dot(&offset), down.dot(&offset));
+        let determinant = rr * dd - rd * rd;
+        let x = (ro * dd - od * rd) / determinant;
+        let y = (od * rr - ro * rd) / determinant;
+        if !(0f64..1f64).contains(&x) || !(0f64..1f64).contains(&y) {
+            return None;
+        }
+        // The viewplane covers the area |right x down| at the distance t * |direction|
+        let distance = direction.length() * t;
+        let cos = (denominator / (direction.length() * normal.length())).abs();
+        Some(Projection {
+            x: x,
+            y: y,
+            origin: self.viewpoint,
+            pdf: distance * distance / (normal.length() * cos),
+        })
     }
+
+    fn shutter(&self) -> Shutter {
+        self.shutter
+    }
+}
+
+/// The camera that tests look through: at the origin towards -z, with a film of two by two units
+/// at distance one
+#[cfg(test)]
+pub(crate) fn test_camera() -> Pinhole {
+    Pinhole::new(
+        Vector::new(0f64, 0f64, 0f64),
+        Vector::new(-1f64, 1f64, -1f64),
+        Vector::new(0f64, -2f64, 0f64),
+        Vector::new(2f64, 0f64, 0f64),
+    )
+}
+
+#[test]
+fn test_project() {
+    let camera = test_camera();
+    // Points along a ray land where the ray started on the film
+    let ray = camera.get_ray(0.3, 0.8);
+    let projection = camera.project(ray.point_at_parameter(3f64)).unwrap();
+    assert!((projection.x - 0.3).abs() < 1e-9);
+    assert!((projection.y - 0.8).abs() < 1e-9);
+    assert_eq!(projection.origin, Vector::new(0f64, 0f64, 0f64));
+
+    // The film covers four units of area at distance one
+    let center = camera.project(Vector::new(0f64, 0f64, -5f64)).unwrap();
+    assert!((center.pdf - 0.25).abs() < 1e-9);
+    assert!(camera.project(Vector::new(0f64, 0f64, 5f64)).is_none());
+    assert!(camera.project(Vector::new(3f64, 0f64, -1f64)).is_none());
 }
@@ -1,17 +1,130 @@
-use crate::cameras::Camera;
+use crate::cameras::{Camera, Shutter};
+use crate::primitives::distribution::Distribution2D;
 use crate::primitives::ray::Ray;
 use crate::primitives::vec::Vector;
+use crate::primitives::sampler;
 
+use rand::Rng;
+use std::f64::consts::PI;
+use std::path::Path;
+
+/// A grayscale mask that shapes the aperture, and thereby the bokeh, of a [`ThinLenseCamera`].
+///
+/// The mask covers the square around the circular lens; brighter texels let through more light.
+pub struct BokehTexture {
+    /// `None` for empty or black masks, which keep the circular aperture
+    distribution: Option<Distribution2D>,
+}
+
+impl BokehTexture {
+    /// Creates a mask from `width * height` row-major weights, starting at the top left.
+    ///
+    /// Masks without any texels, with fewer weights than texels or without any weight keep the
+    /// circular aperture.
+    pub fn new(width: usize, height: usize, weights: &[f64]) -> Self {
+        let texels = width
+            .checked_mul(height)
+            .filter(|texels| *texels > 0 && *texels <= weights.len())
+            .filter(|texels| weights[..*texels].iter().any(|w| *w > 0f64));
+        Self {
+            distribution: texels.map(|_| Distribution2D::new(weights, width, height)),
+        }
+    }
+
+    /// Loads the mask from an image file, using the luminance of every pixel as its weight
+    pub fn open<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        let img = image::open(path)?.to_luma();
+        let weights: Vec<f64> = img.pixels().map(|p| p.0[0] as f64 / 255f64).collect();
+        Ok(Self::new(img.width() as usize, img.height() as usize, &weights))
+    }
+
+    /// Maps two uniform random numbers to a point in [-1, 1]^2, distributed like the mask
+    fn sample(&self, u1: f64, u2: f64) -> (f64, f64) {
+        match &self.distribution {
+            Some(distribution) => {
+                let ((x, y), _) = distribution.sample(u1, u2);
+                (x * 2f64 - 1f64, 1f64 - y * 2f64)
+            }
+            None => concentric_sample_disk(u1, u2),
+        }
+    }
+}
+
+/// The shape of the lens opening
+pub enum ApertureShape {
+    Circle,
+    /// A regular polygon as formed by the aperture blades of a real lens.
+    /// `rotation` is in radians, with the first corner pointing right at zero.
+    Polygon { blades: usize, rotation: f64 },
+    Texture(BokehTexture),
+}
+
+impl ApertureShape {
+    /// Maps two uniform random numbers to a point on the aperture with radius one
+    fn sample(&self, u1: f64, u2: f64) -> (f64, f64) {
+        match self {
+            ApertureShape::Circle => concentric_sample_disk(u1, u2),
+            ApertureShape::Polygon { blades, rotation } => {
+                // Pick one of the triangles between the center and two neighbouring corners
+                let blades = (*blades).max(3);
+                let scaled = u1 * blades as f64;
+                let triangle = (scaled as usize).min(blades - 1);
+                let u1 = scaled - triangle as f64;
+
+                let corner = |i: usize| {
+                    let angle = rotation + 2f64 * PI * (i as f64) / (blades as f64);
+                    (angle.cos(), angle.sin())
+                };
+                let (x0, y0) = corner(triangle);
+                let (x1, y1) = corner(triangle + 1);
+
+                // Uniformly sample the triangle
+                let su = u1.sqrt();
+                let b0 = su * (1f64 - u2);
+                let b1 = su * u2;
+                (x0 * b0 + x1 * b1, y0 * b0 + y1 * b1)
+            }
+            ApertureShape::Texture(texture) => texture.sample(u1, u2),
+        }
+    }
+}
+
+/// Maps the unit square to the unit disk while preserving the relative areas and strata
+fn concentric_sample_disk(u1: f64, u2: f64) -> (f64, f64) {
+    let x = u1 * 2f64 - 1f64;
+    let y = u2 * 2f64 - 1f64;
+    if x == 0f64 && y == 0f64 {
+        (0f64, 0f64)
+    } else if x.abs() > y.abs() {
+        let angle = PI / 4f64 * (y / x);
+        (x * angle.cos(), x * angle.sin())
+    } else {
+        let angle = PI / 2f64 - PI / 4f64 * (x / y);
+        (y * angle.cos(), y * angle.sin())
+    }
+}
+
+/// A camera with a thin lens that produces depth of field.
+///
+/// The lens lies in the plane through `viewpoint` perpendicular to the view direction
+/// `viewplane_right x viewplane_down`. Everything at exactly `focus_distance` along the
+/// view direction is in perfect focus.
 pub struct ThinLenseCamera {
     pub viewpoint: Vector,
     viewplane_top_left: Vector,
     viewplane_down: Vector,
     viewplane_right: Vector,
+    forward: Vector,
+    lens_right: Vector,
+    lens_up: Vector,
     focus_distance: f64,
     aperture: f64,
+    aperture_shape: ApertureShape,
+    shutter: Shutter,
 }
 
 impl ThinLenseCamera {
+    /// `aperture` is the radius of the lens in world units
     pub fn new(
         viewpoint: Vector,
         viewplane_top_left: Vector,
@@ -20,28 +133,90 @@ impl ThinLenseCamera {
         focus_distance: f64,
         aperture: f64,
     ) -> Self {
+        let lens_right = viewplane_right.normalize();
+        let forward = viewplane_right.cross(&viewplane_down).normalize();
         Self {
             viewpoint: viewpoint,
             viewplane_top_left: viewplane_top_left,
             viewplane_down: viewplane_down,
             viewplane_right: viewplane_right,
+            forward: forward,
+            lens_right: lens_right,
+            lens_up: lens_right.cross(&forward),
             focus_distance: focus_distance,
             aperture: aperture,
+            aperture_shape: ApertureShape::Circle,
+            shutter: Shutter::instant(),
         }
     }
+
+    /// Shoots every ray at a random point in time within the shutter interval
+    pub fn set_shutter(&mut self, shutter: Shutter) {
+        self.shutter = shutter;
+    }
+
+    pub fn set_aperture_shape(&mut self, aperture_shape: ApertureShape) {
+        self.aperture_shape = aperture_shape;
+    }
 }
+
 impl Camera for ThinLenseCamera {
     fn get_ray(&self, x: f64, y: f64) -> Ray {
-        let blur_offset = Vector::random_in_unit_sphere() * self.aperture;
-
         let viewplane_point =
             self.viewplane_top_left + (self.viewplane_down * y) + (self.viewplane_right * x);
 
-        let viewpoint = self.viewpoint + blur_offset;
-        Ray::new(
-            viewpoint,
-            ((viewplane_point - self.viewpoint) * self.focus_distance) + self.viewpoint - viewpoint,
-        )
+        // The point on the focus plane that the ray through the lens center hits
+        let center_direction = viewplane_point - self.viewpoint;
+        let focus_point = self.viewpoint
+            + center_direction * (self.focus_distance / center_direction.dot(&self.forward));
+
+        let mut rng = sampler::rng();
+        let (lens_x, lens_y) = self.aperture_shape.sample(rng.gen::<f64>(), rng.gen::<f64>());
+        let viewpoint = self.viewpoint
+            + (self.lens_right * (lens_x * self.aperture))
+            + (self.lens_up * (lens_y * self.aperture));
+
+        Ray::new_at_time(viewpoint, focus_point - viewpoint, self.shutter.sample())
+    }
+
+    fn shutter(&self) -> Shutter {
+        self.shutter
     }
 }
 unsafe impl Sync for ThinLenseCamera {}
+
+#[test]
+fn test_focus_plane() {
+    let mut camera = ThinLenseCamera::new(
+        Vector::new(0f64, 0f64, 0f64),
+        Vector::new(-1f64, 1f64, -1f64),
+        Vector::new(0f64, -2f64, 0f64),
+        Vector::new(2f64, 0f64, 0f64),
+        10f64,
+        0.5f64,
+    );
+    camera.set_aperture_shape(ApertureShape::Polygon {
+        blades: 6,
+        rotation: 0.3,
+    });
+
+    // All rays through the same film position meet at the focus distance
+    for _ in 0..100 {
+        let ray = camera.get_ray(0.25, 0.75);
+        assert!(ray.origin.z().abs() < 1e-12);
+        assert!((ray.origin.x().powi(2) + ray.origin.y().powi(2)).sqrt() <= 0.5 + 1e-12);
+
+        let focus_point = ray.point_at_parameter(-10f64 / ray.direction.z());
+        assert!((focus_point.x() + 5f64).abs() < 1e-9);
+        assert!((focus_point.y() + 5f64).abs() < 1e-9);
+    }
+
+    // Empty and black masks keep the circular aperture
+    for mask in [BokehTexture::new(0, 0, &[]), BokehTexture::new(2, 2, &[0f64; 4])] {
+        camera.set_aperture_shape(ApertureShape::Texture(mask));
+        for _ in 0..100 {
+            let ray = camera.get_ray(0.25, 0.75);
+            assert!((ray.origin.x().powi(2) + ray.origin.y().powi(2)).sqrt() <= 0.5 + 1e-12);
+        }
+    }
+}
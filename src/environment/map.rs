@@ -0,0 +1,196 @@
+use crate::environment::Environment;
+use crate::primitives::distribution::Distribution2D;
+use crate::primitives::matrix::Matrix4;
+use crate::primitives::vec::{Color, Vector};
+
+use image::hdr::HdrDecoder;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// An environment light from an equirectangular (latitude-longitude) image.
+///
+/// The top row of the image is straight up (+y) and the bottom row straight down. The horizontal
+/// center of the image lies towards -z, with +x a quarter of the width to the right. Directions are
+/// importance sampled proportionally to the luminance of the texels.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+    distribution: Distribution2D,
+    rotation: Matrix4,
+    inverse_rotation: Matrix4,
+    intensity: f64,
+}
+
+impl EnvironmentMap {
+    /// Creates a map from `width * height` row-major texels of linear radiance, starting at the top left
+    pub fn new(width: usize, height: usize, texels: Vec<Color>) -> Self {
+        assert_eq!(texels.len(), width * height);
+        assert!(width > 0 && height > 0);
+
+        // Rows near the poles cover a smaller solid angle
+        let weights: Vec<f64> = texels
+            .iter()
+            .enumerate()
+            .map(|(i, texel)| {
+                let theta = ((i / width) as f64 + 0.5) / height as f64 * PI;
+                texel.luminance().max(0f64) * theta.sin()
+            })
+            .collect();
+        Self {
+            width: width,
+            height: height,
+            texels: texels,
+            distribution: Distribution2D::new(&weights, width, height),
+            rotation: Matrix4::identity(),
+            inverse_rotation: Matrix4::identity(),
+            intensity: 1f64,
+        }
+    }
+
+    /// Loads a map from a Radiance HDR (.hdr) file. Other formats, like PNG or JPEG, are read as low
+    /// dynamic range images with the gamma of 2 that is used when exporting images.
+    ///
+    /// EXR files are not supported by the `image` version in use.
+    pub fn open<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        let path = path.as_ref();
+        let is_hdr = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("hdr"));
+        if is_hdr {
+            let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+            let metadata = decoder.metadata();
+            let texels = decoder
+                .read_image_hdr()?
+                .into_iter()
+                .map(|p| Color::from_linear(p.0[0] as f64, p.0[1] as f64, p.0[2] as f64))
+                .collect();
+            Ok(Self::new(
+                metadata.width as usize,
+                metadata.height as usize,
+                texels,
+            ))
+        } else {
+            let img = image::open(path)?.to_rgb();
+            let decode = |value: u8| (value as f64 / 255f64).powi(2);
+            let texels = img
+                .pixels()
+                .map(|p| Color::from_linear(decode(p.0[0]), decode(p.0[1]), decode(p.0[2])))
+                .collect();
+            Ok(Self::new(
+                img.width() as usize,
+                img.height() as usize,
+                texels,
+            ))
+        }
+    }
+
+    /// Rotates the environment counterclockwise around the y axis by `angle` radians
+    pub fn set_rotation(&mut self, angle: f64) {
+        self.rotation = Matrix4::rotation(Vector::new(0f64, 1f64, 0f64), angle);
+        self.inverse_rotation = self.rotation.transpose();
+    }
+
+    /// Scales the radiance of the whole map
+    pub fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity;
+    }
+
+    /// The image coordinates in [0, 1)^2 of a unit direction in world space
+    fn to_image(&self, direction: Vector) -> (f64, f64) {
+        let local = self.inverse_rotation.transform_vector(direction);
+        let u = 0.5 + local.x().atan2(-local.z()) / (2f64 * PI);
+        let v = local.y().clamp(-1f64, 1f64).acos() / PI;
+        (u.min(1f64 - f64::EPSILON), v.min(1f64 - f64::EPSILON))
+    }
+
+    /// The unit direction in world space of image coordinates
+    fn to_direction(&self, u: f64, v: f64) -> Vector {
+        let (sin_phi, cos_phi) = ((u - 0.5) * 2f64 * PI).sin_cos();
+        let (sin_theta, cos_theta) = (v * PI).sin_cos();
+        self.rotation.transform_vector(Vector::new(
+            sin_theta * sin_phi,
+            cos_theta,
+            -sin_theta * cos_phi,
+        ))
+    }
+
+    fn texel(&self, u: f64, v: f64) -> Color {
+        let x = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.texels[y * self.width + x] * self.intensity
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn radiance(&self, direction: Vector) -> Color {
+        let (u, v) = self.to_image(direction);
+        self.texel(u, v)
+    }
+
+    fn sample(&self, u1: f64, u2: f64) -> Option<(Vector, Color, f64)> {
+        let ((u, v), pdf) = self.distribution.sample(u1, u2);
+        let sin_theta = (v * PI).sin();
+        if pdf == 0f64 || sin_theta == 0f64 {
+            return None;
+        }
+        // The image is stretched over 2π x π radians, and rows are shrunk by sin(theta)
+        Some((
+            self.to_direction(u, v),
+            self.texel(u, v),
+            pdf / (2f64 * PI * PI * sin_theta),
+        ))
+    }
+
+    fn pdf(&self, direction: Vector) -> f64 {
+        let (u, v) = self.to_image(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta == 0f64 {
+            0f64
+        } else {
+            self.distribution.pdf(u, v) / (2f64 * PI * PI * sin_theta)
+        }
+    }
+}
+
+#[test]
+fn test_sampling() {
+    // A dim map with a single bright texel
+    let (width, height) = (16, 8);
+    let mut texels = vec![Color::from_linear(0.1, 0.1, 0.1); width * height];
+    texels[3 * width + 12] = Color::from_linear(100f64, 100f64, 100f64);
+    let mut map = EnvironmentMap::new(width, height, texels);
+    map.set_rotation(0.7);
+    map.set_intensity(2f64);
+
+    let mut bright = 0;
+    for i in 0..100 {
+        let (u1, u2) = (
+            (i % 10) as f64 / 10f64 + 0.05,
+            (i / 10) as f64 / 10f64 + 0.05,
+        );
+        let (direction, radiance, pdf) = map.sample(u1, u2).unwrap();
+        assert!((direction.length() - 1f64).abs() < 1e-12);
+        assert_eq!(radiance, map.radiance(direction));
+        assert!((pdf - map.pdf(direction)).abs() < 1e-9 * pdf);
+        if radiance.r() == 200f64 {
+            bright += 1;
+        }
+    }
+    // Most samples go to the bright texel
+    assert!(bright > 80);
+
+    // The pdf integrates to one over the sphere
+    let n = 400;
+    let mut integral = 0f64;
+    for i in 0..n {
+        for j in 0..n {
+            let (u, v) = ((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64);
+            let solid_angle = 2f64 * PI * PI * (v * PI).sin() / (n * n) as f64;
+            integral += map.pdf(map.to_direction(u, v)) * solid_angle;
+        }
+    }
+    assert!((integral - 1f64).abs() < 1e-2);
+}
@@ -0,0 +1,32 @@
+pub mod map;
+pub mod sky;
+
+use crate::primitives::vec::{Color, Vector};
+
+/// The light arriving from infinitely far away, seen by every ray that leaves the scene.
+///
+/// Environments that can be importance sampled let integrators aim rays at their bright parts.
+pub trait Environment: Sync + Send {
+    /// The radiance arriving from the unit vector `direction`
+    fn radiance(&self, direction: Vector) -> Color;
+
+    /// Maps two uniform random numbers to a unit direction towards the environment.
+    ///
+    /// Returns the direction, the radiance arriving from it and its probability density per solid
+    /// angle, or `None` if the environment does not support importance sampling.
+    fn sample(&self, _u1: f64, _u2: f64) -> Option<(Vector, Color, f64)> {
+        None
+    }
+
+    /// The probability density, per solid angle, with which `sample` returns the unit vector `direction`
+    fn pdf(&self, _direction: Vector) -> f64 {
+        0f64
+    }
+}
+
+/// A uniform sky
+impl Environment for Color {
+    fn radiance(&self, _direction: Vector) -> Color {
+        *self
+    }
+}
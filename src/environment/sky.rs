@@ -0,0 +1,261 @@
+use crate::environment::Environment;
+use crate::primitives::vec::{Color, Vector};
+
+use std::f64::consts::PI;
+
+/// The luminance of the sun outside of the atmosphere in kcd/m²
+const EXTRATERRESTRIAL_SUN_LUMINANCE: f64 = 1.6e6;
+/// The wavelengths in micrometers that the red, green and blue channels are attenuated at
+const CHANNEL_WAVELENGTHS: [f64; 3] = [0.68, 0.55, 0.44];
+
+/// The Preetham et al. daylight model: a clear sky lit by the sun, with a sun disk that is
+/// importance sampled together with the sky.
+///
+/// The sun direction is given by its `elevation` above the horizon and its `azimuth`, both in
+/// radians. The azimuth is measured from -z towards +x, and +y is up. `turbidity` describes the
+/// haze, from 2 for a very clear to about 10 for a hazy sky. Below the horizon the sky is black.
+pub struct PhysicalSky {
+    sun_direction: Vector,
+    /// The Perez coefficients A to E for the luminance Y and the chromaticities x and y
+    perez: [[f64; 5]; 3],
+    /// The zenith values of Y, x and y, divided by the Perez function towards the zenith
+    zenith: [f64; 3],
+    sun_color: Color,
+    sun_radius: f64,
+    intensity: f64,
+}
+
+impl PhysicalSky {
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        let t = turbidity;
+        let theta_sun = PI / 2f64 - elevation.clamp(0f64, PI / 2f64);
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4f64 / 9f64 - t / 120f64) * (PI - 2f64 * theta_sun);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let chromaticity = |m: [[f64; 4]; 3]| {
+            let thetas = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1f64];
+            let turbidities = [t * t, t, 1f64];
+            (0..3)
+                .map(|i| turbidities[i] * (0..4).map(|j| m[i][j] * thetas[j]).sum::<f64>())
+                .sum::<f64>()
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0f64],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0f64],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+        let zenith = [zenith_luminance.max(0f64), zenith_x, zenith_y];
+        let normalized =
+            std::array::from_fn(|i| zenith[i] / perez_function(&perez[i], 0f64, theta_sun));
+
+        Self {
+            sun_direction: Vector::new(
+                elevation.cos() * azimuth.sin(),
+                elevation.sin(),
+                -elevation.cos() * azimuth.cos(),
+            ),
+            perez: perez,
+            zenith: normalized,
+            sun_color: sun_transmittance(theta_sun, turbidity) * EXTRATERRESTRIAL_SUN_LUMINANCE,
+            sun_radius: 0.00465,
+            intensity: 0.1,
+        }
+    }
+
+    /// Scales sky and sun, whose radiance is in kcd/m² times the intensity. Defaults to 0.1.
+    pub fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity;
+    }
+
+    /// The angular radius of the sun disk in radians. Larger suns give softer shadows.
+    pub fn set_sun_radius(&mut self, sun_radius: f64) {
+        self.sun_radius = sun_radius;
+    }
+
+    pub fn sun_direction(&self) -> Vector {
+        self.sun_direction
+    }
+
+    fn sun_visible(&self) -> bool {
+        self.sun_direction.y() > 0f64
+    }
+
+    fn cos_sun_radius(&self) -> f64 {
+        self.sun_radius.cos()
+    }
+
+    fn sky(&self, direction: Vector) -> Color {
+        let cos_theta = direction.y();
+        if cos_theta <= 0f64 {
+            return Color::BLACK;
+        }
+        let theta = cos_theta.min(1f64).acos();
+        let gamma = direction.dot(&self.sun_direction).clamp(-1f64, 1f64).acos();
+        let [luminance, x, y] =
+            std::array::from_fn(|i| self.zenith[i] * perez_function(&self.perez[i], theta, gamma));
+        xy_luminance_to_rgb(luminance, x, y)
+    }
+}
+
+/// The relative brightness of the sky at zenith angle `theta` and angle `gamma` from the sun
+fn perez_function(coefficients: &[f64; 5], theta: f64, gamma: f64) -> f64 {
+    let [a, b, c, d, e] = *coefficients;
+    (1f64 + a * (b / theta.cos().max(1e-3)).exp())
+        * (1f64 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+/// Converts the chromaticity (x, y) and luminance Y to linear sRGB
+fn xy_luminance_to_rgb(luminance: f64, x: f64, y: f64) -> Color {
+    if y <= 0f64 {
+        return Color::BLACK;
+    }
+    let big_x = x / y * luminance;
+    let big_z = (1f64 - x - y) / y * luminance;
+    Color::from_linear(
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0f64),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0f64),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0f64),
+    )
+}
+
+/// The fraction of sunlight that passes through the atmosphere by Rayleigh and aerosol scattering
+fn sun_transmittance(theta_sun: f64, turbidity: f64) -> Color {
+    // The relative optical air mass (Kasten and Young)
+    let degrees = theta_sun.to_degrees().min(93f64);
+    let air_mass = 1f64 / (theta_sun.cos().max(0f64) + 0.15 * (93.885 - degrees).powf(-1.253));
+    // Ångström's turbidity formula with a wavelength exponent of 1.3
+    let beta = (0.04608 * turbidity - 0.04586).max(0f64);
+    let channel = |wavelength: f64| {
+        let rayleigh = 0.008735 * wavelength.powf(-4.08);
+        let aerosol = beta * wavelength.powf(-1.3);
+        (-air_mass * (rayleigh + aerosol)).exp()
+    };
+    Color::from_linear(
+        channel(CHANNEL_WAVELENGTHS[0]),
+        channel(CHANNEL_WAVELENGTHS[1]),
+        channel(CHANNEL_WAVELENGTHS[2]),
+    )
+}
+
+impl Environment for PhysicalSky {
+    fn radiance(&self, direction: Vector) -> Color {
+        let sky = self.sky(direction);
+        let sun =
+            if self.sun_visible() && direction.dot(&self.sun_direction) >= self.cos_sun_radius() {
+                self.sun_color
+            } else {
+                Color::BLACK
+            };
+        (sky + sun) * self.intensity
+    }
+
+    /// Samples the sun disk and the upper hemisphere half of the time each
+    fn sample(&self, u1: f64, u2: f64) -> Option<(Vector, Color, f64)> {
+        let direction = if self.sun_visible() && u1 < 0.5 {
+            // Uniformly within the cone of the sun disk
+            let u1 = u1 * 2f64;
+            let cos_theta = 1f64 - u1 * (1f64 - self.cos_sun_radius());
+            let sin_theta = (1f64 - cos_theta * cos_theta).max(0f64).sqrt();
+            let (sin_phi, cos_phi) = (2f64 * PI * u2).sin_cos();
+            let (tangent, bitangent) = self.sun_direction.orthonormal_basis();
+            (tangent * (sin_theta * cos_phi))
+                + (bitangent * (sin_theta * sin_phi))
+                + (self.sun_direction * cos_theta)
+        } else {
+            // Uniformly on the upper hemisphere
+            let u1 = if self.sun_visible() {
+                u1 * 2f64 - 1f64
+            } else {
+                u1
+            };
+            let sin_theta = (1f64 - u1 * u1).max(0f64).sqrt();
+            let (sin_phi, cos_phi) = (2f64 * PI * u2).sin_cos();
+            Vector::new(sin_theta * cos_phi, u1, sin_theta * sin_phi)
+        };
+        let pdf = self.pdf(direction);
+        if pdf == 0f64 {
+            None
+        } else {
+            Some((direction, self.radiance(direction), pdf))
+        }
+    }
+
+    fn pdf(&self, direction: Vector) -> f64 {
+        let hemisphere = if direction.y() > 0f64 {
+            1f64 / (2f64 * PI)
+        } else {
+            0f64
+        };
+        if !self.sun_visible() {
+            return hemisphere;
+        }
+        let cone = if direction.dot(&self.sun_direction) >= self.cos_sun_radius() {
+            1f64 / (2f64 * PI * (1f64 - self.cos_sun_radius()))
+        } else {
+            0f64
+        };
+        0.5 * cone + 0.5 * hemisphere
+    }
+}
+
+#[test]
+fn test_physical_sky() {
+    let sky = PhysicalSky::new(0.5, 1f64, 3f64);
+
+    // Blue sky away from the sun, a brighter horizon and a bright, yellowish sun
+    let zenith = sky.radiance(Vector::new(0f64, 1f64, 0f64));
+    assert!(zenith.b() > zenith.r());
+    let away = Vector::new(-1f64, 0.05, 0f64).normalize();
+    assert!(sky.radiance(away).luminance() > 0f64);
+    assert_eq!(sky.radiance(Vector::new(0f64, -1f64, 0f64)), Color::BLACK);
+    let sun = sky.radiance(sky.sun_direction());
+    assert!(sun.luminance() > 1000f64 * zenith.luminance());
+    assert!(sun.r() > sun.b());
+
+    // A lower sun is redder
+    let sunset = PhysicalSky::new(0.02, 1f64, 3f64);
+    let sunset_sun = sunset.radiance(sunset.sun_direction());
+    assert!(sunset_sun.b() / sunset_sun.r() < sun.b() / sun.r());
+
+    // Samples hit the sun about half of the time and agree with the pdf
+    let mut in_sun = 0;
+    for i in 0..100 {
+        let (u1, u2) = ((i as f64 + 0.5) / 100f64, (i * 37 % 100) as f64 / 100f64);
+        let (direction, radiance, pdf) = sky.sample(u1, u2).unwrap();
+        assert!((direction.length() - 1f64).abs() < 1e-9);
+        assert!((pdf - sky.pdf(direction)).abs() < 1e-9 * pdf);
+        assert_eq!(radiance, sky.radiance(direction));
+        if direction.dot(&sky.sun_direction()) >= sky.cos_sun_radius() {
+            in_sun += 1;
+        }
+    }
+    assert!((50..=52).contains(&in_sun));
+}
@@ -0,0 +1,593 @@
+use crate::materials::Diffuse;
+use crate::media::HenyeyGreenstein;
+use crate::objects::lights::{emission_pdf, emitted, sample_emission};
+use crate::objects::scene::{PathState, Scene, EPSILON};
+use crate::objects::Object;
+use crate::primitives::distribution::power_heuristic;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::{Color, Vector};
+use crate::primitives::sampler;
+
+use rand::Rng;
+
+/// How close to the end of a connection a surface may lie without blocking it, in ray parameters
+const CONNECTION_EPSILON: f64 = 1e-6;
+
+/// Bidirectional path tracing, which connects every vertex of a path from the camera with every
+/// vertex of a path from a light and weights the connections by multiple importance sampling.
+///
+/// Light paths start at emissive objects that support surface sampling, like spheres and disks,
+/// and light that reaches the camera directly from them is splatted onto the film. This finds
+/// caustics, e.g. light focused by glass onto a diffuse floor, that paths from the camera rarely hit.
+///
+/// Only the Lambertian parts of materials and the phase functions of media are connected, other
+/// lobes like mirrors and glass are only followed. The environment is lit as by
+/// [`Scene::trace_ray`], and lights inside of closed objects with media are not supported.
+pub struct Bidirectional<'a> {
+    scene: &'a Scene<'a>,
+}
+
+/// How light is scattered at a vertex
+#[derive(Clone, Copy)]
+enum Scattering {
+    Camera,
+    /// A point on an emissive surface that a light path starts at
+    Light,
+    /// A surface with its Lambertian lobe, if it has one that can be connected
+    Surface(Option<Diffuse>),
+    /// A point in a medium, with the direction that the path arrived along
+    Medium(HenyeyGreenstein, Vector),
+}
+
+#[derive(Clone)]
+struct Vertex<'s> {
+    scattering: Scattering,
+    position: Vector,
+    /// The geometric normal of surfaces
+    normal: Option<Vector>,
+    object: Option<&'s dyn Object>,
+    /// The radiance that a surface emits
+    emission: Color,
+    /// The weight of the subpath up to the vertex, the media around it and the color channels
+    state: PathState<'s>,
+    /// The probability densities, per area or volume, of sampling the vertex from the previous
+    /// vertex of its subpath and from the next one
+    pdf_forward: f64,
+    pdf_reverse: f64,
+    /// Whether the subpath left the vertex with a lobe that cannot be evaluated, like a mirror
+    delta: bool,
+    /// Whether the subpath only carries its hero wavelength since a dispersive surface
+    dispersed: bool,
+}
+
+impl<'s> Vertex<'s> {
+    fn new(scattering: Scattering, position: Vector, state: PathState<'s>, dispersed: bool) -> Self {
+        Self {
+            scattering: scattering,
+            position: position,
+            normal: None,
+            object: None,
+            emission: Color::BLACK,
+            state: state,
+            pdf_forward: 0f64,
+            pdf_reverse: 0f64,
+            delta: false,
+            dispersed: dispersed,
+        }
+    }
+
+    fn beta(&self) -> Color {
+        self.state.throughput
+    }
+
+    fn is_connectable(&self) -> bool {
+        !matches!(self.scattering, Scattering::Surface(None))
+    }
+
+    /// The scattering function times the cosine, or the emitted radiance of lights times the
+    /// cosine, towards the unit vector `direction`
+    fn eval(&self, direction: Vector) -> Color {
+        match (self.scattering, self.normal) {
+            (Scattering::Light, Some(normal)) => self.emission * normal.dot(&direction).abs(),
+            (Scattering::Surface(Some(diffuse)), Some(normal)) => {
+                // The light has to leave on the side of the lobe, also with respect to the geometry
+                if normal.dot(&direction) * normal.dot(&diffuse.normal) <= 0f64 {
+                    Color::BLACK
+                } else {
+                    self.state.channels.reflectance(diffuse.eval(direction))
+                }
+            }
+            (Scattering::Medium(phase, incoming), _) => Color::WHITE * phase.pdf(incoming, direction),
+            _ => Color::BLACK,
+        }
+    }
+
+    /// The probability density, per area or volume, of sampling `next` from this vertex after
+    /// arriving from `previous`
+    fn pdf(&self, scene: &Scene, previous: Option<&Vertex>, next: &Vertex) -> f64 {
+        let direction = (next.position - self.position).normalize();
+        let pdf = match self.scattering {
+            Scattering::Camera => scene.camera.project(next.position).map_or(0f64, |projection| projection.pdf),
+            Scattering::Light => return self.emission_pdf(next),
+            Scattering::Surface(diffuse) => diffuse.map_or(0f64, |diffuse| diffuse.pdf(direction)),
+            Scattering::Medium(phase, incoming) => {
+                let incoming = previous.map_or(incoming, |previous| (self.position - previous.position).normalize());
+                phase.pdf(incoming, direction)
+            }
+        };
+        self.area_density(pdf, next)
+    }
+
+    /// The probability density, per area, of a light path starting at this emissive surface
+    /// reaching `next`
+    fn emission_pdf(&self, next: &Vertex) -> f64 {
+        let normal = match self.normal {
+            Some(normal) => normal,
+            None => return 0f64,
+        };
+        let direction = (next.position - self.position).normalize();
+        self.area_density(emission_pdf(normal, direction), next)
+    }
+
+    /// Converts the probability density per solid angle of sampling the direction towards `next`
+    /// to the density per area, or per volume in media, of sampling `next`
+    fn area_density(&self, pdf: f64, next: &Vertex) -> f64 {
+        let offset = next.position - self.position;
+        let distance_squared = offset.dot(&offset);
+        if distance_squared == 0f64 {
+            return 0f64;
+        }
+        match next.normal {
+            Some(normal) => pdf * normal.dot(&offset).abs() / (distance_squared * distance_squared.sqrt()),
+            None => pdf / distance_squared,
+        }
+    }
+}
+
+/// The probability densities that multiple importance sampling compares for a vertex
+#[derive(Clone, Copy)]
+struct Densities {
+    forward: f64,
+    reverse: f64,
+    delta: bool,
+}
+
+impl Densities {
+    fn of(vertex: &Vertex) -> Self {
+        Self {
+            forward: vertex.pdf_forward,
+            reverse: vertex.pdf_reverse,
+            delta: vertex.delta,
+        }
+    }
+
+    /// The ratio of the squared densities, where zero densities stand for lobes that cannot be evaluated
+    fn ratio(&self) -> f64 {
+        let remap = |pdf: f64| if pdf == 0f64 { 1f64 } else { pdf };
+        (remap(self.reverse) / remap(self.forward)).powi(2)
+    }
+}
+
+impl<'a> Bidirectional<'a> {
+    pub fn new(scene: &'a Scene<'a>) -> Self {
+        Self { scene: scene }
+    }
+
+    /// The linear sRGB radiance arriving through the film coordinates `x` and `y`, with paths of at
+    /// most `max_depth` bounces.
+    ///
+    /// Light that the light path carries to other points of the film is passed to `splat` with its
+    /// film coordinates. A film with one light path per camera sample has to scale the splatted
+    /// light by its number of pixels over the number of samples.
+    pub fn sample(&self, x: f64, y: f64, max_depth: u64, splat: &mut dyn FnMut(f64, f64, Color)) -> Color {
+        let scene = self.scene;
+        let max_depth = max_depth as usize;
+        let channels = scene.sample_channels();
+        let start = PathState {
+            media: Vec::new(),
+            throughput: Color::WHITE,
+            channels: channels,
+        };
+
+        let ray = scene.camera.get_ray(x, y);
+        let mut camera = vec![Vertex::new(Scattering::Camera, ray.origin, start.clone(), false)];
+        let mut radiance = self.random_walk(ray, start.clone(), 0f64, max_depth + 1, &mut camera, true);
+        if camera.len() > 1 {
+            // Light paths can only reach cameras that project points onto their film
+            camera[0].delta = scene.camera.project(camera[1].position).is_none();
+            camera[1].pdf_forward = camera[0].pdf(scene, None, &camera[1]);
+        }
+
+        let mut light = Vec::new();
+        if let Some((vertex, direction, pdf)) = self.sample_light(&start, ray.time) {
+            let mut state = start.clone();
+            state.throughput = vertex.beta() * vertex.eval(direction) / pdf;
+            let light_ray = Ray::new_at_time(vertex.position + direction * EPSILON, direction, ray.time);
+            light.push(vertex);
+            self.random_walk(light_ray, state, pdf, max_depth, &mut light, false);
+        }
+
+        for t in 1..=camera.len() {
+            for s in 0..=light.len() {
+                let depth = s + t - 1;
+                if depth == 0 || depth > max_depth {
+                    continue;
+                }
+                if t == 1 {
+                    if let Some((x, y, value)) = self.connect_camera(&camera, &light, s, ray.time) {
+                        splat(x, y, channels.to_rgb(value));
+                    }
+                } else {
+                    radiance = radiance + self.connect(&camera, &light, s, t, ray.time);
+                }
+            }
+        }
+        channels.to_rgb(radiance)
+    }
+
+    /// Starts a light path on a random point of an emissive object.
+    ///
+    /// Returns the vertex, a direction that light leaves it in and the probability density of the
+    /// direction per solid angle.
+    fn sample_light(&self, start: &PathState<'a>, time: f64) -> Option<(Vertex<'a>, Vector, f64)> {
+        let (vertex, _) = self.sample_light_point(start, time)?;
+        let (direction, pdf) = sample_emission(vertex.normal?);
+        if pdf == 0f64 {
+            return None;
+        }
+        Some((vertex, direction, pdf))
+    }
+
+    /// A random point on an emissive object and its probability density per area
+    fn sample_light_point(&self, start: &PathState<'a>, time: f64) -> Option<(Vertex<'a>, f64)> {
+        let (intersection, pdf) = self.scene.sample_light(time)?;
+        if pdf == 0f64 {
+            return None;
+        }
+        let mut state = start.clone();
+        state.throughput = Color::WHITE / pdf;
+        let mut vertex = Vertex::new(Scattering::Light, intersection.position, state, false);
+        vertex.normal = Some(intersection.normal);
+        vertex.object = Some(intersection.object);
+        vertex.emission = emitted(&intersection, &start.channels);
+        vertex.pdf_forward = pdf;
+        Some((vertex, pdf))
+    }
+
+    /// Extends `path` by following `ray` until it has `max_vertices` vertices, the ray leaves the
+    /// scene or carries no more light.
+    ///
+    /// `pdf` is the probability density per solid angle of the direction of the ray. Paths from the
+    /// `camera` also collect the light of the environment, which is returned.
+    fn random_walk(
+        &self,
+        ray: Ray,
+        state: PathState<'a>,
+        pdf: f64,
+        max_vertices: usize,
+        path: &mut Vec<Vertex<'a>>,
+        camera: bool,
+    ) -> Color {
+        let scene = self.scene;
+        let mut rng = sampler::rng();
+        let (mut ray, mut state, mut pdf) = (ray, state, pdf);
+        let mut dispersed = path.last().is_some_and(|vertex| vertex.dispersed);
+        let mut radiance = Color::BLACK;
+        // The density of the direction of the ray, if it was sampled from a lobe that next event
+        // estimation of the environment competes with
+        let mut scattering_pdf: Option<f64> = None;
+
+        while path.len() < max_vertices {
+            let hit = scene.shoot_ray(&ray);
+
+            if let Some(medium) = scene.current_medium(&state.media) {
+                let t_max = hit.as_ref().map_or(f64::INFINITY, |intersection| intersection.ray_parameter);
+                let (scattered, weight) = medium.sample(&ray, t_max, state.throughput, &state.channels);
+                state.throughput = state.throughput * weight;
+                if let Some(t) = scattered {
+                    let position = ray.point_at_parameter(t);
+                    let incoming = ray.direction.normalize();
+                    let phase = *medium.phase();
+                    let mut vertex = Vertex::new(Scattering::Medium(phase, incoming), position, state.clone(), dispersed);
+                    vertex.pdf_forward = path[path.len() - 1].area_density(pdf, &vertex);
+                    path.push(vertex);
+                    if path.len() == max_vertices || state.throughput == Color::BLACK {
+                        break;
+                    }
+                    if camera {
+                        radiance = radiance
+                            + state.throughput
+                                * scene.sample_environment(position, &state, ray.time, |direction| {
+                                    let pdf = phase.pdf(incoming, direction);
+                                    Some((Color::WHITE * pdf, pdf))
+                                });
+                    }
+                    let direction = phase.sample(incoming, rng.gen(), rng.gen());
+                    pdf = phase.pdf(incoming, direction);
+                    // The phase function is symmetric, so the path is as likely in reverse
+                    let n = path.len();
+                    path[n - 2].pdf_reverse = path[n - 1].area_density(pdf, &path[n - 2]);
+                    scattering_pdf = Some(pdf);
+                    ray = Ray::new_at_time(position, direction, ray.time);
+                    continue;
+                }
+            }
+            if state.throughput == Color::BLACK {
+                break;
+            }
+
+            let intersection = match hit {
+                Some(intersection) => intersection,
+                None => {
+                    if camera {
+                        let direction = ray.direction.normalize();
+                        let light = state.channels.illuminant(scene.environment().radiance(direction));
+                        let weight = match scattering_pdf {
+                            Some(pdf) => power_heuristic(pdf, scene.environment().pdf(direction)),
+                            None => 1f64,
+                        };
+                        radiance = radiance + state.throughput * light * weight;
+                    }
+                    break;
+                }
+            };
+            let material = intersection.object.material();
+            let scatter = material.scatter_channels(&ray, &intersection, &state.channels);
+            let outgoing = scatter.ray.direction;
+            if material.is_boundary() {
+                // Passing the boundary of a medium is not a vertex
+                if let Some(media) = Scene::cross(&state.media, &intersection, ray.direction, outgoing) {
+                    state.media = media;
+                }
+                state.throughput = state.throughput * state.channels.reflectance(scatter.attenuation);
+                ray = Ray::new_at_time(intersection.position + outgoing * EPSILON, outgoing, ray.time);
+                continue;
+            }
+
+            // The Lambertian lobe of a dispersive surface only holds for the hero wavelength
+            let diffuse = if scatter.dispersed { None } else { scatter.diffuse };
+            let mut vertex = Vertex::new(Scattering::Surface(diffuse), intersection.position, state.clone(), dispersed);
+            vertex.normal = Some(intersection.normal);
+            vertex.object = Some(intersection.object);
+            vertex.emission = material.emission(&scatter, &state.channels);
+            vertex.pdf_forward = path[path.len() - 1].area_density(pdf, &vertex);
+            path.push(vertex);
+            if path.len() == max_vertices {
+                break;
+            }
+
+            if camera {
+                if let Some(diffuse) = &diffuse {
+                    radiance = radiance
+                        + state.throughput
+                            * scene.sample_environment(intersection.position, &state, ray.time, |direction| {
+                                let geometric_side = intersection.normal.dot(&direction) * intersection.normal.dot(&diffuse.normal);
+                                if geometric_side <= 0f64 {
+                                    return None;
+                                }
+                                Some((state.channels.reflectance(diffuse.eval(direction)), diffuse.pdf(direction)))
+                            });
+                }
+            }
+
+            let (next_channels, dispersion) = if scatter.dispersed {
+                state.channels.terminate_secondary()
+            } else {
+                (state.channels, Color::WHITE)
+            };
+            let attenuation = state.channels.reflectance(scatter.attenuation) * dispersion;
+            let n = path.len();
+            let incoming = ray.direction.normalize();
+            match diffuse {
+                Some(diffuse) if scatter.sampled_diffuse => {
+                    pdf = diffuse.pdf(outgoing.normalize());
+                    path[n - 2].pdf_reverse = path[n - 1].area_density(diffuse.pdf(-incoming), &path[n - 2]);
+                    scattering_pdf = Some(pdf);
+                }
+                _ => {
+                    pdf = 0f64;
+                    path[n - 2].pdf_reverse = 0f64;
+                    path[n - 1].delta = true;
+                    scattering_pdf = None;
+                }
+            }
+            state.throughput = state.throughput * attenuation;
+            state.channels = next_channels;
+            dispersed = dispersed || scatter.dispersed;
+            if let Some(media) = Scene::cross(&state.media, &intersection, ray.direction, outgoing) {
+                state.media = media;
+            }
+            ray = Ray::new_at_time(intersection.position + outgoing * EPSILON, outgoing, ray.time);
+        }
+        radiance
+    }
+
+    /// The light that the path of `s` light and `t` camera vertices, with `t` at least two, carries
+    /// to the camera
+    fn connect(&self, camera: &[Vertex<'a>], light: &[Vertex<'a>], s: usize, t: usize, time: f64) -> Color {
+        let z = &camera[t - 1];
+        if s == 0 {
+            // The camera path hit an emissive surface by itself
+            if z.emission == Color::BLACK || !matches!(z.scattering, Scattering::Surface(_)) {
+                return Color::BLACK;
+            }
+            return z.beta() * z.emission * self.mis_weight(camera, light, None, s, t);
+        }
+        if !z.is_connectable() {
+            return Color::BLACK;
+        }
+        let sampled;
+        let y = if s == 1 {
+            // Sample a new point on a light instead of reusing the start of the light path
+            sampled = match self.sample_light_point(&z.state, time) {
+                Some((vertex, _)) => vertex,
+                None => return Color::BLACK,
+            };
+            &sampled
+        } else {
+            &light[s - 1]
+        };
+        if !y.is_connectable() {
+            return Color::BLACK;
+        }
+        let offset = y.position - z.position;
+        let distance_squared = offset.dot(&offset);
+        let direction = offset / distance_squared.sqrt();
+        let value = z.beta() * z.eval(direction) * y.eval(-direction) * y.beta() / distance_squared;
+        if value == Color::BLACK {
+            return Color::BLACK;
+        }
+        let transmittance = self.transmittance(z, y.position, time);
+        if transmittance == Color::BLACK {
+            return Color::BLACK;
+        }
+        let sampled = if s == 1 { Some(y) } else { None };
+        value * transmittance * dispersion(z, y) * self.mis_weight(camera, light, sampled, s, t)
+    }
+
+    /// Connects the last of `s` light vertices to the camera.
+    ///
+    /// Returns the film coordinates that the light reaches and its amount.
+    fn connect_camera(&self, camera: &[Vertex<'a>], light: &[Vertex<'a>], s: usize, time: f64) -> Option<(f64, f64, Color)> {
+        let y = &light[s - 1];
+        if !y.is_connectable() {
+            return None;
+        }
+        let projection = self.scene.camera.project(y.position)?;
+        let offset = projection.origin - y.position;
+        let distance_squared = offset.dot(&offset);
+        let direction = offset / distance_squared.sqrt();
+        let value = y.beta() * y.eval(direction) * (projection.pdf / distance_squared);
+        if value == Color::BLACK {
+            return None;
+        }
+        let transmittance = self.transmittance(&camera[0], y.position, time);
+        if transmittance == Color::BLACK {
+            return None;
+        }
+        let weight = self.mis_weight(camera, light, None, s, 1);
+        Some((projection.x, projection.y, value * transmittance * weight))
+    }
+
+    /// The fraction of light that gets from `target` to the vertex
+    fn transmittance(&self, vertex: &Vertex<'a>, target: Vector, time: f64) -> Color {
+        let direction = target - vertex.position;
+        let ray = Ray::new_at_time(vertex.position + direction * EPSILON, direction, time);
+        self.scene.transmittance(&ray, 1f64 - CONNECTION_EPSILON, &vertex.state)
+    }
+
+    /// The power heuristic weight of the path of `s` light and `t` camera vertices against all other
+    /// ways of sampling it, where `sampled` replaces the first light vertex
+    fn mis_weight(&self, camera: &[Vertex<'a>], light: &[Vertex<'a>], sampled: Option<&Vertex<'a>>, s: usize, t: usize) -> f64 {
+        let scene = self.scene;
+        let y = |i: usize| if i == 0 { sampled.unwrap_or(&light[0]) } else { &light[i] };
+        let mut camera_densities: Vec<Densities> = camera[..t].iter().map(Densities::of).collect();
+        let mut light_densities: Vec<Densities> = (0..s).map(|i| Densities::of(y(i))).collect();
+
+        // The vertices at the connection are sampled in reverse from each other
+        let z = &camera[t - 1];
+        camera_densities[t - 1].delta = false;
+        camera_densities[t - 1].reverse = if s > 0 {
+            let previous = if s > 1 { Some(y(s - 2)) } else { None };
+            y(s - 1).pdf(scene, previous, z)
+        } else {
+            let pdf = z.object.map_or(0f64, |object| scene.lights().pdf(object));
+            if pdf == 0f64 {
+                // No light path can start on the surface
+                return 1f64;
+            }
+            pdf
+        };
+        if t > 1 {
+            camera_densities[t - 2].reverse = if s > 0 {
+                z.pdf(scene, Some(y(s - 1)), &camera[t - 2])
+            } else {
+                z.emission_pdf(&camera[t - 2])
+            };
+        }
+        if s > 0 {
+            let previous = if t > 1 { Some(&camera[t - 2]) } else { None };
+            light_densities[s - 1].delta = false;
+            light_densities[s - 1].reverse = z.pdf(scene, previous, y(s - 1));
+        }
+        if s > 1 {
+            light_densities[s - 2].reverse = y(s - 1).pdf(scene, Some(z), y(s - 2));
+        }
+
+        // The ratios of the densities of the other strategies to the density of this one
+        let mut sum = 0f64;
+        let mut ratio = 1f64;
+        for i in (1..t).rev() {
+            ratio *= camera_densities[i].ratio();
+            if !camera_densities[i].delta && !camera_densities[i - 1].delta {
+                sum += ratio;
+            }
+        }
+        let mut ratio = 1f64;
+        for i in (0..s).rev() {
+            ratio *= light_densities[i].ratio();
+            let previous_delta = i > 0 && light_densities[i - 1].delta;
+            if !light_densities[i].delta && !previous_delta {
+                sum += ratio;
+            }
+        }
+        1f64 / (1f64 + sum)
+    }
+}
+
+/// Corrects the weight of a connection between two subpaths that both only carry their hero
+/// wavelength, as each was scaled up for dropping the other wavelengths
+fn dispersion(camera: &Vertex, light: &Vertex) -> f64 {
+    if camera.dispersed && light.dispersed {
+        1f64 / 3f64
+    } else {
+        1f64
+    }
+}
+
+#[test]
+fn test_bidirectional() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::cameras::Camera;
+    use crate::materials::phong::PseudoPhong;
+    use crate::materials::phong_with_refraction::PseudoPhongRefraction;
+    use crate::objects::sphere::Sphere;
+
+    let camera = test_camera();
+    let mut scene = Scene::new(&camera, Color::BLACK, 0.0001);
+    let diffuse = |reflectance: f64| {
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::from_linear(reflectance, reflectance, reflectance), Color::BLACK))
+    };
+    // A floor, a diffuse and a glass sphere below a large light
+    scene.add_object(Box::new(Sphere::new(Vector::new(0f64, -101f64, -3f64), 100f64, diffuse(0.5))));
+    scene.add_object(Box::new(Sphere::new(Vector::new(-0.6, -0.5, -3f64), 0.5, diffuse(0.8))));
+    scene.add_object(Box::new(Sphere::new(
+        Vector::new(0.6, -0.5, -3f64),
+        0.5,
+        Box::new(PseudoPhongRefraction::new(1f64, 0f64, 1.5, Color::WHITE, Color::BLACK)),
+    )));
+    scene.add_object(Box::new(Sphere::new(
+        Vector::new(0f64, 1.5, -3f64),
+        0.7,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::from_linear(2f64, 2f64, 2f64))),
+    )));
+
+    // Averaged over the whole film, splats included, both integrators agree
+    // The path tracer finds the light only by chance, so it needs more samples
+    let integrator = Bidirectional::new(&scene);
+    let samples = 20000;
+    let mut rng = sampler::rng();
+    let mut splatted = Color::BLACK;
+    let mut bidirectional = Color::BLACK;
+    for _ in 0..samples {
+        let (x, y) = (rng.gen::<f64>(), rng.gen::<f64>());
+        bidirectional = bidirectional
+            + integrator.sample(x, y, 5, &mut |_, _, color| splatted = splatted + color);
+    }
+    let bidirectional = (bidirectional + splatted) / samples as f64;
+    let path_traced = (0..samples * 10)
+        .map(|_| scene.trace_ray(&camera.get_ray(rng.gen(), rng.gen()), 5))
+        .fold(Color::BLACK, |sum, color| sum + color)
+        / (samples * 10) as f64;
+    assert!((bidirectional - path_traced).abs().less_than(path_traced * 0.04));
+}
@@ -0,0 +1,202 @@
+use crate::objects::scene::{Scene, EPSILON};
+use crate::primitives::intersection::Intersection;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::{Color, Vector};
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// The most surfaces bounding media that a ray passes before it counts as hitting nothing
+const MAX_BOUNDARIES: usize = 64;
+
+/// A fast, non-physical view of the scene to check geometry and framing, which renderers of single
+/// camera paths render instead of light with [`Integrator::Debug`](crate::renderer::settings::Integrator::Debug):
+///
+/// ```ignore
+/// let settings = RenderSettings {
+///     integrator: Integrator::Debug(DebugView::Normals),
+///     ..RenderSettings::default()
+/// };
+/// ```
+///
+/// All views except the bounce count look at the first surface along the ray that is not just the
+/// boundary of a medium, and render rays that hit nothing black.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugView {
+    /// The shading normal facing the camera, with the axes mapped from [-1, 1] to the color channels
+    Normals,
+    /// The distance to the surface, from white up close to black at `max_distance`
+    Depth { max_distance: f64 },
+    /// The fraction of `samples` cosine distributed rays that leave the surface without hitting
+    /// anything within `distance`
+    AmbientOcclusion { distance: f64, samples: usize },
+    /// The surface parameterization, with u in red and v in green, repeating every unit
+    Uv,
+    /// A false color for every object
+    ObjectId,
+    /// A false color for every type of material
+    MaterialId,
+    /// The number of bounces of a path, from blue without any to red at `max_depth`
+    Bounces { max_depth: u64 },
+}
+
+/// What `view` shows along the ray
+pub fn trace(scene: &Scene, ray: &Ray, view: &DebugView) -> Color {
+    if let DebugView::Bounces { max_depth } = view {
+        return heat(bounces(scene, ray, *max_depth) as f64 / (*max_depth).max(1) as f64);
+    }
+    let intersection = match first_surface(scene, ray) {
+        Some(intersection) => intersection,
+        None => return Color::BLACK,
+    };
+    match view {
+        DebugView::Normals => {
+            let normal = intersection.facing_shading_normal(ray.direction);
+            Color::from_linear(
+                (normal.x() + 1f64) / 2f64,
+                (normal.y() + 1f64) / 2f64,
+                (normal.z() + 1f64) / 2f64,
+            )
+        }
+        DebugView::Depth { max_distance } => {
+            let distance = (intersection.position - ray.origin).length();
+            let brightness = (1f64 - distance / max_distance).max(0f64);
+            Color::from_linear(brightness, brightness, brightness)
+        }
+        DebugView::AmbientOcclusion { distance, samples } => {
+            let visible = ambient_occlusion(scene, ray, &intersection, *distance, *samples);
+            Color::from_linear(visible, visible, visible)
+        }
+        DebugView::Uv => {
+            let (u, v) = intersection.uv;
+            Color::from_linear(u - u.floor(), v - v.floor(), 0f64)
+        }
+        DebugView::ObjectId => match scene.object_index(intersection.object) {
+            Some(index) => false_color(index),
+            // Objects inside of other objects, like the children of a CSG object, have no index
+            None => false_color(intersection.object as *const _ as *const () as usize),
+        },
+        DebugView::MaterialId => false_color(intersection.object.material().name()),
+        DebugView::Bounces { .. } => unreachable!(),
+    }
+}
+
+/// The first surface along the ray, passing the boundaries of media
+fn first_surface<'s>(scene: &'s Scene, ray: &Ray) -> Option<Intersection<'s>> {
+    let mut ray = *ray;
+    for _ in 0..MAX_BOUNDARIES {
+        let intersection = scene.shoot_ray(&ray)?;
+        if !intersection.object.material().is_boundary() {
+            return Some(intersection);
+        }
+        ray = Ray::new_at_time(
+            intersection.position + ray.direction * EPSILON,
+            ray.direction,
+            ray.time,
+        );
+    }
+    None
+}
+
+/// The fraction of cosine distributed rays from the intersection that hit nothing within `distance`
+fn ambient_occlusion(scene: &Scene, ray: &Ray, intersection: &Intersection, distance: f64, samples: usize) -> f64 {
+    let normal = if ray.direction.dot(&intersection.normal) > 0f64 {
+        -intersection.normal
+    } else {
+        intersection.normal
+    };
+    let visible = (0..samples)
+        .filter(|_| {
+            let direction = (normal + Vector::random_on_unit_sphere()).normalize();
+            let occlusion_ray = Ray::new_at_time(intersection.position + direction * EPSILON, direction, ray.time);
+            first_surface(scene, &occlusion_ray)
+                .is_none_or(|occluder| (occluder.position - intersection.position).length() > distance)
+        })
+        .count();
+    visible as f64 / samples.max(1) as f64
+}
+
+/// The number of surfaces that a path scatters at before it leaves the scene, stops carrying light
+/// or reaches `max_depth`. Media are ignored.
+fn bounces(scene: &Scene, ray: &Ray, max_depth: u64) -> u64 {
+    let mut ray = *ray;
+    let mut throughput = Color::WHITE;
+    let mut depth = 0;
+    while depth < max_depth && throughput != Color::BLACK {
+        let intersection = match first_surface(scene, &ray) {
+            Some(intersection) => intersection,
+            None => break,
+        };
+        let scatter = intersection.object.material().scatter(&ray, &intersection);
+        throughput = throughput * scatter.attenuation;
+        depth += 1;
+        let direction = scatter.ray.direction;
+        ray = Ray::new_at_time(scatter.ray.origin + direction * EPSILON, direction, ray.time);
+    }
+    depth
+}
+
+/// A saturated color picked by the hash of `id`, so that neighbouring ids look different
+fn false_color<T: Hash>(id: T) -> Color {
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    let hue = (hasher.finish() % 360) as f64 / 60f64;
+    let ramp = 1f64 - (hue % 2f64 - 1f64).abs();
+    match hue as usize {
+        0 => Color::from_linear(1f64, ramp, 0f64),
+        1 => Color::from_linear(ramp, 1f64, 0f64),
+        2 => Color::from_linear(0f64, 1f64, ramp),
+        3 => Color::from_linear(0f64, ramp, 1f64),
+        4 => Color::from_linear(ramp, 0f64, 1f64),
+        _ => Color::from_linear(1f64, 0f64, ramp),
+    }
+}
+
+/// Blue for 0 over green to red for 1
+fn heat(value: f64) -> Color {
+    let value = value.clamp(0f64, 1f64);
+    Color::from_linear(
+        (2f64 * value - 1f64).max(0f64),
+        1f64 - (2f64 * value - 1f64).abs(),
+        (1f64 - 2f64 * value).max(0f64),
+    )
+}
+
+#[test]
+fn test_debug_views() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::materials::phong::PseudoPhong;
+    use crate::materials::transparent::Transparent;
+    use crate::objects::sphere::Sphere;
+
+    let camera = test_camera();
+    let mut scene = Scene::new(&camera, Color::WHITE, 0.0001);
+    let diffuse = || Box::new(PseudoPhong::new(0f64, 0f64, Color::from_linear(0.5, 0.5, 0.5), Color::BLACK));
+    scene.add_object(Box::new(Sphere::new(Vector::new(0f64, 0f64, -3f64), 1f64, diffuse())));
+    scene.add_object(Box::new(Sphere::new(Vector::new(0f64, 0f64, -6f64), 1f64, diffuse())));
+    scene.add_object(Box::new(Sphere::new(Vector::new(0f64, 0f64, -2f64), 1.5, Box::new(Transparent))));
+
+    // The ray passes the boundary and hits the front of the first sphere head on
+    let ray = Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0f64, 0f64, -2f64));
+    let normals = trace(&scene, &ray, &DebugView::Normals);
+    assert!((normals.b() - 1f64).abs() < 1e-9 && (normals.r() - 0.5).abs() < 1e-9);
+    let depth = trace(&scene, &ray, &DebugView::Depth { max_distance: 4f64 });
+    assert!((depth.r() - 0.5).abs() < 1e-9);
+    let sky = Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0f64, 0f64, 1f64));
+    assert_eq!(trace(&scene, &sky, &DebugView::Normals), Color::BLACK);
+
+    // Nothing occludes the front of the first sphere, but the front of the second one is
+    // occluded by the back of the first one when it is close enough
+    let occlusion = DebugView::AmbientOcclusion { distance: 10f64, samples: 100 };
+    assert_eq!(trace(&scene, &ray, &occlusion).r(), 1f64);
+    let behind = Ray::new(Vector::new(0f64, 0f64, -4.5), Vector::new(0f64, 0f64, -1f64));
+    assert!(trace(&scene, &behind, &occlusion).r() < 1f64);
+
+    // Objects differ in color, but their materials are of the same type
+    assert_ne!(trace(&scene, &ray, &DebugView::ObjectId), trace(&scene, &behind, &DebugView::ObjectId));
+    assert_eq!(trace(&scene, &ray, &DebugView::MaterialId), trace(&scene, &behind, &DebugView::MaterialId));
+
+    // Every path bounces at least once off the first sphere
+    let bounces = trace(&scene, &ray, &DebugView::Bounces { max_depth: 2 });
+    assert!(bounces == heat(0.5) || bounces == heat(1f64));
+}
@@ -0,0 +1,190 @@
+use crate::objects::scene::Scene;
+use crate::primitives::distribution::Distribution1D;
+use crate::primitives::sampler::{self, with_primary_sample, PrimarySample};
+use crate::primitives::vec::Color;
+use crate::renderer::settings::CropWindow;
+
+use rand::Rng;
+use rayon::prelude::*;
+
+/// Primary sample space Metropolis light transport (Kelemen et al.), which explores the paths of
+/// [`Scene::trace_ray`] with Markov chains that mutate the random numbers the paths are sampled with.
+///
+/// Once a chain has found a path that carries light, e.g. through a keyhole or a small gap, small
+/// mutations find similar paths around it, so that light that is hard to reach is sampled as often
+/// as it contributes to the image. Large steps sample independent paths, so that chains don't get
+/// stuck in one bright region.
+///
+/// Chains visit the film proportionally to the luminance of the paths, so their splats have to be
+/// scaled by the mean luminance that [`Metropolis::bootstrap`] estimates.
+pub struct Metropolis<'a> {
+    scene: &'a Scene<'a>,
+    max_depth: u64,
+    large_step_probability: f64,
+    mutation_size: f64,
+    crop: CropWindow,
+}
+
+/// Independent paths that estimate the brightness of the image and that chains start from
+pub struct Bootstrap {
+    /// The mean luminance of paths over primary sample space
+    pub normalization: f64,
+    /// The luminance of the path of every seed
+    distribution: Option<Distribution1D>,
+}
+
+impl Bootstrap {
+    /// Picks the seed of a bootstrap path proportionally to its luminance with the uniform random
+    /// number `u`, or `None` if no path carried any light
+    pub fn sample_seed(&self, u: f64) -> Option<u64> {
+        let distribution = self.distribution.as_ref()?;
+        let (_, _, offset) = distribution.sample(u);
+        Some(offset as u64)
+    }
+}
+
+/// A path sampled from a primary sample, with the point of the film that it starts at
+struct PathSample {
+    x: f64,
+    y: f64,
+    radiance: Color,
+    /// The luminance of the radiance, which chains are distributed proportionally to
+    importance: f64,
+}
+
+impl<'a> Metropolis<'a> {
+    /// `mutation_size` is the standard deviation of the offset that small steps move every random
+    /// number by, and `large_step_probability` the probability of sampling an independent path instead.
+    pub fn new(scene: &'a Scene<'a>, max_depth: u64, large_step_probability: f64, mutation_size: f64) -> Self {
+        Self {
+            scene: scene,
+            max_depth: max_depth,
+            large_step_probability: large_step_probability,
+            mutation_size: mutation_size,
+            crop: CropWindow::FULL,
+        }
+    }
+
+    /// Only samples paths through the window of the film, so that the normalization of the
+    /// bootstrap is the mean luminance within it. Defaults to the whole film.
+    pub fn set_crop_window(&mut self, crop: CropWindow) {
+        self.crop = crop;
+    }
+
+    /// Traces `count` independent paths, one for every seed below `count`
+    pub fn bootstrap(&self, count: usize) -> Bootstrap {
+        let importances: Vec<f64> = (0..count)
+            .into_par_iter()
+            .map(|seed| self.trace(self.primary_sample(seed as u64)).1.importance)
+            .collect();
+        let normalization = importances.iter().sum::<f64>() / count.max(1) as f64;
+        Bootstrap {
+            normalization: normalization,
+            distribution: if normalization > 0f64 {
+                Some(Distribution1D::new(importances))
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Runs a chain for `mutations` mutations, starting at the bootstrap path with `seed`.
+    ///
+    /// Every mutation splats the proposed and the current path onto the point of the film in
+    /// [0, 1]² that they start at, weighted by their probability of being accepted. The splats of
+    /// all chains add up to the image when they are multiplied with the normalization and divided
+    /// by the mean number of mutations per pixel.
+    pub fn run_chain(&self, seed: u64, mutations: usize, splat: &mut dyn FnMut(f64, f64, Color)) {
+        let mut rng = sampler::rng();
+        let (mut sample, mut current) = self.trace(self.primary_sample(seed));
+        if current.importance == 0f64 {
+            return;
+        }
+        for _ in 0..mutations {
+            sample.start_iteration();
+            let (mutated, proposed) = self.trace(sample);
+            sample = mutated;
+
+            let accept = (proposed.importance / current.importance).min(1f64);
+            if accept > 0f64 {
+                splat(proposed.x, proposed.y, proposed.radiance * (accept / proposed.importance));
+            }
+            splat(current.x, current.y, current.radiance * ((1f64 - accept) / current.importance));
+
+            if rng.gen::<f64>() < accept {
+                sample.accept();
+                current = proposed;
+            } else {
+                sample.reject();
+            }
+        }
+    }
+
+    fn primary_sample(&self, seed: u64) -> PrimarySample {
+        PrimarySample::new(seed, self.large_step_probability, self.mutation_size)
+    }
+
+    /// Traces the path of the current coordinates of `sample`, the first two of which pick the
+    /// point on the film within the crop window
+    fn trace(&self, sample: PrimarySample) -> (PrimarySample, PathSample) {
+        with_primary_sample(sample, || {
+            let mut rng = sampler::rng();
+            let (x, y) = self.crop.film_point(rng.gen(), rng.gen());
+            let ray = self.scene.camera.get_ray(x, y);
+            let radiance = self.scene.trace_ray(&ray, self.max_depth);
+            let importance = radiance.luminance();
+            PathSample {
+                x: x,
+                y: y,
+                radiance: radiance,
+                importance: if importance.is_finite() { importance.max(0f64) } else { 0f64 },
+            }
+        })
+    }
+}
+
+#[test]
+fn test_metropolis() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::cameras::Camera;
+    use crate::materials::phong::PseudoPhong;
+    use crate::objects::sphere::Sphere;
+    use crate::primitives::vec::Vector;
+    use rand::thread_rng;
+
+    let camera = test_camera();
+    let mut scene = Scene::new(&camera, Color::from_linear(0.2, 0.2, 0.2), 0.0001);
+    scene.add_object(Box::new(Sphere::new(
+        Vector::new(0f64, -101f64, -3f64),
+        100f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::from_linear(0.5, 0.5, 0.5), Color::BLACK)),
+    )));
+    scene.add_object(Box::new(Sphere::new(
+        Vector::new(0.5, 0f64, -3f64),
+        0.5,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::from_linear(2f64, 2f64, 2f64))),
+    )));
+
+    // The quadrants of the film are as bright as the path tracer finds them
+    let quadrant = |x: f64, y: f64| (x >= 0.5) as usize * 2 + (y >= 0.5) as usize;
+    let samples = 200000;
+    let mut path_traced = [0f64; 4];
+    let mut rng = thread_rng();
+    for _ in 0..samples {
+        let (x, y) = (rng.gen::<f64>(), rng.gen::<f64>());
+        path_traced[quadrant(x, y)] += scene.trace_ray(&camera.get_ray(x, y), 5).r() * 4f64 / samples as f64;
+    }
+
+    let metropolis = Metropolis::new(&scene, 5, 0.3, 0.01);
+    let bootstrap = metropolis.bootstrap(50000);
+    let (chains, mutations) = (50, 4000);
+    let mut splatted = [0f64; 4];
+    for chain in 0..chains {
+        let seed = bootstrap.sample_seed((chain as f64 + rng.gen::<f64>()) / chains as f64).unwrap();
+        metropolis.run_chain(seed, mutations, &mut |x, y, color| splatted[quadrant(x, y)] += color.r());
+    }
+    let scale = bootstrap.normalization * 4f64 / (chains * mutations) as f64;
+    for (metropolis, path_traced) in splatted.iter().zip(path_traced.iter()) {
+        assert!((metropolis * scale - path_traced).abs() < 0.08 * path_traced);
+    }
+}
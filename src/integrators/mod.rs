@@ -0,0 +1,4 @@
+pub mod bidirectional;
+pub mod debug;
+pub mod metropolis;
+pub mod photon;
@@ -0,0 +1,340 @@
+use crate::materials::Diffuse;
+use crate::objects::lights::{emitted, sample_emission};
+use crate::objects::scene::{PathState, Scene, EPSILON};
+use crate::primitives::distribution::power_heuristic;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::{Color, Vector};
+use crate::spectrum::Channels;
+use crate::primitives::sampler;
+
+use rand::Rng;
+use rayon::prelude::*;
+use std::f64::consts::PI;
+
+/// A packet of light that arrived at a surface with a Lambertian lobe
+#[derive(Debug, Clone, Copy)]
+pub struct Photon {
+    pub position: Vector,
+    /// The unit direction that the light travelled along
+    pub direction: Vector,
+    /// The flux that the photon carries, before dividing by the number of emitted photons
+    pub power: Color,
+}
+
+/// A kd-tree over photons to find all photons close to a point.
+///
+/// The photons are stored in a single array, where the median of every range splits it in two.
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+    /// The axis that every photon splits its range at, by its index
+    axes: Vec<u8>,
+}
+
+impl PhotonMap {
+    pub fn new(mut photons: Vec<Photon>) -> Self {
+        let mut axes = vec![0u8; photons.len()];
+        build(&mut photons, &mut axes);
+        Self {
+            photons: photons,
+            axes: axes,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    /// Calls `f` with every photon that lies within `radius` of `position`
+    pub fn for_each_within<F>(&self, position: Vector, radius: f64, mut f: F)
+    where
+        F: FnMut(&Photon),
+    {
+        self.visit(0, self.photons.len(), position, radius, &mut f);
+    }
+
+    fn visit<F>(&self, start: usize, end: usize, position: Vector, radius: f64, f: &mut F)
+    where
+        F: FnMut(&Photon),
+    {
+        if start >= end {
+            return;
+        }
+        let median = start + (end - start) / 2;
+        let photon = &self.photons[median];
+        let offset = photon.position - position;
+        if offset.dot(&offset) <= radius * radius {
+            f(photon);
+        }
+        let axis = self.axes[median] as usize;
+        let distance = position.axis(axis) - photon.position.axis(axis);
+        if distance <= radius {
+            self.visit(start, median, position, radius, f);
+        }
+        if distance >= -radius {
+            self.visit(median + 1, end, position, radius, f);
+        }
+    }
+}
+
+/// Sorts the photons into a kd-tree, splitting along the axis in which they spread the most
+fn build(photons: &mut [Photon], axes: &mut [u8]) {
+    if photons.len() <= 1 {
+        return;
+    }
+    let (min, max) = photons.iter().fold(
+        ([f64::INFINITY; 3], [f64::NEG_INFINITY; 3]),
+        |(mut min, mut max), photon| {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(photon.position.axis(axis));
+                max[axis] = max[axis].max(photon.position.axis(axis));
+            }
+            (min, max)
+        },
+    );
+    let axis = (0..3)
+        .max_by(|a, b| (max[*a] - min[*a]).total_cmp(&(max[*b] - min[*b])))
+        .unwrap_or(0);
+    let median = photons.len() / 2;
+    photons.select_nth_unstable_by(median, |a, b| a.position.axis(axis).total_cmp(&b.position.axis(axis)));
+    axes[median] = axis as u8;
+    let (below, above) = photons.split_at_mut(median);
+    let (below_axes, above_axes) = axes.split_at_mut(median);
+    build(below, below_axes);
+    build(&mut above[1..], &mut above_axes[1..]);
+}
+
+/// Emits `count` photons from the emissive objects of the scene and follows them for at most
+/// `max_depth` bounces, storing them wherever they hit a surface with a Lambertian lobe.
+///
+/// Photons carry light in RGB, also in spectral scenes, and pass through media unchanged. With a
+/// `seed`, every photon draws its random numbers from its own stream of it.
+pub fn trace_photons(scene: &Scene, count: usize, max_depth: u64, seed: Option<u64>) -> Vec<Photon> {
+    (0..count)
+        .into_par_iter()
+        .flat_map(|index| {
+            let seed = seed.map(|seed| sampler::stream_seed(seed, index as u64));
+            sampler::with_seed(seed, || trace_photon(scene, max_depth))
+        })
+        .collect()
+}
+
+fn trace_photon(scene: &Scene, max_depth: u64) -> Vec<Photon> {
+    let mut photons = Vec::new();
+    // Photons are emitted at random points in time while the shutter is open, like camera rays
+    let time = scene.camera.shutter().sample();
+    let (light, pdf) = match scene.sample_light(time) {
+        Some(light) => light,
+        None => return photons,
+    };
+    let (direction, direction_pdf) = sample_emission(light.normal);
+    if pdf == 0f64 || direction_pdf == 0f64 {
+        return photons;
+    }
+    let channels = Channels::Rgb;
+    let mut power = emitted(&light, &channels) * (light.normal.dot(&direction).abs() / (pdf * direction_pdf));
+    let mut ray = Ray::new_at_time(light.position + direction * EPSILON, direction, time);
+    let mut depth = 0;
+    while depth < max_depth && power != Color::BLACK {
+        let intersection = match scene.shoot_ray(&ray) {
+            Some(intersection) => intersection,
+            None => break,
+        };
+        let material = intersection.object.material();
+        let scatter = material.scatter_channels(&ray, &intersection, &channels);
+        if !material.is_boundary() {
+            if scatter.diffuse.is_some_and(|diffuse| diffuse.weight > 0f64) {
+                photons.push(Photon {
+                    position: intersection.position,
+                    direction: ray.direction.normalize(),
+                    power: power,
+                });
+            }
+            depth += 1;
+        }
+        power = power * scatter.attenuation;
+        let outgoing = scatter.ray.direction;
+        ray = Ray::new_at_time(intersection.position + outgoing * EPSILON, outgoing, time);
+    }
+    photons
+}
+
+/// Where a path from the camera reached a Lambertian lobe, whose light is estimated from nearby photons
+#[derive(Debug, Clone, Copy)]
+pub struct VisiblePoint {
+    pub position: Vector,
+    /// The Lambertian lobe, with the probability of picking it divided out
+    pub diffuse: Diffuse,
+    /// The weight of the path from the camera
+    pub throughput: Color,
+}
+
+impl VisiblePoint {
+    /// The number of photons within `radius` that arrived on the side of the lobe and their flux,
+    /// reflected towards the camera and weighted by the throughput
+    pub fn gather(&self, map: &PhotonMap, radius: f64) -> (usize, Color) {
+        let mut count = 0;
+        let mut flux = Color::BLACK;
+        let brdf = self.throughput * self.diffuse.reflectance / PI;
+        map.for_each_within(self.position, radius, |photon| {
+            if photon.direction.dot(&self.diffuse.normal) < 0f64 {
+                count += 1;
+                flux = flux + brdf * photon.power;
+            }
+        });
+        (count, flux)
+    }
+}
+
+/// Follows a path from the camera through mirrors and glass to the first surface where its
+/// Lambertian lobe is sampled.
+///
+/// Returns the light of emissive surfaces and of the environment along the way, including the
+/// environment directly lighting the visible point, and the visible point, if any.
+pub fn find_visible_point(scene: &Scene, ray: &Ray, max_depth: u64) -> (Color, Option<VisiblePoint>) {
+    let channels = Channels::Rgb;
+    let mut ray = *ray;
+    let mut throughput = Color::WHITE;
+    let mut radiance = Color::BLACK;
+    let mut depth = 0;
+    while depth < max_depth {
+        let intersection = match scene.shoot_ray(&ray) {
+            Some(intersection) => intersection,
+            None => {
+                let light = scene.environment().radiance(ray.direction.normalize());
+                return (radiance + throughput * light, None);
+            }
+        };
+        let material = intersection.object.material();
+        let scatter = material.scatter_channels(&ray, &intersection, &channels);
+        radiance = radiance + throughput * material.emission(&scatter, &channels);
+        if !material.is_boundary() {
+            depth += 1;
+            match scatter.diffuse {
+                Some(diffuse) if scatter.sampled_diffuse && diffuse.weight > 0f64 => {
+                    let visible = VisiblePoint {
+                        position: intersection.position,
+                        diffuse: Diffuse::new(diffuse.normal, diffuse.reflectance, 1f64),
+                        throughput: throughput,
+                    };
+                    let direct = environment_light(scene, &visible, intersection.normal, ray.time);
+                    return (radiance + throughput * direct, Some(visible));
+                }
+                _ => {}
+            }
+        }
+        throughput = throughput * scatter.attenuation;
+        if throughput == Color::BLACK {
+            break;
+        }
+        let outgoing = scatter.ray.direction;
+        ray = Ray::new_at_time(intersection.position + outgoing * EPSILON, outgoing, ray.time);
+    }
+    (radiance, None)
+}
+
+/// The light of the environment reflected directly by the lobe of a visible point, by sampling both
+/// the lobe and the environment. Photons only carry the light of emissive objects.
+fn environment_light(scene: &Scene, visible: &VisiblePoint, normal: Vector, time: f64) -> Color {
+    let mut rng = sampler::rng();
+    let diffuse = &visible.diffuse;
+    let path = PathState {
+        media: Vec::new(),
+        throughput: Color::WHITE,
+        channels: Channels::Rgb,
+    };
+    let environment = scene.environment();
+    let unoccluded = |direction: Vector| {
+        if normal.dot(&direction) * normal.dot(&diffuse.normal) <= 0f64 {
+            return Color::BLACK;
+        }
+        let ray = Ray::new_at_time(visible.position + direction * EPSILON, direction, time);
+        scene.transmittance(&ray, f64::INFINITY, &path)
+    };
+
+    // Sampling the lobe
+    let direction = (diffuse.normal + Vector::random_on_unit_sphere()).normalize();
+    let lobe_pdf = diffuse.pdf(direction);
+    let mut light = Color::BLACK;
+    if lobe_pdf > 0f64 {
+        let weight = power_heuristic(lobe_pdf, environment.pdf(direction));
+        light = diffuse.reflectance * environment.radiance(direction) * unoccluded(direction) * weight;
+    }
+
+    // Sampling the environment
+    if let Some((direction, radiance, pdf)) = environment.sample(rng.gen(), rng.gen()) {
+        let lobe_pdf = diffuse.pdf(direction);
+        if pdf > 0f64 && lobe_pdf > 0f64 {
+            let weight = power_heuristic(pdf, lobe_pdf);
+            light = light + diffuse.eval(direction) * radiance * unoccluded(direction) * (weight / pdf);
+        }
+    }
+    light
+}
+
+#[test]
+fn test_photon_map() {
+    let mut rng = sampler::rng();
+    let photons: Vec<Photon> = (0..1000)
+        .map(|_| Photon {
+            position: Vector::new(rng.gen(), rng.gen::<f64>() * 2f64, rng.gen::<f64>() * 0.5),
+            direction: Vector::new(0f64, -1f64, 0f64),
+            power: Color::WHITE,
+        })
+        .collect();
+    let map = PhotonMap::new(photons.clone());
+    assert_eq!(map.len(), 1000);
+
+    // The tree finds the same photons as checking all of them
+    for _ in 0..20 {
+        let position = Vector::new(rng.gen(), rng.gen::<f64>() * 2f64, rng.gen::<f64>() * 0.5);
+        let radius = rng.gen::<f64>() * 0.3;
+        let mut found = Vec::new();
+        map.for_each_within(position, radius, |photon| found.push(photon.position));
+        let expected = photons
+            .iter()
+            .filter(|photon| (photon.position - position).length() <= radius)
+            .count();
+        assert_eq!(found.len(), expected);
+        assert!(found.iter().all(|found| (*found - position).length() <= radius));
+    }
+}
+
+#[test]
+fn test_photon_mapping() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::materials::phong::PseudoPhong;
+    use crate::objects::sphere::Sphere;
+
+    let camera = test_camera();
+    let mut scene = Scene::new(&camera, Color::BLACK, 0.0001);
+    scene.add_object(Box::new(Sphere::new(
+        Vector::new(0f64, -101f64, -3f64),
+        100f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::from_linear(0.5, 0.5, 0.5), Color::BLACK)),
+    )));
+    scene.add_object(Box::new(Sphere::new(
+        Vector::new(0f64, 1f64, -3f64),
+        0.5,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::from_linear(2f64, 2f64, 2f64))),
+    )));
+
+    // The floor below the light is as bright as the path tracer finds it
+    let ray = Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0f64, -1f64, -3f64));
+    let (direct, visible_point) = find_visible_point(&scene, &ray, 5);
+    assert_eq!(direct, Color::BLACK);
+    let visible_point = visible_point.unwrap();
+    let photons = 1000000;
+    let map = PhotonMap::new(trace_photons(&scene, photons, 5, None));
+    let radius = 0.2;
+    let (found, flux) = visible_point.gather(&map, radius);
+    assert!(found > 500);
+    let photon_mapped = flux.r() / (photons as f64 * PI * radius * radius);
+
+    let samples = 50000;
+    let path_traced = (0..samples).map(|_| scene.trace_ray(&ray, 5).r()).sum::<f64>() / samples as f64;
+    assert!((photon_mapped - path_traced).abs() < 0.1 * path_traced);
+}
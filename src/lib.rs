@@ -5,4 +5,9 @@ pub mod objects;
 pub mod materials;
 pub mod renderer;
 pub mod io;
-pub mod cameras;
\ No newline at end of file
+pub mod cameras;
+pub mod textures;
+pub mod environment;
+pub mod media;
+pub mod spectrum;
+pub mod integrators;
\ No newline at end of file
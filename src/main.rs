@@ -8,15 +8,18 @@ use raytracer::io::export;
 use raytracer::materials::phong::PseudoPhong;
 use raytracer::materials::phong_with_refraction::PseudoPhongRefraction;
 
-use raytracer::objects::{scene::Scene, sphere::Sphere};
+use raytracer::objects::{plane::Plane, scene::Scene, sphere::Sphere};
 use raytracer::primitives::vec::{Color, Vector};
 use raytracer::renderer::combined_renderer::CombinedRenderer;
 
+use raytracer::renderer::progress::TerminalProgressBar;
 use raytracer::renderer::raw::RawImage;
+use raytracer::renderer::settings::RenderSettings;
 
 use raytracer::renderer::Renderer;
 
 use rayon::prelude::*;
+use std::path::Path;
 
 fn main() {
     (0..1).into_par_iter().for_each(|_focus_factor| {
@@ -102,9 +105,9 @@ fn main() {
             Box::new(material_metal),
         );
 
-        let ground = Sphere::new(
-            Vector::new(0f64, -1001f64, 0f64),
-            1000f64,
+        let ground = Plane::new(
+            Vector::new(0f64, -1f64, 0f64),
+            Vector::new(0f64, 1f64, 0f64),
             Box::new(material_ground),
         );
 
@@ -123,25 +126,31 @@ fn main() {
 
         let _rng = thread_rng();
 
-        //let mut renderer = FixedSamplesRenderer::new(&scene);
-        //let mut std_div_renderer = StdDivRenderer::new(&scene);
-        let mut combined_renderer = CombinedRenderer::new(&scene);
-        //renderer.set_samples_per_pixel(250);
-        //std_div_renderer.set_samples_per_pixel(2000);
-        //combined_renderer.set_min_std_div(scene.sky_color);
-        combined_renderer.first_stage_set_samples_per_pixel(1000);
+        let settings = RenderSettings {
+            samples_per_pixel: 1000,
+            ..RenderSettings::default()
+        };
+        let mut combined_renderer = CombinedRenderer::new(&scene, settings);
+        //combined_renderer.set_min_std_div(scene.sky_color());
         combined_renderer.second_stage_set_samples_per_pixel(3000);
+        combined_renderer.set_progress_observer(Box::new(TerminalProgressBar::new()));
         //combined_renderer.set_min_std_div(Color::new(0.05, 0.05, 0.05));
 
         let mut img = RawImage::new(150 * 20, 100 * 20);
 
-        //renderer.render(&mut img);
-        combined_renderer.render(&mut img);
-        export::gen_ppm(
-            &mut img,
-            format!("/home/max/results/run8/raytraced_image_3.png").to_string(),
-        );
-        //std_div_renderer.render(&mut img);
-        //export::gen_ppm(&mut img, "/home/max/raytraced_image2.png".to_string());
+        let stats = match combined_renderer.render(&mut img) {
+            Ok(stats) => stats,
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
+            }
+        };
+        let filename = "/home/max/results/run8/raytraced_image_3.png".to_string();
+        export::gen_ppm(&mut img, filename.clone());
+
+        println!("{}", stats);
+        if let Err(error) = stats.save_json(&Path::new(&filename).with_extension("json")) {
+            eprintln!("{}", error);
+        }
     });
 }
@@ -0,0 +1,84 @@
+/// The wavelength in nanometers at which the refractive index of glass is usually specified,
+/// the yellow helium d line
+pub const REFERENCE_WAVELENGTH: f64 = 587.56;
+
+/// The refractive index of a dielectric as a function of the wavelength.
+///
+/// The coefficients of the Cauchy and Sellmeier equations take wavelengths in micrometers, as they
+/// are usually tabulated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RefractiveIndex {
+    /// The same index for all wavelengths
+    Constant(f64),
+    /// n = a + b / λ² + c / λ⁴
+    Cauchy { a: f64, b: f64, c: f64 },
+    /// n² = 1 + Σ b_i λ² / (λ² - c_i)
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl RefractiveIndex {
+    /// Schott N-BK7, the most common optical glass
+    pub fn bk7() -> Self {
+        RefractiveIndex::Sellmeier {
+            b: [1.039_612_12, 0.231_792_344, 1.010_469_45],
+            c: [0.006_000_698_67, 0.020_017_914_4, 103.560_653],
+        }
+    }
+
+    /// Fused silica, i.e. quartz glass (Malitson)
+    pub fn fused_silica() -> Self {
+        RefractiveIndex::Sellmeier {
+            b: [0.696_166_3, 0.407_942_6, 0.897_479_4],
+            c: [0.004_679_148_26, 0.013_512_063_1, 97.934_002_5],
+        }
+    }
+
+    /// Diamond, which gets its fire from a strong dispersion (Peter)
+    pub fn diamond() -> Self {
+        RefractiveIndex::Sellmeier {
+            b: [0.3306, 4.3356, 0f64],
+            c: [0.030_625, 0.011_236, 0f64],
+        }
+    }
+
+    /// The index at a wavelength in nanometers
+    pub fn at(&self, wavelength: f64) -> f64 {
+        let micrometers = wavelength / 1000f64;
+        let squared = micrometers * micrometers;
+        match self {
+            RefractiveIndex::Constant(index) => *index,
+            RefractiveIndex::Cauchy { a, b, c } => a + b / squared + c / (squared * squared),
+            RefractiveIndex::Sellmeier { b, c } => (1f64
+                + (0..3)
+                    .map(|i| b[i] * squared / (squared - c[i]))
+                    .sum::<f64>())
+            .sqrt(),
+        }
+    }
+
+    /// The index at the [`REFERENCE_WAVELENGTH`], used when rendering without wavelengths
+    pub fn reference(&self) -> f64 {
+        self.at(REFERENCE_WAVELENGTH)
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, RefractiveIndex::Constant(_))
+    }
+}
+
+#[test]
+fn test_refractive_index() {
+    // Tabulated indices at the d line
+    assert!((RefractiveIndex::bk7().reference() - 1.5168).abs() < 1e-4);
+    assert!((RefractiveIndex::fused_silica().reference() - 1.4585).abs() < 1e-4);
+    assert!((RefractiveIndex::diamond().reference() - 2.417).abs() < 2e-3);
+
+    // Blue light is refracted more strongly than red light
+    for index in [RefractiveIndex::bk7(), RefractiveIndex::diamond()].iter() {
+        assert!(index.at(450f64) > index.at(650f64));
+    }
+    let cauchy = RefractiveIndex::Cauchy { a: 1.5, b: 0.01, c: 0f64 };
+    assert!((cauchy.at(500f64) - 1.54).abs() < 1e-12);
+    assert_eq!(RefractiveIndex::Constant(1.3).at(400f64), 1.3);
+    assert!(!RefractiveIndex::Constant(1.3).is_dispersive());
+}
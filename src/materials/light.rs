@@ -0,0 +1,50 @@
+use crate::materials::{Material, Scatter};
+use crate::primitives::intersection::Intersection;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::{Color, Vector};
+use crate::spectrum::{self, Channels, Spectrum};
+
+/// A black surface that emits light with a given spectrum, like a
+/// [`Blackbody`](crate::spectrum::blackbody::Blackbody) or a measured
+/// [`Tabulated`](crate::spectrum::tabulated::Tabulated) spectrum.
+///
+/// Without spectral rendering the light has the color of its spectrum.
+pub struct Light {
+    spectrum: Box<dyn Spectrum>,
+    intensity: f64,
+    color: Color,
+}
+
+impl Light {
+    pub fn new(spectrum: Box<dyn Spectrum>, intensity: f64) -> Self {
+        let color = spectrum::to_rgb(spectrum.as_ref()) * intensity;
+        Self {
+            spectrum: spectrum,
+            intensity: intensity,
+            color: color,
+        }
+    }
+}
+
+impl Material for Light {
+    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> Scatter {
+        // The path ends here, but it still needs a ray to continue with
+        let direction = intersection.facing_shading_normal(ray.direction) + Vector::random_on_unit_sphere();
+        Scatter::new(
+            Ray::new_at_time(
+                intersection.position,
+                intersection.keep_side(ray.direction, direction, false),
+                ray.time,
+            ),
+            Color::BLACK,
+            self.color,
+        )
+    }
+
+    fn emission(&self, _scatter: &Scatter, channels: &Channels) -> Color {
+        match channels {
+            Channels::Rgb => self.color,
+            Channels::Spectral(_) => channels.spectrum(self.spectrum.as_ref()) * self.intensity,
+        }
+    }
+}
@@ -1,14 +1,121 @@
 pub mod phong;
 pub mod phong_with_refraction;
+pub mod dispersion;
+pub mod light;
+pub mod normal_mapped;
+pub mod subsurface;
+pub mod transparent;
 
 use crate::primitives::intersection::Intersection;
 use crate::primitives::ray::Ray;
-use crate::primitives::vec::Color;
+use crate::primitives::vec::{Color, Vector};
+use crate::spectrum::Channels;
+
+use std::f64::consts::PI;
 
 pub trait Material {
-    
+
     /// Scatter the incoming ray.
-    /// 
-    /// Returns a recursive ray, a reflective (mutiplicative) color and a irradiated (additive) color
-    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> (Ray, Color, Color);
-}
\ No newline at end of file
+    ///
+    /// Returns a recursive ray, a reflective (mutiplicative) color and a irradiated (additive) color,
+    /// plus the Lambertian part of the material for direct light sampling
+    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> Scatter;
+
+    /// Like [`Material::scatter`], for a path whose color channels stand for `channels`.
+    ///
+    /// Materials that scatter different wavelengths in different directions, like dispersive glass,
+    /// override this and mark scatters that only hold for the hero wavelength as
+    /// [`dispersed`](Scatter::dispersed).
+    fn scatter_channels(&self, ray: &Ray, intersection: &Intersection, _channels: &Channels) -> Scatter {
+        self.scatter(ray, intersection)
+    }
+
+    /// Whether the surface only marks the boundary of a medium and lets rays pass unchanged.
+    ///
+    /// Integrators do not count passing such a surface as a bounce, and shadow rays pass it too.
+    fn is_boundary(&self) -> bool {
+        false
+    }
+
+    /// The emitted radiance of a scattered ray in the color channels of the path.
+    ///
+    /// Materials that emit a [`Spectrum`](crate::spectrum::Spectrum) override this to be rendered
+    /// with their exact spectrum instead of one upsampled from the color of the scatter.
+    fn emission(&self, scatter: &Scatter, channels: &Channels) -> Color {
+        channels.illuminant(scatter.emission)
+    }
+
+    /// The name of the type of the material, which debug views tell materials apart by
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// The result of scattering a ray at a surface
+pub struct Scatter {
+    /// The recursive ray
+    pub ray: Ray,
+    /// The reflective (multiplicative) color
+    pub attenuation: Color,
+    /// The irradiated (additive) color
+    pub emission: Color,
+    /// The Lambertian part of the material, if it has one, which lets integrators sample lights directly
+    pub diffuse: Option<Diffuse>,
+    /// Whether the recursive ray was sampled from the Lambertian part
+    pub sampled_diffuse: bool,
+    /// Whether the recursive ray only holds for the hero wavelength of a spectral path
+    pub dispersed: bool,
+}
+
+impl Scatter {
+    pub fn new(ray: Ray, attenuation: Color, emission: Color) -> Self {
+        Self {
+            ray: ray,
+            attenuation: attenuation,
+            emission: emission,
+            diffuse: None,
+            sampled_diffuse: false,
+            dispersed: false,
+        }
+    }
+
+    pub fn with_diffuse(mut self, diffuse: Diffuse, sampled: bool) -> Self {
+        self.diffuse = Some(diffuse);
+        self.sampled_diffuse = sampled;
+        self
+    }
+
+    pub fn dispersed(mut self, dispersed: bool) -> Self {
+        self.dispersed = dispersed;
+        self
+    }
+}
+
+/// A Lambertian lobe that a material picks with probability `weight` and then samples
+/// proportionally to the cosine around `normal`, attenuating by `reflectance`
+#[derive(Debug, Clone, Copy)]
+pub struct Diffuse {
+    pub normal: Vector,
+    pub reflectance: Color,
+    pub weight: f64,
+}
+
+impl Diffuse {
+    pub fn new(normal: Vector, reflectance: Color, weight: f64) -> Self {
+        Self {
+            normal: normal,
+            reflectance: reflectance,
+            weight: weight,
+        }
+    }
+
+    /// The BRDF of the lobe times the cosine towards the unit vector `direction`
+    pub fn eval(&self, direction: Vector) -> Color {
+        self.reflectance * self.pdf(direction)
+    }
+
+    /// The probability density, per solid angle, of sampling the unit vector `direction` from the lobe
+    pub fn pdf(&self, direction: Vector) -> f64 {
+        self.weight * self.normal.dot(&direction).max(0f64) / PI
+    }
+}
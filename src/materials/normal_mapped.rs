@@ -0,0 +1,143 @@
+use crate::materials::{Material, Scatter};
+use crate::primitives::intersection::Intersection;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::{Color, Vector};
+use crate::textures::Texture;
+
+/// The step in uv used to differentiate bump maps
+const BUMP_DELTA: f64 = 1e-3;
+
+/// Fine surface detail that changes the shading normal, but not the geometry
+pub enum SurfaceDetail {
+    /// Tangent space normals encoded as colors in [0, 1]: red along dp/du, green along dp/dv and
+    /// blue along the normal. Image textures should be loaded with [`ImageTexture::open_linear`].
+    ///
+    /// [`ImageTexture::open_linear`]: crate::textures::image_texture::ImageTexture::open_linear
+    NormalMap(Box<dyn Texture<Color>>),
+    /// Heights along the normal, multiplied by `scale` to get world units
+    BumpMap {
+        height: Box<dyn Texture<f64>>,
+        scale: f64,
+    },
+}
+
+/// Renders another material with the shading normal perturbed by a normal or bump map
+pub struct NormalMapped {
+    material: Box<dyn Material>,
+    detail: SurfaceDetail,
+}
+
+impl NormalMapped {
+    pub fn new(material: Box<dyn Material>, detail: SurfaceDetail) -> Self {
+        Self {
+            material: material,
+            detail: detail,
+        }
+    }
+
+    pub fn normal_map(material: Box<dyn Material>, normals: Box<dyn Texture<Color>>) -> Self {
+        Self::new(material, SurfaceDetail::NormalMap(normals))
+    }
+
+    pub fn bump_map(
+        material: Box<dyn Material>,
+        height: Box<dyn Texture<f64>>,
+        scale: f64,
+    ) -> Self {
+        Self::new(material, SurfaceDetail::BumpMap { height, scale })
+    }
+
+    fn shading_normal(&self, intersection: &Intersection) -> Vector {
+        let normal = intersection.shading_normal;
+        let (u, v) = intersection.uv;
+        let position = intersection.position;
+        let perturbed = match &self.detail {
+            SurfaceDetail::NormalMap(normals) => {
+                let tangent =
+                    (intersection.dpdu - normal * normal.dot(&intersection.dpdu)).normalize();
+                let mut bitangent = normal.cross(&tangent);
+                if bitangent.dot(&intersection.dpdv) < 0f64 {
+                    bitangent = -bitangent;
+                }
+                let color = normals.value((u, v), position);
+                (tangent * (color.r() * 2f64 - 1f64))
+                    + (bitangent * (color.g() * 2f64 - 1f64))
+                    + (normal * (color.b() * 2f64 - 1f64))
+            }
+            SurfaceDetail::BumpMap { height, scale } => {
+                // Differentiate the displaced surface p + n * height(u, v)
+                let h = height.value((u, v), position);
+                let h_u = height.value(
+                    (u + BUMP_DELTA, v),
+                    position + intersection.dpdu * BUMP_DELTA,
+                );
+                let h_v = height.value(
+                    (u, v + BUMP_DELTA),
+                    position + intersection.dpdv * BUMP_DELTA,
+                );
+                let dpdu = intersection.dpdu + normal * ((h_u - h) / BUMP_DELTA * scale);
+                let dpdv = intersection.dpdv + normal * ((h_v - h) / BUMP_DELTA * scale);
+                let perturbed = dpdu.cross(&dpdv);
+                if perturbed.dot(&normal) < 0f64 {
+                    -perturbed
+                } else {
+                    perturbed
+                }
+            }
+        };
+        let perturbed = perturbed.normalize();
+        if perturbed.length() > 0f64 {
+            perturbed
+        } else {
+            normal
+        }
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> Scatter {
+        let mut intersection = intersection.clone();
+        intersection.shading_normal = self.shading_normal(&intersection);
+        self.material.scatter(ray, &intersection)
+    }
+}
+
+#[test]
+fn test_shading_normal() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::objects::plane::Plane;
+    use crate::textures::Constant;
+
+    let plane = Plane::new(
+        Vector::new(0f64, 0f64, 0f64),
+        Vector::new(0f64, 0f64, 1f64),
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    );
+    let intersection = Intersection::new(
+        Vector::new(0f64, 0f64, 0f64),
+        &plane,
+        1f64,
+        Vector::new(0f64, 0f64, 1f64),
+    )
+    .with_tangents(Vector::new(1f64, 0f64, 0f64), Vector::new(0f64, 1f64, 0f64));
+    let phong = || Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK));
+
+    // The flat normal map color keeps the normal, others tilt it within the tangent frame
+    let flat = NormalMapped::normal_map(
+        phong(),
+        Box::new(Constant(Color::new(127.5, 127.5, 255f64))),
+    );
+    assert!((flat.shading_normal(&intersection) - intersection.normal).length() < 1e-12);
+    let tilted = NormalMapped::normal_map(
+        phong(),
+        Box::new(Constant(Color::new(255f64, 127.5, 127.5))),
+    );
+    assert!(
+        (tilted.shading_normal(&intersection) - Vector::new(1f64, 0f64, 0f64)).length() < 1e-12
+    );
+
+    // A ramp rising along u with slope one tilts the normal by 45 degrees against u
+    let ramp = NormalMapped::bump_map(phong(), Box::new(|(u, _v): (f64, f64), _p: Vector| u), 1f64);
+    let expected = Vector::new(-1f64, 0f64, 1f64).normalize();
+    assert!((ramp.shading_normal(&intersection) - expected).length() < 1e-9);
+}
@@ -1,20 +1,21 @@
-use crate::materials::Material;
+use crate::materials::{Diffuse, Material, Scatter};
 use crate::primitives::intersection::Intersection;
 use crate::primitives::ray::Ray;
+use crate::primitives::sampler;
 use crate::primitives::vec::{Color, Vector};
+use crate::textures::{Constant, Texture};
 
 #[cfg(test)]
 use crate::objects::sphere::Sphere;
 
-
 use rand::Rng;
 
 pub struct PseudoPhong {
-    spectral_term: f64,
-    spectral_fuzziness: f64,
+    spectral_term: Box<dyn Texture<f64>>,
+    spectral_fuzziness: Box<dyn Texture<f64>>,
 
-    reflection_color: Color,
-    radiation_color: Color,
+    reflection_color: Box<dyn Texture<Color>>,
+    radiation_color: Box<dyn Texture<Color>>,
 }
 impl PseudoPhong {
     pub fn new(
@@ -22,6 +23,21 @@ impl PseudoPhong {
         spectral_fuzziness: f64,
         reflection_color: Color,
         radiation_color: Color,
+    ) -> Self {
+        Self::textured(
+            Box::new(Constant(spectral_term)),
+            Box::new(Constant(spectral_fuzziness)),
+            Box::new(Constant(reflection_color)),
+            Box::new(Constant(radiation_color)),
+        )
+    }
+
+    /// Like [`PseudoPhong::new`], but every parameter may vary over the surface
+    pub fn textured(
+        spectral_term: Box<dyn Texture<f64>>,
+        spectral_fuzziness: Box<dyn Texture<f64>>,
+        reflection_color: Box<dyn Texture<Color>>,
+        radiation_color: Box<dyn Texture<Color>>,
     ) -> Self {
         Self {
             spectral_term: spectral_term,
@@ -33,19 +49,33 @@ impl PseudoPhong {
 }
 
 impl Material for PseudoPhong {
-    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> (Ray, Color, Color) {
-        let mut rng = rand::thread_rng();
-        let direction = if rng.gen::<f64>() < self.spectral_term {
-            ray.direction.reflect(&intersection.normal).normalize()
-                + Vector::random_on_unit_sphere() * self.spectral_fuzziness
+    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> Scatter {
+        let mut rng = sampler::rng();
+        let (uv, position) = (intersection.uv, intersection.position);
+        let normal = intersection.shading_normal;
+        let spectral_term = self.spectral_term.value(uv, position);
+        let spectral = rng.gen::<f64>() < spectral_term;
+        let direction = if spectral {
+            ray.direction.reflect(&normal).normalize()
+                + Vector::random_on_unit_sphere() * self.spectral_fuzziness.value(uv, position)
         } else {
            // println!("Diffuse");
-            intersection.normal + Vector::random_on_unit_sphere()
+            normal + Vector::random_on_unit_sphere()
         };
-        (
-            Ray::new(intersection.position, direction),
-            self.reflection_color,
-            self.radiation_color,
+        let direction = intersection.keep_side(ray.direction, direction, false);
+        let reflection_color = self.reflection_color.value(uv, position);
+        Scatter::new(
+            Ray::new_at_time(intersection.position, direction, ray.time),
+            reflection_color,
+            self.radiation_color.value(uv, position),
+        )
+        .with_diffuse(
+            Diffuse::new(
+                intersection.facing_shading_normal(ray.direction),
+                reflection_color,
+                1f64 - spectral_term.clamp(0f64, 1f64),
+            ),
+            !spectral,
         )
     }
 }
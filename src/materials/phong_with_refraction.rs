@@ -1,7 +1,11 @@
-use crate::materials::Material;
+use crate::materials::dispersion::RefractiveIndex;
+use crate::materials::{Diffuse, Material, Scatter};
 use crate::primitives::intersection::Intersection;
 use crate::primitives::ray::Ray;
+use crate::primitives::sampler;
 use crate::primitives::vec::{Color, Vector};
+use crate::spectrum::Channels;
+use crate::textures::{Constant, Texture};
 
 #[cfg(test)]
 use crate::objects::sphere::Sphere;
@@ -10,13 +14,13 @@ use num::clamp;
 use rand::Rng;
 
 pub struct PseudoPhongRefraction {
-    spectral_term: f64,
-    spectral_fuzziness: f64,
+    spectral_term: Box<dyn Texture<f64>>,
+    spectral_fuzziness: Box<dyn Texture<f64>>,
 
-    refraction_index: f64,
+    refraction_index: RefractiveIndex,
 
-    reflection_color: Color,
-    radiation_color: Color,
+    reflection_color: Box<dyn Texture<Color>>,
+    radiation_color: Box<dyn Texture<Color>>,
 }
 impl PseudoPhongRefraction {
     pub fn new(
@@ -25,26 +29,50 @@ impl PseudoPhongRefraction {
         refraction_index: f64,
         reflection_color: Color,
         radiation_color: Color,
+    ) -> Self {
+        Self::textured(
+            Box::new(Constant(spectral_term)),
+            Box::new(Constant(spectral_fuzziness)),
+            refraction_index,
+            Box::new(Constant(reflection_color)),
+            Box::new(Constant(radiation_color)),
+        )
+    }
+
+    /// Like [`PseudoPhongRefraction::new`], but every parameter except for the refraction index
+    /// may vary over the surface
+    pub fn textured(
+        spectral_term: Box<dyn Texture<f64>>,
+        spectral_fuzziness: Box<dyn Texture<f64>>,
+        refraction_index: f64,
+        reflection_color: Box<dyn Texture<Color>>,
+        radiation_color: Box<dyn Texture<Color>>,
     ) -> Self {
         Self {
             spectral_term: spectral_term,
             spectral_fuzziness: spectral_fuzziness,
-            refraction_index: refraction_index,
+            refraction_index: RefractiveIndex::Constant(refraction_index),
             reflection_color: reflection_color,
             radiation_color: radiation_color,
         }
     }
+
+    /// Replaces the constant refraction index with one that depends on the wavelength, so that
+    /// spectral rendering shows dispersion
+    pub fn set_refractive_index(&mut self, refraction_index: RefractiveIndex) {
+        self.refraction_index = refraction_index;
+    }
 }
 
 impl PseudoPhongRefraction {
-    fn refract(&self, ray: &Ray, intersection: &Intersection) -> Option<Vector> {
-        let cosI = clamp(intersection.normal.dot(&ray.direction), -1., 1.);
-        let normal = intersection.normal;
+    fn refract(&self, ray: &Ray, intersection: &Intersection, refraction_index: f64) -> Option<Vector> {
+        let cosI = clamp(intersection.shading_normal.dot(&ray.direction), -1., 1.);
+        let normal = intersection.shading_normal;
         let (eta, cosI, normal) = if cosI < 0. {
             //The ray is coming from the outside
-            (1. / self.refraction_index, -cosI, normal)
+            (1. / refraction_index, -cosI, normal)
         } else {
-            (self.refraction_index, cosI, -normal)
+            (refraction_index, cosI, -normal)
         };
 
         let k = 1. - eta * eta * (1. - cosI * cosI);
@@ -58,13 +86,13 @@ impl PseudoPhongRefraction {
         }
     }
 
-    fn fresnel(&self, ray: &Ray, intersection: &Intersection) -> f64 {
-        let cosi = clamp(ray.direction.dot(&intersection.normal), -1., 1.);
+    fn fresnel(&self, ray: &Ray, intersection: &Intersection, refraction_index: f64) -> f64 {
+        let cosi = clamp(ray.direction.dot(&intersection.shading_normal), -1., 1.);
 
         let (etai, etat) = if cosi > 0. {
-            (self.refraction_index, 1.)
+            (refraction_index, 1.)
         } else {
-            (1., self.refraction_index)
+            (1., refraction_index)
         };
         // Compute sini using Snell's law
         let sint = etai / etat * 0f64.max(1. - cosi * cosi).sqrt();
@@ -84,47 +112,74 @@ impl PseudoPhongRefraction {
 }
 
 impl Material for PseudoPhongRefraction {
-    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> (Ray, Color, Color) {
-        let mut rng = rand::thread_rng();
+    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> Scatter {
+        self.scatter_channels(ray, intersection, &Channels::Rgb)
+    }
 
-        let cosI = clamp(intersection.normal.dot(&ray.direction), -1., 1.);
-        let normal = intersection.normal;
+    fn scatter_channels(&self, ray: &Ray, intersection: &Intersection, channels: &Channels) -> Scatter {
+        let mut rng = sampler::rng();
+        let refraction_index = match channels.hero() {
+            Some(wavelength) => self.refraction_index.at(wavelength),
+            None => self.refraction_index.reference(),
+        };
+        // Every wavelength takes its own path, so only the hero can follow this one
+        let dispersed = self.refraction_index.is_dispersive() && channels.hero().is_some();
+        let (uv, position) = (intersection.uv, intersection.position);
+
+        let cosI = clamp(intersection.shading_normal.dot(&ray.direction), -1., 1.);
+        let normal = intersection.shading_normal;
         let (eta, cosI, normal) = if cosI < 0. {
             //The ray is coming from the outside
-            (1. / self.refraction_index, -cosI, normal)
+            (1. / refraction_index, -cosI, normal)
         } else {
-            (self.refraction_index, cosI, -normal)
+            (refraction_index, cosI, -normal)
         };
 
         //reflection_chance >= 1 is total internal reflection
-        let reflection_chance = self.fresnel(ray, intersection);
+        let reflection_chance = self.fresnel(ray, intersection, refraction_index);
        // println!("Reflection chance {}", reflection_chance);
         let val = rng.gen::<f64>();
+        let spectral_term = self.spectral_term.value(uv, position);
+        let mut sampled_diffuse = false;
         let direction = if val < reflection_chance {
             //Reflect the ray according to phong
-            if rng.gen::<f64>() < self.spectral_term {
+            if rng.gen::<f64>() < spectral_term {
                 //Spectral reflection
                 
-                ray.direction.reflect(&intersection.normal).normalize()
-                    + Vector::random_on_unit_sphere() * self.spectral_fuzziness
+                let direction = ray.direction.reflect(&intersection.shading_normal).normalize()
+                    + Vector::random_on_unit_sphere() * self.spectral_fuzziness.value(uv, position);
+                intersection.keep_side(ray.direction, direction, false)
 
             } else {
                 // Diffuse reflection
-                intersection.normal + Vector::random_on_unit_sphere()
+                sampled_diffuse = true;
+                let direction = intersection.shading_normal + Vector::random_on_unit_sphere();
+                intersection.keep_side(ray.direction, direction, false)
             }
         } else {
             // Refract the ray according to phong
            // if rng.gen::<f64>() < self.spectral_term {
-                self.refract(ray, intersection).unwrap().normalize()
+                let direction = self.refract(ray, intersection, refraction_index).unwrap().normalize();
+                intersection.keep_side(ray.direction, direction, true)
            // } else {
            //     (-intersection.normal) + Vector::random_on_unit_sphere()
             //}
         };
-        (
-            Ray::new(intersection.position, direction),
-            self.reflection_color,
-            self.radiation_color,
+        let reflection_color = self.reflection_color.value(uv, position);
+        Scatter::new(
+            Ray::new_at_time(intersection.position, direction, ray.time),
+            reflection_color,
+            self.radiation_color.value(uv, position),
         )
+        .with_diffuse(
+            Diffuse::new(
+                intersection.facing_shading_normal(ray.direction),
+                reflection_color,
+                reflection_chance.min(1f64) * (1f64 - spectral_term.clamp(0f64, 1f64)),
+            ),
+            sampled_diffuse,
+        )
+        .dispersed(dispersed)
     }
 }
 
@@ -159,3 +214,40 @@ fn test_scatter() {
     );
     */
 }
+
+#[test]
+fn test_dispersion() {
+    use crate::objects::Object;
+    use crate::spectrum::Wavelengths;
+
+    let mut glass = PseudoPhongRefraction::new(1f64, 0f64, 1.5, Color::WHITE, Color::BLACK);
+    glass.set_refractive_index(RefractiveIndex::diamond());
+    let sphere = Sphere::new(Vector::new(0f64, 0f64, 0f64), 1f64, Box::new(glass));
+    let intersection = Intersection::new(
+        Vector::new(1f64, 0f64, 0f64),
+        &sphere,
+        1f64,
+        Vector::new(1f64, 0f64, 0f64),
+    );
+    let ray = Ray::new(Vector::new(2f64, 1f64, 0f64), Vector::new(-1f64, -1f64, 0f64));
+
+    // The refracted direction for a wavelength, retrying when the ray is reflected instead
+    let refract = |wavelength: f64| loop {
+        let channels = Channels::Spectral(Wavelengths::sample(
+            (wavelength - crate::spectrum::WAVELENGTH_MIN)
+                / (crate::spectrum::WAVELENGTH_MAX - crate::spectrum::WAVELENGTH_MIN),
+        ));
+        let scatter = sphere.material().scatter_channels(&ray, &intersection, &channels);
+        assert!(scatter.dispersed);
+        if scatter.ray.direction.x() < 0f64 {
+            return scatter.ray.direction.normalize();
+        }
+    };
+    // Blue light bends more towards the inward normal than red light
+    let (blue, red) = (refract(450f64), refract(650f64));
+    assert!(blue.y().abs() < red.y().abs());
+    assert!(!sphere
+        .material()
+        .scatter_channels(&ray, &intersection, &Channels::Rgb)
+        .dispersed);
+}
@@ -0,0 +1,144 @@
+use crate::materials::{Material, Scatter};
+use crate::media::homogeneous::Homogeneous;
+use crate::media::HenyeyGreenstein;
+use crate::objects::volume::Volume;
+use crate::objects::Object;
+use crate::primitives::intersection::Intersection;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Color;
+use crate::primitives::sampler;
+
+use rand::Rng;
+
+/// A translucent material like skin, wax or marble, where light enters the object, scatters
+/// around inside of it and leaves somewhere else.
+///
+/// The surface is a smooth dielectric interface. The scattering inside is simulated by a random
+/// walk through the [`medium`](Subsurface::medium), which has to fill the object. Objects built with
+/// [`volume`](Subsurface::volume) are filled with it. Paths take many bounces inside, so render with
+/// a generous maximum depth.
+pub struct Subsurface {
+    albedo: Color,
+    mean_free_path: Color,
+    refraction_index: f64,
+    anisotropy: f64,
+}
+
+impl Subsurface {
+    /// `albedo` is the color of a thick slab of the material and `mean_free_path` the average
+    /// distance, per color channel, that light travels inside before it scatters
+    pub fn new(albedo: Color, mean_free_path: Color, refraction_index: f64) -> Self {
+        Self {
+            albedo: albedo,
+            mean_free_path: mean_free_path,
+            refraction_index: refraction_index,
+            anisotropy: 0f64,
+        }
+    }
+
+    /// The asymmetry of the Henyey-Greenstein phase function inside. Defaults to isotropic scattering.
+    pub fn set_anisotropy(&mut self, anisotropy: f64) {
+        self.anisotropy = anisotropy;
+    }
+
+    /// Builds an object of this material with `object` and fills it with the matching medium.
+    ///
+    /// ```ignore
+    /// let wax = Subsurface::new(Color::WHITE, Color::from_linear(0.2, 0.3, 0.5), 1.4);
+    /// scene.add_object(Box::new(wax.volume(|material| Box::new(Sphere::new(center, 1f64, material)))));
+    /// ```
+    pub fn volume<F>(self, object: F) -> Volume
+    where
+        F: FnOnce(Box<dyn Material>) -> Box<dyn Object>,
+    {
+        let medium = self.medium();
+        Volume::new(object(Box::new(self)), Box::new(medium))
+    }
+
+    /// The medium that objects with this material have to be filled with
+    pub fn medium(&self) -> Homogeneous {
+        let extinction = self.mean_free_path.map(|distance| 1f64 / distance.max(1e-9));
+        let single_scattering = self.albedo.map(single_scattering_albedo);
+        Homogeneous::new(
+            extinction * (Color::WHITE - single_scattering),
+            extinction * single_scattering,
+            HenyeyGreenstein::new(self.anisotropy),
+        )
+    }
+}
+
+/// Inverts the albedo of a semi-infinite slab after any number of scattering events to the albedo
+/// of single scattering events, as fitted by Chiang et al. for random walks
+fn single_scattering_albedo(albedo: f64) -> f64 {
+    let a = albedo.clamp(0f64, 1f64);
+    let term = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+    (1f64 - term * term).clamp(0f64, 1f64)
+}
+
+/// The fraction of unpolarized light that is reflected at a smooth dielectric interface, for the
+/// cosine to the normal on the incoming side and the ratio of the indices of refraction `eta`
+fn fresnel(cos_incoming: f64, eta: f64) -> f64 {
+    let sin_transmitted_squared = eta * eta * (1f64 - cos_incoming * cos_incoming);
+    if sin_transmitted_squared >= 1f64 {
+        return 1f64;
+    }
+    let cos_transmitted = (1f64 - sin_transmitted_squared).sqrt();
+    let parallel = (cos_incoming - eta * cos_transmitted) / (cos_incoming + eta * cos_transmitted);
+    let perpendicular = (eta * cos_incoming - cos_transmitted) / (eta * cos_incoming + cos_transmitted);
+    (parallel * parallel + perpendicular * perpendicular) / 2f64
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> Scatter {
+        let mut rng = sampler::rng();
+        let incoming = ray.direction.normalize();
+        let normal = intersection.facing_shading_normal(ray.direction);
+        let eta = if ray.direction.dot(&intersection.normal) < 0f64 {
+            1f64 / self.refraction_index
+        } else {
+            self.refraction_index
+        };
+        let cos_incoming = (-incoming.dot(&normal)).clamp(0f64, 1f64);
+        let refracted = incoming
+            .refract(&normal, eta)
+            .filter(|_| rng.gen::<f64>() >= fresnel(cos_incoming, eta));
+        let direction = match refracted {
+            Some(direction) => intersection.keep_side(ray.direction, direction, true),
+            None => intersection.keep_side(ray.direction, incoming.reflect(&normal), false),
+        };
+        Scatter::new(
+            Ray::new_at_time(intersection.position, direction, ray.time),
+            Color::WHITE,
+            Color::BLACK,
+        )
+    }
+}
+
+#[test]
+fn test_subsurface() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::objects::scene::Scene;
+    use crate::objects::sphere::Sphere;
+    use crate::primitives::vec::Vector;
+
+    assert!(single_scattering_albedo(0f64) < 1e-4);
+    assert!((single_scattering_albedo(1f64) - 1f64).abs() < 1e-4);
+    assert!(single_scattering_albedo(0.5) > 0.9);
+    assert!((fresnel(1f64, 1f64 / 1.5) - 0.04).abs() < 1e-9);
+    assert_eq!(fresnel(0.1, 1.5), 1f64);
+
+    // A white translucent object in a uniform environment neither gains nor loses light
+    let camera = test_camera();
+    let mut scene = Scene::new(&camera, Color::WHITE, 0.0001);
+    let wax = Subsurface::new(Color::WHITE, Color::from_linear(0.2, 0.3, 0.5), 1.4);
+    scene.add_object(Box::new(
+        wax.volume(|material| Box::new(Sphere::new(Vector::new(0f64, 0f64, -3f64), 1f64, material))),
+    ));
+    let ray = Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0.1, 0.2, -1f64));
+    let samples = 5000;
+    let average = (0..samples)
+        .map(|_| scene.trace_ray(&ray, 500))
+        .fold(Color::BLACK, |sum, color| sum + color)
+        / samples as f64;
+    assert!((average - Color::WHITE).abs().less_than(Color::from_linear(0.05, 0.05, 0.05)));
+}
@@ -0,0 +1,21 @@
+use crate::materials::{Material, Scatter};
+use crate::primitives::intersection::Intersection;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Color;
+
+/// An invisible surface that only marks where a medium begins and ends, e.g. around fog or smoke
+pub struct Transparent;
+
+impl Material for Transparent {
+    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> Scatter {
+        Scatter::new(
+            Ray::new_at_time(intersection.position, ray.direction, ray.time),
+            Color::WHITE,
+            Color::BLACK,
+        )
+    }
+
+    fn is_boundary(&self) -> bool {
+        true
+    }
+}
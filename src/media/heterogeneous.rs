@@ -0,0 +1,294 @@
+use crate::media::{channel_probabilities, expectation, HenyeyGreenstein, Medium};
+use crate::primitives::aabb::Aabb;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::{Color, Vector};
+use crate::spectrum::Channels;
+use crate::textures::Texture;
+use crate::primitives::sampler;
+
+use rand::Rng;
+
+/// The number of cells of the majorant grid along each axis
+const MAJORANT_RESOLUTION: usize = 16;
+
+/// A density that varies in space and scales the coefficients of a [`Heterogeneous`] medium
+pub trait Density: Sync + Send {
+    fn density(&self, position: Vector) -> f64;
+
+    /// An upper bound of the density within `region`. Tighter bounds make rendering faster.
+    fn max_density(&self, region: &Aabb) -> f64;
+
+    /// The box outside of which the density is zero
+    fn bounds(&self) -> Aabb;
+}
+
+/// A density given by a texture, e.g. [`Fbm`](crate::textures::noise::Fbm) noise or a closure,
+/// that is evaluated at the position and zero outside of `bounds`.
+///
+/// `max_density` has to bound the texture everywhere. Baking the texture into a
+/// [`VoxelGrid`](crate::media::voxel::VoxelGrid) gives tighter bounds for sparse densities.
+pub struct Procedural<T> {
+    texture: T,
+    bounds: Aabb,
+    max_density: f64,
+}
+
+impl<T: Texture<f64>> Procedural<T> {
+    pub fn new(texture: T, bounds: Aabb, max_density: f64) -> Self {
+        Self {
+            texture: texture,
+            bounds: bounds,
+            max_density: max_density,
+        }
+    }
+}
+
+impl<T: Texture<f64>> Density for Procedural<T> {
+    fn density(&self, position: Vector) -> f64 {
+        let inside = (0..3).all(|axis| {
+            position.axis(axis) >= self.bounds.min.axis(axis)
+                && position.axis(axis) <= self.bounds.max.axis(axis)
+        });
+        if inside {
+            self.texture.value((0f64, 0f64), position).max(0f64)
+        } else {
+            0f64
+        }
+    }
+
+    fn max_density(&self, _region: &Aabb) -> f64 {
+        self.max_density
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+/// A medium whose coefficients are scaled by a spatially varying density, like clouds or smoke.
+///
+/// Distances are sampled by delta tracking and shadow rays are attenuated by ratio tracking, both
+/// against a coarse grid of upper bounds of the density, so that empty space is skipped quickly.
+pub struct Heterogeneous {
+    density: Box<dyn Density>,
+    absorption: Color,
+    scattering: Color,
+    phase: HenyeyGreenstein,
+    bounds: Aabb,
+    /// The upper bounds of the extinction coefficient per cell, x varying fastest
+    majorants: Vec<f64>,
+}
+
+impl Heterogeneous {
+    /// `absorption` and `scattering` are the coefficients per world unit at a density of one
+    pub fn new(
+        density: Box<dyn Density>,
+        absorption: Color,
+        scattering: Color,
+        phase: HenyeyGreenstein,
+    ) -> Self {
+        let bounds = density.bounds();
+        let cell_size = (bounds.max - bounds.min) / MAJORANT_RESOLUTION as f64;
+        // Spectra upsampled from the coefficients never exceed their largest channel
+        let max_extinction = absorption.max_channel() + scattering.max_channel();
+        let majorants = (0..MAJORANT_RESOLUTION.pow(3))
+            .map(|i| {
+                let cell = Vector::new(
+                    (i % MAJORANT_RESOLUTION) as f64,
+                    (i / MAJORANT_RESOLUTION % MAJORANT_RESOLUTION) as f64,
+                    (i / (MAJORANT_RESOLUTION * MAJORANT_RESOLUTION)) as f64,
+                );
+                let min = bounds.min + cell_size * cell;
+                let region = Aabb::new(min, min + cell_size);
+                density.max_density(&region) * max_extinction
+            })
+            .collect();
+        Self {
+            density: density,
+            absorption: absorption,
+            scattering: scattering,
+            phase: phase,
+            bounds: bounds,
+            majorants: majorants,
+        }
+    }
+
+    /// The cell of the majorant grid that the ray traverses right after the ray parameter `t`,
+    /// its majorant and the ray parameter at which the ray leaves it
+    fn cell(&self, ray: &Ray, t: f64) -> (f64, f64) {
+        let size = self.bounds.max - self.bounds.min;
+        let position = ray.point_at_parameter(t);
+        let mut index = 0;
+        let mut stride = 1;
+        let mut exit = f64::INFINITY;
+        for axis in 0..3 {
+            let direction = ray.direction.axis(axis);
+            let cell_size = size.axis(axis) / MAJORANT_RESOLUTION as f64;
+            let coordinate = (position.axis(axis) - self.bounds.min.axis(axis)) / cell_size;
+            // On a cell face, the ray is in the cell that it moves into
+            let cell = if direction < 0f64 {
+                coordinate.ceil() - 1f64
+            } else {
+                coordinate.floor()
+            }
+            .clamp(0f64, MAJORANT_RESOLUTION as f64 - 1f64);
+            if direction != 0f64 {
+                let face = if direction > 0f64 { cell + 1f64 } else { cell };
+                let face = self.bounds.min.axis(axis) + face * cell_size;
+                exit = exit.min((face - ray.origin.axis(axis)) / direction);
+            }
+            index += cell as usize * stride;
+            stride *= MAJORANT_RESOLUTION;
+        }
+        (self.majorants[index], exit.max(t))
+    }
+
+    /// Calls `collide` with the ray parameter, extinction majorant and position of tentative
+    /// collisions along the ray up to `t_max`, until it returns false.
+    ///
+    /// Collisions are distributed exponentially with the majorant of each cell of the grid.
+    fn track<F>(&self, ray: &Ray, t_max: f64, mut collide: F)
+    where
+        F: FnMut(f64, f64, Vector) -> bool,
+    {
+        let (mut t, end) = match self.bounds.clip(ray, 0f64, t_max) {
+            Some(range) => range,
+            None => return,
+        };
+        let mut rng = sampler::rng();
+        let speed = ray.direction.length();
+        while t < end {
+            let (majorant, exit) = self.cell(ray, t);
+            let exit = exit.min(end);
+            if majorant > 0f64 {
+                let step = -(1f64 - rng.gen::<f64>()).ln() / (majorant * speed);
+                if t + step < exit {
+                    t += step;
+                    if !collide(t, majorant, ray.point_at_parameter(t)) {
+                        return;
+                    }
+                    continue;
+                }
+            }
+            // The sampled distances are memoryless, so the next cell starts afresh
+            if exit <= t {
+                // Guards against a ray that makes no progress due to rounding
+                return;
+            }
+            t = exit;
+        }
+    }
+}
+
+impl Medium for Heterogeneous {
+    fn sample(&self, ray: &Ray, t_max: f64, throughput: Color, channels: &Channels) -> (Option<f64>, Color) {
+        let mut rng = sampler::rng();
+        let probabilities = channel_probabilities(throughput);
+        let (absorption, scattering) = (channels.reflectance(self.absorption), channels.reflectance(self.scattering));
+        let mut weight = Color::WHITE;
+        let mut scattered = None;
+        // Delta tracking: every tentative collision is an absorption, a real scattering or a
+        // null collision, chosen by the coefficients averaged over the channels and weighted by the color ones
+        self.track(ray, t_max, |t, majorant, position| {
+            let density = self.density.density(position);
+            let absorption = absorption * density;
+            let scattering = scattering * density;
+            let null = (absorption + scattering).map(|sigma| majorant - sigma);
+            let p_absorption = expectation(absorption, probabilities) / majorant;
+            let p_scattering = expectation(scattering, probabilities) / majorant;
+            let u = rng.gen::<f64>();
+            if u < p_absorption {
+                weight = Color::BLACK;
+                false
+            } else if u < p_absorption + p_scattering {
+                weight = weight * scattering / (majorant * p_scattering);
+                scattered = Some(t);
+                false
+            } else {
+                let p_null = 1f64 - p_absorption - p_scattering;
+                if p_null <= 0f64 {
+                    weight = Color::BLACK;
+                    return false;
+                }
+                weight = weight * null / (majorant * p_null);
+                true
+            }
+        });
+        (scattered, weight)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_max: f64, channels: &Channels) -> Color {
+        // Ratio tracking: the fraction of null collisions at every tentative collision
+        let extinction = channels.reflectance(self.absorption) + channels.reflectance(self.scattering);
+        let mut transmittance = Color::WHITE;
+        self.track(ray, t_max, |_, majorant, position| {
+            let extinction = extinction * self.density.density(position);
+            transmittance = transmittance * extinction.map(|sigma| 1f64 - sigma / majorant);
+            transmittance.max_channel() > 0f64
+        });
+        transmittance
+    }
+
+    fn phase(&self) -> &HenyeyGreenstein {
+        &self.phase
+    }
+}
+
+#[test]
+fn test_tracking() {
+    use crate::media::homogeneous::beer_lambert;
+    use crate::media::voxel::VoxelGrid;
+
+    let bounds = Aabb::new(Vector::new(-1f64, -1f64, -1f64), Vector::new(1f64, 1f64, 1f64));
+    let absorption = Color::from_linear(0.2, 0.4, 0.6);
+    let scattering = Color::from_linear(0.6, 0.4, 0.2);
+
+    // A constant density behaves like a homogeneous medium inside of the bounds
+    let constant = Heterogeneous::new(
+        Box::new(Procedural::new(|_, _| 1f64, bounds, 1f64)),
+        absorption,
+        scattering,
+        HenyeyGreenstein::isotropic(),
+    );
+    // The ray passes the box from z = 1 to z = -1
+    let ray = Ray::new(Vector::new(0.3, -0.2, 3f64), Vector::new(0f64, 0f64, -0.5));
+    let samples = 20000;
+    let mut transmittance = Color::BLACK;
+    let mut passed = Color::BLACK;
+    let mut scattered = 0;
+    for _ in 0..samples {
+        transmittance = transmittance + constant.transmittance(&ray, 10f64, &Channels::Rgb);
+        match constant.sample(&ray, 10f64, Color::WHITE, &Channels::Rgb) {
+            (None, weight) => passed = passed + weight,
+            (Some(t), _) => {
+                assert!((4f64..=8f64).contains(&t));
+                scattered += 1;
+            }
+        }
+    }
+    let expected = beer_lambert(absorption + scattering, 2f64);
+    let tolerance = Color::from_linear(0.02, 0.02, 0.02);
+    assert!((transmittance / samples as f64 - expected).abs().less_than(tolerance));
+    assert!((passed / samples as f64 - expected).abs().less_than(tolerance));
+    assert!(scattered > 0);
+
+    // A voxel grid that is empty in one half is transparent there
+    let half = Heterogeneous::new(
+        Box::new(VoxelGrid::from_fn(4, 4, 4, bounds, |position| {
+            if position.x() > 0f64 { 2f64 } else { 0f64 }
+        })),
+        absorption,
+        scattering,
+        HenyeyGreenstein::isotropic(),
+    );
+    let empty = Ray::new(Vector::new(-0.8, 0f64, 3f64), Vector::new(0f64, 0f64, -1f64));
+    assert_eq!(half.transmittance(&empty, 10f64, &Channels::Rgb), Color::WHITE);
+    assert_eq!(half.sample(&empty, 10f64, Color::WHITE, &Channels::Rgb), (None, Color::WHITE));
+    let dense = Ray::new(Vector::new(0.8, 0f64, 3f64), Vector::new(0f64, 0f64, -1f64));
+    let average = (0..samples)
+        .map(|_| half.transmittance(&dense, 10f64, &Channels::Rgb))
+        .fold(Color::BLACK, |sum, value| sum + value)
+        / samples as f64;
+    let expected = beer_lambert((absorption + scattering) * 2f64, 2f64);
+    assert!((average - expected).abs().less_than(tolerance));
+}
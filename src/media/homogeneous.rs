@@ -0,0 +1,129 @@
+use crate::media::{channel_probabilities, expectation, pick_channel, HenyeyGreenstein, Medium};
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Color;
+use crate::spectrum::Channels;
+use crate::primitives::sampler;
+
+use rand::Rng;
+
+/// A medium with the same density everywhere.
+///
+/// The coefficients are the probabilities per world unit of light being absorbed or scattered,
+/// per color channel.
+pub struct Homogeneous {
+    absorption: Color,
+    scattering: Color,
+    phase: HenyeyGreenstein,
+}
+
+impl Homogeneous {
+    pub fn new(absorption: Color, scattering: Color, phase: HenyeyGreenstein) -> Self {
+        Self {
+            absorption: absorption,
+            scattering: scattering,
+            phase: phase,
+        }
+    }
+
+    fn extinction(&self, channels: &Channels) -> Color {
+        channels.reflectance(self.absorption) + channels.reflectance(self.scattering)
+    }
+}
+
+/// Beer-Lambert's law for the given extinction coefficients and distance, which may be infinite
+pub(crate) fn beer_lambert(extinction: Color, distance: f64) -> Color {
+    extinction.map(|sigma| {
+        if sigma == 0f64 {
+            1f64
+        } else {
+            (-sigma * distance).exp()
+        }
+    })
+}
+
+impl Medium for Homogeneous {
+    fn sample(&self, ray: &Ray, t_max: f64, throughput: Color, channels: &Channels) -> (Option<f64>, Color) {
+        let mut rng = sampler::rng();
+        let speed = ray.direction.length();
+        let extinction = self.extinction(channels);
+        let max_distance = t_max * speed;
+
+        // Light that can't scatter only gets absorbed, so there is no distance to sample
+        let scattering = channels.reflectance(self.scattering);
+        if scattering == Color::BLACK {
+            return (None, beer_lambert(extinction, max_distance));
+        }
+
+        // Sample the distance for a random channel, and weight by the mixture over all channels
+        let probabilities = channel_probabilities(throughput);
+        let sigma = extinction.channel(pick_channel(probabilities, rng.gen()));
+        let distance = if sigma > 0f64 {
+            -(1f64 - rng.gen::<f64>()).ln() / sigma
+        } else {
+            f64::INFINITY
+        };
+        if distance < max_distance {
+            let transmittance = beer_lambert(extinction, distance);
+            let density = expectation(extinction * transmittance, probabilities);
+            let weight = if density > 0f64 {
+                transmittance * scattering / density
+            } else {
+                Color::BLACK
+            };
+            (Some(distance / speed), weight)
+        } else {
+            let transmittance = beer_lambert(extinction, max_distance);
+            let probability = expectation(transmittance, probabilities);
+            let weight = if probability > 0f64 {
+                transmittance / probability
+            } else {
+                Color::BLACK
+            };
+            (None, weight)
+        }
+    }
+
+    fn transmittance(&self, ray: &Ray, t_max: f64, channels: &Channels) -> Color {
+        beer_lambert(self.extinction(channels), t_max * ray.direction.length())
+    }
+
+    fn phase(&self) -> &HenyeyGreenstein {
+        &self.phase
+    }
+}
+
+#[test]
+fn test_distance_sampling() {
+    use crate::primitives::vec::Vector;
+
+    let medium = Homogeneous::new(
+        Color::from_linear(0.1, 0.2, 0.3),
+        Color::from_linear(0.4, 0.3, 0.2),
+        HenyeyGreenstein::isotropic(),
+    );
+    let ray = Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0f64, 0f64, 2f64));
+
+    // Passing through, the expected weight is the transmittance
+    let samples = 20000;
+    let mut passed = Color::BLACK;
+    let mut scattered = Color::BLACK;
+    for _ in 0..samples {
+        match medium.sample(&ray, 1f64, Color::WHITE, &Channels::Rgb) {
+            (None, weight) => passed = passed + weight,
+            (Some(t), weight) => {
+                assert!(t > 0f64 && t < 1f64);
+                scattered = scattered + weight;
+            }
+        }
+    }
+    let passed = passed / samples as f64;
+    let expected = medium.transmittance(&ray, 1f64, &Channels::Rgb);
+    assert!((passed - expected).abs().less_than(Color::from_linear(0.02, 0.02, 0.02)));
+    assert!((expected.r() - (-1f64).exp()).abs() < 1e-12);
+
+    // Scattering takes the albedo times the rest of the light
+    let scattered = scattered / samples as f64;
+    let albedo = Color::from_linear(0.8, 0.6, 0.4);
+    let expected = albedo * (Color::from_linear(1f64, 1f64, 1f64) - expected);
+    assert!((scattered - expected).abs().less_than(Color::from_linear(0.02, 0.02, 0.02)));
+}
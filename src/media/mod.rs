@@ -0,0 +1,138 @@
+pub mod heterogeneous;
+pub mod homogeneous;
+pub mod voxel;
+
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::{Color, Vector};
+use crate::spectrum::Channels;
+
+use std::f64::consts::PI;
+
+/// A participating medium like fog, smoke or the murky inside of glass, which absorbs and
+/// scatters light along rays instead of only at surfaces.
+///
+/// Distances are measured in world units along the ray, whose direction need not be normalized.
+pub trait Medium: Sync + Send {
+    /// Samples how far light gets along the ray before it scatters, up to the ray parameter `t_max`.
+    ///
+    /// `throughput` is the weight that the path carries so far. Distances are sampled mostly for
+    /// the color channels that still carry light, which keeps the weights of long colored paths bounded.
+    ///
+    /// Returns the ray parameter at which the ray scatters, or `None` if it reaches `t_max`,
+    /// together with the factor that the light of the path has to be weighted with.
+    ///
+    /// The coefficients of the medium are converted to the color `channels` of the path.
+    fn sample(&self, ray: &Ray, t_max: f64, throughput: Color, channels: &Channels) -> (Option<f64>, Color);
+
+    /// The fraction of light that gets from the origin of the ray to the ray parameter `t_max`
+    fn transmittance(&self, ray: &Ray, t_max: f64, channels: &Channels) -> Color;
+
+    /// The distribution of directions that light is scattered into
+    fn phase(&self) -> &HenyeyGreenstein;
+}
+
+/// The probabilities of sampling distances for each color channel, proportional to `throughput`
+pub(crate) fn channel_probabilities(throughput: Color) -> Color {
+    let throughput = throughput.abs();
+    let total = throughput.average() * 3f64;
+    if total > 0f64 && total.is_finite() {
+        throughput / total
+    } else {
+        Color::WHITE / 3f64
+    }
+}
+
+/// Picks a color channel with the given probabilities
+pub(crate) fn pick_channel(probabilities: Color, u: f64) -> usize {
+    if u < probabilities.r() {
+        0
+    } else if u < probabilities.r() + probabilities.g() {
+        1
+    } else {
+        2
+    }
+}
+
+/// The expected value of the channels of `values` when they are picked with `probabilities`
+pub(crate) fn expectation(values: Color, probabilities: Color) -> f64 {
+    (values * probabilities).average() * 3f64
+}
+
+/// The Henyey-Greenstein phase function.
+///
+/// The asymmetry `g` is in (-1, 1): positive values scatter forward, negative values backward
+/// and zero scatters uniformly in all directions.
+#[derive(Debug, Clone, Copy)]
+pub struct HenyeyGreenstein {
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: f64) -> Self {
+        Self {
+            g: g.clamp(-0.999, 0.999),
+        }
+    }
+
+    pub fn isotropic() -> Self {
+        Self::new(0f64)
+    }
+
+    /// The probability density, per solid angle, of light travelling along the unit vector
+    /// `incoming` being scattered into the unit vector `outgoing`
+    pub fn pdf(&self, incoming: Vector, outgoing: Vector) -> f64 {
+        let cos_theta = incoming.dot(&outgoing);
+        let denominator = 1f64 + self.g * self.g - 2f64 * self.g * cos_theta;
+        (1f64 - self.g * self.g) / (4f64 * PI * denominator * denominator.sqrt())
+    }
+
+    /// Maps two uniform random numbers to a unit direction that light travelling along the
+    /// unit vector `incoming` is scattered into, distributed like [`HenyeyGreenstein::pdf`]
+    pub fn sample(&self, incoming: Vector, u1: f64, u2: f64) -> Vector {
+        let g = self.g;
+        let cos_theta = if g.abs() < 1e-3 {
+            1f64 - 2f64 * u1
+        } else {
+            let term = (1f64 - g * g) / (1f64 - g + 2f64 * g * u1);
+            (1f64 + g * g - term * term) / (2f64 * g)
+        }
+        .clamp(-1f64, 1f64);
+        let sin_theta = (1f64 - cos_theta * cos_theta).max(0f64).sqrt();
+        let (sin_phi, cos_phi) = (2f64 * PI * u2).sin_cos();
+        let (tangent, bitangent) = incoming.orthonormal_basis();
+        (tangent * (sin_theta * cos_phi))
+            + (bitangent * (sin_theta * sin_phi))
+            + (incoming * cos_theta)
+    }
+}
+
+#[test]
+fn test_henyey_greenstein() {
+    let incoming = Vector::new(0f64, 0f64, 1f64);
+    for g in [-0.7, 0f64, 0.3, 0.9].iter() {
+        let phase = HenyeyGreenstein::new(*g);
+
+        // The pdf integrates to one over the sphere
+        let n = 100000;
+        let integral: f64 = (0..n)
+            .map(|i| {
+                let cos_theta = 1f64 - 2f64 * (i as f64 + 0.5) / n as f64;
+                let sin_theta = (1f64 - cos_theta * cos_theta).sqrt();
+                let outgoing = Vector::new(sin_theta, 0f64, cos_theta);
+                phase.pdf(incoming, outgoing) * 4f64 * PI / n as f64
+            })
+            .sum();
+        assert!((integral - 1f64).abs() < 1e-3);
+
+        // The mean cosine of the samples is g
+        let samples = 1000;
+        let mean: f64 = (0..samples)
+            .map(|i| {
+                let u1 = (i as f64 + 0.5) / samples as f64;
+                phase.sample(incoming, u1, 0.3).dot(&incoming)
+            })
+            .sum::<f64>()
+            / samples as f64;
+        assert!((mean - g).abs() < 1e-2);
+    }
+}
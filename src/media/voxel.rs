@@ -0,0 +1,222 @@
+use crate::media::heterogeneous::Density;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::vec::Vector;
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The first bytes of a voxel file
+const MAGIC: &[u8; 4] = b"VOXL";
+
+/// The length of the magic bytes and the size of the grid at the start of a voxel file
+const HEADER_LENGTH: u64 = 16;
+
+/// A dense grid of densities stretched over a box, interpolated trilinearly between voxel centers.
+///
+/// Outside of the box the density is zero.
+pub struct VoxelGrid {
+    width: usize,
+    height: usize,
+    depth: usize,
+    bounds: Aabb,
+    values: Vec<f64>,
+}
+
+impl VoxelGrid {
+    /// Creates a grid from `width * height * depth` values, with x varying fastest and z slowest
+    pub fn new(width: usize, height: usize, depth: usize, bounds: Aabb, values: Vec<f64>) -> Self {
+        assert_eq!(values.len(), width * height * depth);
+        assert!(width > 0 && height > 0 && depth > 0);
+        Self {
+            width: width,
+            height: height,
+            depth: depth,
+            bounds: bounds,
+            values: values,
+        }
+    }
+
+    /// Samples `density` at the voxel centers, e.g. to bake procedural noise into a grid
+    pub fn from_fn<F>(width: usize, height: usize, depth: usize, bounds: Aabb, density: F) -> Self
+    where
+        F: Fn(Vector) -> f64,
+    {
+        let size = bounds.max - bounds.min;
+        let values = (0..width * height * depth)
+            .map(|i| {
+                let (x, y, z) = (i % width, i / width % height, i / (width * height));
+                density(
+                    bounds.min
+                        + Vector::new(
+                            size.x() * (x as f64 + 0.5) / width as f64,
+                            size.y() * (y as f64 + 0.5) / height as f64,
+                            size.z() * (z as f64 + 0.5) / depth as f64,
+                        ),
+                )
+            })
+            .collect();
+        Self::new(width, height, depth, bounds, values)
+    }
+
+    /// Loads a grid that is stretched over `bounds`.
+    ///
+    /// The file starts with the bytes `VOXL`, followed by the width, height and depth as little
+    /// endian `u32` and then the values as little endian `f32`, with x varying fastest.
+    pub fn open<P: AsRef<Path>>(path: P, bounds: Aabb) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a voxel file"));
+        }
+        let mut read_u32 = || -> io::Result<usize> {
+            let mut bytes = [0u8; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes) as usize)
+        };
+        let (width, height, depth) = (read_u32()?, read_u32()?, read_u32()?);
+        let size = width
+            .checked_mul(height)
+            .and_then(|count| count.checked_mul(depth))
+            .filter(|count| *count > 0)
+            .and_then(|count| count.checked_mul(4))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid voxel grid size"))?;
+        // Check the size against the file before allocating, as the header may be corrupt
+        if length.saturating_sub(HEADER_LENGTH) < size as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "truncated voxel file"));
+        }
+        let mut bytes = vec![0u8; size];
+        reader.read_exact(&mut bytes)?;
+        let values = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as f64)
+            .collect();
+        Ok(Self::new(width, height, depth, bounds, values))
+    }
+
+    /// Writes the grid in the format read by [`VoxelGrid::open`]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        for size in [self.width, self.height, self.depth].iter() {
+            writer.write_all(&(*size as u32).to_le_bytes())?;
+        }
+        for value in self.values.iter() {
+            writer.write_all(&(*value as f32).to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.values[(z * self.height + y) * self.width + x]
+    }
+
+    /// The continuous voxel coordinates of a position, with voxel centers at integers
+    fn to_voxels(&self, position: Vector) -> [f64; 3] {
+        let size = self.bounds.max - self.bounds.min;
+        let resolution = [self.width, self.height, self.depth];
+        std::array::from_fn(|axis| {
+            (position.axis(axis) - self.bounds.min.axis(axis)) / size.axis(axis)
+                * resolution[axis] as f64
+                - 0.5
+        })
+    }
+}
+
+impl Density for VoxelGrid {
+    fn density(&self, position: Vector) -> f64 {
+        let inside = (0..3).all(|axis| {
+            position.axis(axis) >= self.bounds.min.axis(axis)
+                && position.axis(axis) <= self.bounds.max.axis(axis)
+        });
+        if !inside {
+            return 0f64;
+        }
+        let coordinates = self.to_voxels(position);
+        let resolution = [self.width, self.height, self.depth];
+        // The two voxels to interpolate between along each axis, clamped at the faces of the box
+        let [(x0, x1, tx), (y0, y1, ty), (z0, z1, tz)]: [(usize, usize, f64); 3] =
+            std::array::from_fn(|axis| {
+                let last = resolution[axis] as f64 - 1f64;
+                let c = coordinates[axis].clamp(0f64, last);
+                let low = c.floor();
+                (low as usize, (low + 1f64).min(last) as usize, c - low)
+            });
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let plane = |z: usize| {
+            lerp(
+                lerp(self.voxel(x0, y0, z), self.voxel(x1, y0, z), tx),
+                lerp(self.voxel(x0, y1, z), self.voxel(x1, y1, z), tx),
+                ty,
+            )
+        };
+        lerp(plane(z0), plane(z1), tz)
+    }
+
+    fn max_density(&self, region: &Aabb) -> f64 {
+        let region = region.intersection(&self.bounds);
+        if (0..3).any(|axis| region.min.axis(axis) > region.max.axis(axis)) {
+            return 0f64;
+        }
+        // All voxels that are interpolated anywhere within the region
+        let (low, high) = (self.to_voxels(region.min), self.to_voxels(region.max));
+        let resolution = [self.width, self.height, self.depth];
+        let range = |axis: usize| {
+            let last = resolution[axis] - 1;
+            let start = (low[axis].floor().max(0f64) as usize).min(last);
+            let end = (high[axis].ceil().max(0f64) as usize).min(last);
+            start..=end
+        };
+        let mut max = 0f64;
+        for z in range(2) {
+            for y in range(1) {
+                for x in range(0) {
+                    max = max.max(self.voxel(x, y, z));
+                }
+            }
+        }
+        max
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+#[test]
+fn test_voxel_grid() {
+    let bounds = Aabb::new(Vector::new(0f64, 0f64, 0f64), Vector::new(2f64, 2f64, 4f64));
+    // The density grows along x
+    let grid = VoxelGrid::from_fn(2, 2, 2, bounds, |position| position.x());
+    assert_eq!(grid.density(Vector::new(0.5, 0.5, 1f64)), 0.5);
+    assert_eq!(grid.density(Vector::new(1f64, 1.3, 3f64)), 1f64);
+    assert_eq!(grid.density(Vector::new(2f64, 0.1, 0.1)), 1.5);
+    assert_eq!(grid.density(Vector::new(2.1, 1f64, 1f64)), 0f64);
+
+    assert_eq!(grid.max_density(&bounds), 1.5);
+    let corner = Aabb::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0.4, 0.4, 0.4));
+    assert_eq!(grid.max_density(&corner), 0.5);
+    let outside = Aabb::new(Vector::new(3f64, 0f64, 0f64), Vector::new(4f64, 1f64, 1f64));
+    assert_eq!(grid.max_density(&outside), 0f64);
+
+    // Saving and loading keeps the grid
+    let path = std::env::temp_dir().join(format!("test_voxel_grid_{}.voxl", std::process::id()));
+    grid.save(&path).unwrap();
+    let loaded = VoxelGrid::open(&path, bounds).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.values, grid.values);
+    assert_eq!((loaded.width, loaded.height, loaded.depth), (2, 2, 2));
+
+    // Sizes in the header that the file doesn't hold are rejected without allocating them
+    let mut header = MAGIC.to_vec();
+    for size in [1024u32, 1024, 1024].iter() {
+        header.extend_from_slice(&size.to_le_bytes());
+    }
+    std::fs::write(&path, &header).unwrap();
+    let error = VoxelGrid::open(&path, bounds).err().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
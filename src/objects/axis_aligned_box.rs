@@ -0,0 +1,192 @@
+use crate::materials::Material;
+use crate::objects::traits::Intersect;
+use crate::objects::Object;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::{Intersection, Span};
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+/// A solid box whose faces are aligned with the coordinate axes.
+///
+/// Every face is mapped to the whole UV square.
+pub struct AxisAlignedBox {
+    bounds: Aabb,
+    material: Box<dyn Material>,
+}
+
+impl AxisAlignedBox {
+    pub fn new(min: Vector, max: Vector, material: Box<dyn Material>) -> Self {
+        Self {
+            bounds: Aabb::new(min, max),
+            material: material,
+        }
+    }
+
+    /// The ray parameters and axes at which the line through the ray enters and exits the box
+    fn slabs(&self, ray: &Ray) -> Option<((f64, usize), (f64, usize))> {
+        let mut t_near = f64::NEG_INFINITY;
+        let mut t_far = f64::INFINITY;
+        let mut near_axis = 0;
+        let mut far_axis = 0;
+
+        for axis in 0..3 {
+            let origin = ray.origin.axis(axis);
+            let direction = ray.direction.axis(axis);
+            let (min, max) = (self.bounds.min.axis(axis), self.bounds.max.axis(axis));
+            if direction == 0f64 {
+                // Parallel to the slab, so the ray is either always or never between its faces
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let (t0, t1) = if direction > 0f64 {
+                ((min - origin) / direction, (max - origin) / direction)
+            } else {
+                ((max - origin) / direction, (min - origin) / direction)
+            };
+            if t0 > t_near {
+                t_near = t0;
+                near_axis = axis;
+            }
+            if t1 < t_far {
+                t_far = t1;
+                far_axis = axis;
+            }
+        }
+
+        if t_near > t_far {
+            None
+        } else {
+            Some(((t_near, near_axis), (t_far, far_axis)))
+        }
+    }
+
+    /// The edge of the box along an axis
+    fn edge(&self, axis: usize) -> Vector {
+        let mut edge = [0f64; 3];
+        edge[axis] = self.bounds.max.axis(axis) - self.bounds.min.axis(axis);
+        Vector::new(edge[0], edge[1], edge[2])
+    }
+
+    fn intersection_at(&self, ray: &Ray, t: f64, axis: usize, sign: f64) -> Intersection<'_> {
+        let position = ray.point_at_parameter(t);
+        let mut normal = [0f64; 3];
+        normal[axis] = sign;
+
+        let face_coordinate = |axis: usize| {
+            (position.axis(axis) - self.bounds.min.axis(axis))
+                / (self.bounds.max.axis(axis) - self.bounds.min.axis(axis))
+        };
+        Intersection::new(
+            position,
+            self as &dyn Object,
+            t,
+            Vector::new(normal[0], normal[1], normal[2]),
+        )
+        .with_uv(
+            face_coordinate((axis + 1) % 3),
+            face_coordinate((axis + 2) % 3),
+        )
+        .with_tangents(self.edge((axis + 1) % 3), self.edge((axis + 2) % 3))
+    }
+}
+
+impl Object for AxisAlignedBox {
+    fn material(&self) -> &Box<dyn Material> {
+        &self.material
+    }
+}
+
+impl Intersect for AxisAlignedBox {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection<'_>> {
+        let ((t_near, near_axis), (t_far, far_axis)) = self.slabs(ray)?;
+        if t_near > param_min {
+            let sign = -ray.direction.axis(near_axis).signum();
+            Some(self.intersection_at(ray, t_near, near_axis, sign))
+        } else if t_far > param_min {
+            // The ray starts inside of the box
+            let sign = ray.direction.axis(far_axis).signum();
+            Some(self.intersection_at(ray, t_far, far_axis, sign))
+        } else {
+            None
+        }
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        self.slabs(ray)
+            .map(|((t_near, near_axis), (t_far, far_axis))| {
+                Span::new(
+                    self.intersection_at(
+                        ray,
+                        t_near,
+                        near_axis,
+                        -ray.direction.axis(near_axis).signum(),
+                    ),
+                    self.intersection_at(
+                        ray,
+                        t_far,
+                        far_axis,
+                        ray.direction.axis(far_axis).signum(),
+                    ),
+                )
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
+
+unsafe impl Sync for AxisAlignedBox {}
+
+#[test]
+fn test_intersect() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::primitives::vec::Color;
+
+    let cube = AxisAlignedBox::new(
+        Vector::new(-1f64, -1f64, -1f64),
+        Vector::new(1f64, 1f64, 1f64),
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    );
+
+    let hit = cube
+        .intersect(
+            &Ray::new(Vector::new(0.5, 0f64, 5f64), Vector::new(0f64, 0f64, -2f64)),
+            0.001,
+        )
+        .unwrap();
+    assert_eq!(hit.ray_parameter, 2f64);
+    assert_eq!(hit.normal, Vector::new(0f64, 0f64, 1f64));
+    assert_eq!(hit.uv, (0.75, 0.5));
+
+    // From the inside the exit face is hit, with an outward normal
+    let hit = cube
+        .intersect(
+            &Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0f64, -1f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert_eq!(hit.ray_parameter, 1f64);
+    assert_eq!(hit.normal, Vector::new(0f64, -1f64, 0f64));
+
+    // Grazing along a face touches the box at its edge, slightly above it misses
+    let hit = cube
+        .intersect(
+            &Ray::new(Vector::new(-3f64, 1f64, 0f64), Vector::new(1f64, 0f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert_eq!(hit.ray_parameter, 2f64);
+    assert_eq!(hit.normal, Vector::new(-1f64, 0f64, 0f64));
+    assert!(cube
+        .intersect(
+            &Ray::new(Vector::new(-3f64, 1.001, 0f64), Vector::new(1f64, 0f64, 0f64)),
+            0.001
+        )
+        .is_none());
+}
@@ -0,0 +1,191 @@
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::Intersection;
+use crate::primitives::ray::Ray;
+use crate::renderer::stats::{self, Counter};
+
+use std::cmp::Ordering::Equal;
+
+enum Node {
+    Leaf { bounds: Aabb, index: usize },
+    Inner { bounds: Aabb, left: usize, right: usize },
+}
+
+impl Node {
+    fn bounds(&self) -> &Aabb {
+        match self {
+            Node::Leaf { bounds, .. } => bounds,
+            Node::Inner { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// A bounding volume hierarchy over the indices of bounded objects
+pub struct Bvh {
+    nodes: Vec<Node>,
+}
+
+impl Bvh {
+    /// Builds the hierarchy over pairs of object indices and their bounding boxes
+    pub fn new(mut items: Vec<(usize, Aabb)>) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(items.len() * 2),
+        };
+        if !items.is_empty() {
+            bvh.build(&mut items);
+        }
+        bvh
+    }
+
+    /// Appends the subtree over `items` and returns the index of its root
+    fn build(&mut self, items: &mut [(usize, Aabb)]) -> usize {
+        if items.len() == 1 {
+            self.nodes.push(Node::Leaf {
+                bounds: items[0].1,
+                index: items[0].0,
+            });
+            return self.nodes.len() - 1;
+        }
+
+        // Split at the median along the axis in which the centers are spread the most
+        let centers = items
+            .iter()
+            .map(|(_, bounds)| Aabb::new(bounds.center(), bounds.center()))
+            .fold(None, |all: Option<Aabb>, center| {
+                Some(all.map_or(center, |all| all.union(&center)))
+            })
+            .unwrap();
+        let axis = centers.longest_axis();
+        items.sort_by(|(_, a), (_, b)| {
+            a.center()
+                .axis(axis)
+                .partial_cmp(&b.center().axis(axis))
+                .unwrap_or(Equal)
+        });
+
+        let bounds = items
+            .iter()
+            .skip(1)
+            .fold(items[0].1, |all, (_, bounds)| all.union(bounds));
+        let (left_items, right_items) = items.split_at_mut(items.len() / 2);
+
+        // Reserve the slot of this node so that the root ends up at index 0
+        let index = self.nodes.len();
+        self.nodes.push(Node::Leaf {
+            bounds: bounds,
+            index: 0,
+        });
+        let left = self.build(left_items);
+        let right = self.build(right_items);
+        self.nodes[index] = Node::Inner {
+            bounds: bounds,
+            left: left,
+            right: right,
+        };
+        index
+    }
+
+    /// Finds the closest intersection by calling `intersect` with the index of every object
+    /// whose bounding box the ray enters before the closest intersection found so far
+    pub fn intersect<'a, F>(&self, ray: &Ray, param_min: f64, mut intersect: F) -> Option<Intersection<'a>>
+    where
+        F: FnMut(usize) -> Option<Intersection<'a>>,
+    {
+        let mut closest: Option<Intersection<'a>> = None;
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        let mut stack = vec![0];
+        let mut visited = 0;
+        while let Some(node) = stack.pop() {
+            visited += 1;
+            let param_max = closest
+                .as_ref()
+                .map_or(f64::INFINITY, |closest| closest.ray_parameter);
+            if self.nodes[node]
+                .bounds()
+                .hit(ray, param_min, param_max)
+                .is_none()
+            {
+                continue;
+            }
+            match self.nodes[node] {
+                Node::Leaf { index, .. } => {
+                    if let Some(intersection) = intersect(index) {
+                        if intersection.ray_parameter < param_max {
+                            closest = Some(intersection);
+                        }
+                    }
+                }
+                Node::Inner { left, right, .. } => {
+                    // Visit the nearer child first to find close intersections early
+                    let left_hit = self.nodes[left].bounds().hit(ray, param_min, param_max);
+                    let right_hit = self.nodes[right].bounds().hit(ray, param_min, param_max);
+                    match (left_hit, right_hit) {
+                        (Some(l), Some(r)) if r < l => {
+                            stack.push(left);
+                            stack.push(right);
+                        }
+                        (Some(_), Some(_)) => {
+                            stack.push(right);
+                            stack.push(left);
+                        }
+                        (Some(_), None) => stack.push(left),
+                        (None, Some(_)) => stack.push(right),
+                        (None, None) => {}
+                    }
+                }
+            }
+        }
+        stats::count(Counter::BvhNodes, visited);
+        closest
+    }
+}
+
+#[test]
+fn test_matches_linear_search() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::traits::Intersect;
+    use crate::primitives::vec::{Color, Vector};
+
+    let spheres: Vec<Sphere> = (0..50)
+        .map(|i| {
+            let i = i as f64;
+            Sphere::new(
+                Vector::new((i * 7.3) % 11f64, (i * 3.1) % 5f64, (i * 1.7) % 13f64),
+                0.3 + (i % 4f64) * 0.2,
+                Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+            )
+        })
+        .collect();
+    let bvh = Bvh::new(
+        spheres
+            .iter()
+            .enumerate()
+            .map(|(i, sphere)| (i, sphere.bounding_box().unwrap()))
+            .collect(),
+    );
+
+    let mut hits = 0;
+    for i in 0..200 {
+        let i = i as f64;
+        let ray = Ray::new(
+            Vector::new(-5f64, 2f64, -5f64),
+            Vector::new(1f64, (i * 0.37) % 1f64 - 0.5, (i * 0.61) % 1.5),
+        );
+        let linear = spheres
+            .iter()
+            .filter_map(|sphere| sphere.intersect(&ray, 0.001))
+            .map(|intersection| intersection.ray_parameter)
+            .fold(f64::INFINITY, f64::min);
+        let accelerated = bvh
+            .intersect(&ray, 0.001, |index| spheres[index].intersect(&ray, 0.001))
+            .map_or(f64::INFINITY, |intersection| intersection.ray_parameter);
+        assert_eq!(linear, accelerated);
+        if linear.is_finite() {
+            hits += 1;
+        }
+    }
+    assert!(hits > 0);
+}
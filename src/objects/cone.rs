@@ -0,0 +1,216 @@
+use crate::materials::Material;
+use crate::objects::cylinder::{
+    angle_coordinate, closest_hit, convex_span, polar_tangents, Frame, LocalHit,
+};
+use crate::objects::disk::disk_bounds;
+use crate::objects::traits::Intersect;
+use crate::objects::Object;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::{Intersection, Span};
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+/// A solid cone from a round base to its apex.
+///
+/// On the mantle U runs around the axis and V from the base (0) to the apex (1).
+/// On the base V is the distance from the axis relative to the radius.
+pub struct Cone {
+    frame: Frame,
+    height: f64,
+    radius: f64,
+    material: Box<dyn Material>,
+}
+
+impl Cone {
+    pub fn new(base: Vector, apex: Vector, radius: f64, material: Box<dyn Material>) -> Self {
+        Self {
+            frame: Frame::new(base, apex - base),
+            height: (apex - base).length(),
+            radius: radius,
+            material: material,
+        }
+    }
+
+    /// All points where the line through the ray crosses the surface
+    fn hits(&self, ray: &Ray) -> Vec<LocalHit> {
+        let (origin, direction) = self.frame.to_local(ray);
+        let at = |t: f64| origin + direction * t;
+        let mut candidates = Vec::with_capacity(3);
+
+        // The mantle: x^2 + y^2 = (k * (height - z))^2
+        let k2 = (self.radius / self.height) * (self.radius / self.height);
+        let to_apex = self.height - origin.z();
+        let a = direction.x() * direction.x() + direction.y() * direction.y()
+            - k2 * direction.z() * direction.z();
+        let b = 2f64
+            * (origin.x() * direction.x()
+                + origin.y() * direction.y()
+                + k2 * to_apex * direction.z());
+        let c = origin.x() * origin.x() + origin.y() * origin.y() - k2 * to_apex * to_apex;
+
+        let mantle_hits = if a.abs() < 1e-12 {
+            // The ray runs parallel to the mantle and crosses it at most once
+            if b == 0f64 {
+                vec![]
+            } else {
+                vec![-c / b]
+            }
+        } else {
+            let discriminant = b * b - 4f64 * a * c;
+            if discriminant < 0f64 {
+                vec![]
+            } else {
+                vec![
+                    (-b - discriminant.sqrt()) / (2f64 * a),
+                    (-b + discriminant.sqrt()) / (2f64 * a),
+                ]
+            }
+        };
+        for t in mantle_hits {
+            let point = at(t);
+            // Skip the mirrored cone above the apex
+            if point.z() >= 0f64 && point.z() <= self.height {
+                candidates.push((
+                    t,
+                    Vector::new(point.x(), point.y(), k2 * (self.height - point.z())).normalize(),
+                    (
+                        angle_coordinate(point.x(), point.y()),
+                        point.z() / self.height,
+                    ),
+                ));
+            }
+        }
+
+        // The base
+        if direction.z() != 0f64 {
+            let t = -origin.z() / direction.z();
+            let point = at(t);
+            let distance = (point.x() * point.x() + point.y() * point.y()).sqrt();
+            if distance <= self.radius {
+                candidates.push((
+                    t,
+                    Vector::new(0f64, 0f64, -1f64),
+                    (
+                        angle_coordinate(point.x(), point.y()),
+                        distance / self.radius,
+                    ),
+                ));
+            }
+        }
+
+        candidates
+    }
+
+    fn intersection_at(&self, ray: &Ray, (t, normal, (u, v)): LocalHit) -> Intersection<'_> {
+        let (origin, direction) = self.frame.to_local(ray);
+        let (dpdu, mut dpdv) = polar_tangents(origin + direction * t, self.radius);
+        if normal.z() != -1f64 {
+            // On the mantle v goes up towards the apex, where the radius shrinks to zero
+            dpdv = Vector::new(0f64, 0f64, self.height) - dpdv;
+        }
+        Intersection::new(
+            ray.point_at_parameter(t),
+            self as &dyn Object,
+            t,
+            self.frame.to_world(normal),
+        )
+        .with_uv(u, v)
+        .with_tangents(self.frame.to_world(dpdu), self.frame.to_world(dpdv))
+    }
+}
+
+impl Object for Cone {
+    fn material(&self) -> &Box<dyn Material> {
+        &self.material
+    }
+}
+
+impl Intersect for Cone {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection<'_>> {
+        closest_hit(self.hits(ray).into_iter(), param_min).map(|hit| self.intersection_at(ray, hit))
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        convex_span(self.hits(ray))
+            .map(|(enter, exit)| {
+                Span::new(
+                    self.intersection_at(ray, enter),
+                    self.intersection_at(ray, exit),
+                )
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let apex = self.frame.origin + self.frame.w * self.height;
+        Some(
+            disk_bounds(self.frame.origin, self.frame.w, self.radius).union(&Aabb::new(apex, apex)),
+        )
+    }
+}
+
+unsafe impl Sync for Cone {}
+
+#[test]
+fn test_intersect() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::primitives::vec::Color;
+
+    // Upright with its base of radius 1 at y = 0 and its apex at y = 1
+    let cone = Cone::new(
+        Vector::new(0f64, 0f64, 0f64),
+        Vector::new(0f64, 1f64, 0f64),
+        1f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    );
+
+    let hit = cone
+        .intersect(
+            &Ray::new(Vector::new(5f64, 0.5, 0f64), Vector::new(-1f64, 0f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert!((hit.ray_parameter - 4.5).abs() < 1e-12);
+    let expected_normal = Vector::new(1f64, 1f64, 0f64).normalize();
+    assert!((hit.normal - expected_normal).length() < 1e-12);
+    assert!((hit.uv.1 - 0.5).abs() < 1e-12);
+
+    // The base from below
+    let hit = cone
+        .intersect(
+            &Ray::new(Vector::new(0.2, -1f64, 0f64), Vector::new(0f64, 1f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert!((hit.ray_parameter - 1f64).abs() < 1e-12);
+    assert!((hit.normal - Vector::new(0f64, -1f64, 0f64)).length() < 1e-12);
+
+    // From the inside
+    let hit = cone
+        .intersect(
+            &Ray::new(Vector::new(0f64, 0.5, 0f64), Vector::new(0f64, 1f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert!((hit.ray_parameter - 0.5).abs() < 1e-9);
+
+    // Grazing the mantle along its slope touches it once, above the apex there is nothing
+    let hit = cone
+        .intersect(
+            &Ray::new(Vector::new(2f64, -1f64, 0f64), Vector::new(-1f64, 1f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert!((hit.position - Vector::new(1f64, 0f64, 0f64)).length() < 1e-9);
+    assert!(cone
+        .intersect(
+            &Ray::new(Vector::new(5f64, 1.5, 0f64), Vector::new(-1f64, 0f64, 0f64)),
+            0.001
+        )
+        .is_none());
+
+    let bounding_box = cone.bounding_box().unwrap();
+    assert!((bounding_box.min - Vector::new(-1f64, 0f64, -1f64)).length() < 1e-12);
+    assert!((bounding_box.max - Vector::new(1f64, 1f64, 1f64)).length() < 1e-12);
+}
@@ -0,0 +1,253 @@
+use crate::materials::Material;
+use crate::objects::traits::Intersect;
+use crate::objects::Object;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::{Intersection, Span};
+use crate::primitives::ray::Ray;
+
+use std::cmp::Ordering::Equal;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOperation {
+    /// Everything inside of either object
+    Union,
+    /// Everything inside of both objects
+    Intersection,
+    /// Everything inside of the left but outside of the right object
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(&self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// A solid built from two other solids with a boolean operation.
+///
+/// Both objects have to report their [`spans`](Intersect::spans). Unless the node is given
+/// a material of its own, every part of the surface keeps the material of the object it stems from.
+///
+/// A biconvex lens, for example, is the intersection of two overlapping spheres.
+pub struct Csg {
+    operation: CsgOperation,
+    left: Box<dyn Object>,
+    right: Box<dyn Object>,
+    material: Option<Box<dyn Material>>,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+        Self {
+            operation: operation,
+            left: left,
+            right: right,
+            material: None,
+        }
+    }
+
+    pub fn union(left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+        Self::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+        Self::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference(left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+        Self::new(CsgOperation::Difference, left, right)
+    }
+
+    /// Renders the whole surface with `material` instead of the materials of the parts
+    pub fn set_material(&mut self, material: Box<dyn Material>) {
+        self.material = Some(material);
+    }
+}
+
+impl Object for Csg {
+    fn material(&self) -> &Box<dyn Material> {
+        self.material
+            .as_ref()
+            .unwrap_or_else(|| self.left.material())
+    }
+}
+
+impl Intersect for Csg {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection<'_>> {
+        self.spans(ray)
+            .into_iter()
+            .flat_map(|span| vec![span.enter, span.exit])
+            .find(|boundary| {
+                boundary.ray_parameter.is_finite() && boundary.ray_parameter > param_min
+            })
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        // Walk along all boundaries of both objects and keep track of which ones the ray is in
+        let mut events: Vec<(bool, bool, Intersection)> = Vec::new();
+        for (is_left, spans) in [(true, self.left.spans(ray)), (false, self.right.spans(ray))] {
+            for span in spans {
+                events.push((is_left, true, span.enter));
+                events.push((is_left, false, span.exit));
+            }
+        }
+        events.sort_by(|a, b| {
+            a.2.ray_parameter
+                .partial_cmp(&b.2.ray_parameter)
+                .unwrap_or(Equal)
+        });
+
+        let mut in_left = false;
+        let mut in_right = false;
+        let mut enter: Option<Intersection> = None;
+        let mut spans = Vec::new();
+        for (is_left, entering, mut boundary) in events {
+            let was_inside = self.operation.contains(in_left, in_right);
+            if is_left {
+                in_left = entering;
+            } else {
+                in_right = entering;
+            }
+            let is_inside = self.operation.contains(in_left, in_right);
+            if was_inside == is_inside {
+                continue;
+            }
+
+            if !is_left && self.operation == CsgOperation::Difference {
+                // The surface of the subtracted object faces into the result
+                boundary.normal = -boundary.normal;
+                boundary.shading_normal = -boundary.shading_normal;
+            }
+            if self.material.is_some() {
+                boundary.object = self as &dyn Object;
+            }
+            if is_inside {
+                enter = Some(boundary);
+            } else if let Some(enter) = enter.take() {
+                spans.push(Span::new(enter, boundary));
+            }
+        }
+        spans
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let left = self.left.bounding_box();
+        let right = self.right.bounding_box();
+        match self.operation {
+            CsgOperation::Union => match (left, right) {
+                (Some(left), Some(right)) => Some(left.union(&right)),
+                _ => None,
+            },
+            CsgOperation::Intersection => match (left, right) {
+                (Some(left), Some(right)) => Some(left.intersection(&right)),
+                (Some(bounds), None) | (None, Some(bounds)) => Some(bounds),
+                (None, None) => None,
+            },
+            CsgOperation::Difference => left,
+        }
+    }
+}
+
+unsafe impl Sync for Csg {}
+
+#[cfg(test)]
+fn test_material() -> Box<dyn Material> {
+    use crate::materials::phong::PseudoPhong;
+    use crate::primitives::vec::Color;
+
+    Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK))
+}
+
+#[test]
+fn test_lens() {
+    use crate::objects::sphere::Sphere;
+    use crate::primitives::vec::Vector;
+
+    // Two spheres of radius 5 whose centers are 8 apart form a lens of thickness 2 around the origin
+    let lens = Csg::intersection(
+        Box::new(Sphere::new(
+            Vector::new(0f64, 0f64, 4f64),
+            5f64,
+            test_material(),
+        )),
+        Box::new(Sphere::new(
+            Vector::new(0f64, 0f64, -4f64),
+            5f64,
+            test_material(),
+        )),
+    );
+
+    let ray = Ray::new(
+        Vector::new(0f64, 0f64, 10f64),
+        Vector::new(0f64, 0f64, -1f64),
+    );
+    let spans = lens.spans(&ray);
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].enter.ray_parameter, 9f64);
+    assert_eq!(spans[0].enter.normal, Vector::new(0f64, 0f64, 1f64));
+    assert_eq!(spans[0].exit.ray_parameter, 11f64);
+    assert_eq!(spans[0].exit.normal, Vector::new(0f64, 0f64, -1f64));
+
+    assert_eq!(lens.intersect(&ray, 0.001).unwrap().ray_parameter, 9f64);
+    // From inside of the lens the exit is hit
+    assert_eq!(lens.intersect(&ray, 9.5).unwrap().ray_parameter, 11f64);
+    // Outside of the lens rim, but inside of both spheres' bounding boxes
+    let outside = Ray::new(
+        Vector::new(3.5, 0f64, 10f64),
+        Vector::new(0f64, 0f64, -1f64),
+    );
+    assert!(lens.intersect(&outside, 0.001).is_none());
+
+    let bounding_box = lens.bounding_box().unwrap();
+    assert_eq!(bounding_box.min, Vector::new(-5f64, -5f64, -1f64));
+    assert_eq!(bounding_box.max, Vector::new(5f64, 5f64, 1f64));
+}
+
+#[test]
+fn test_difference() {
+    use crate::objects::axis_aligned_box::AxisAlignedBox;
+    use crate::objects::sphere::Sphere;
+    use crate::primitives::vec::Vector;
+
+    // A cube with a spherical hole through its center
+    let part = Csg::difference(
+        Box::new(AxisAlignedBox::new(
+            Vector::new(-2f64, -2f64, -2f64),
+            Vector::new(2f64, 2f64, 2f64),
+            test_material(),
+        )),
+        Box::new(Sphere::new(
+            Vector::new(0f64, 0f64, 0f64),
+            1f64,
+            test_material(),
+        )),
+    );
+
+    let ray = Ray::new(
+        Vector::new(-5f64, 0f64, 0f64),
+        Vector::new(1f64, 0f64, 0f64),
+    );
+    let spans = part.spans(&ray);
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[0].enter.ray_parameter, 3f64);
+    assert_eq!(spans[0].exit.ray_parameter, 4f64);
+    // The wall of the hole faces towards its center
+    assert_eq!(spans[0].exit.normal, Vector::new(1f64, 0f64, 0f64));
+    assert_eq!(spans[1].enter.ray_parameter, 6f64);
+    assert_eq!(spans[1].enter.normal, Vector::new(-1f64, 0f64, 0f64));
+    assert_eq!(spans[1].exit.ray_parameter, 7f64);
+
+    // Starting in the hole, the far wall of the hole is hit first
+    let hit = part
+        .intersect(
+            &Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0f64, 1f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert_eq!(hit.ray_parameter, 1f64);
+    assert_eq!(hit.normal, Vector::new(0f64, -1f64, 0f64));
+}
@@ -0,0 +1,287 @@
+use crate::materials::Material;
+use crate::objects::disk::disk_bounds;
+use crate::objects::traits::Intersect;
+use crate::objects::Object;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::{Intersection, Span};
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+use std::f64::consts::PI;
+
+/// An orthonormal coordinate system in which the w axis is the axis of a rotationally symmetric object
+pub(crate) struct Frame {
+    pub origin: Vector,
+    pub u: Vector,
+    pub v: Vector,
+    pub w: Vector,
+}
+
+impl Frame {
+    pub fn new(origin: Vector, axis: Vector) -> Self {
+        let w = axis.normalize();
+        let (u, v) = w.orthonormal_basis();
+        Self {
+            origin: origin,
+            u: u,
+            v: v,
+            w: w,
+        }
+    }
+
+    /// Transforms the ray into the frame. Ray parameters stay the same.
+    pub fn to_local(&self, ray: &Ray) -> (Vector, Vector) {
+        let origin = ray.origin - self.origin;
+        (
+            Vector::new(
+                origin.dot(&self.u),
+                origin.dot(&self.v),
+                origin.dot(&self.w),
+            ),
+            Vector::new(
+                ray.direction.dot(&self.u),
+                ray.direction.dot(&self.v),
+                ray.direction.dot(&self.w),
+            ),
+        )
+    }
+
+    pub fn to_world(&self, vector: Vector) -> Vector {
+        (self.u * vector.x()) + (self.v * vector.y()) + (self.w * vector.z())
+    }
+}
+
+/// A hit in a [`Frame`] as (ray parameter, normal in the frame, uv)
+pub(crate) type LocalHit = (f64, Vector, (f64, f64));
+
+/// The angle around the w axis, mapped to [0, 1]
+pub(crate) fn angle_coordinate(x: f64, y: f64) -> f64 {
+    0.5 + y.atan2(x) / (2f64 * PI)
+}
+
+/// The derivatives of a point in a [`Frame`] with respect to the angle coordinate and to the
+/// distance from the w axis relative to `radius`, as used for the uv of caps
+pub(crate) fn polar_tangents(point: Vector, radius: f64) -> (Vector, Vector) {
+    let distance = (point.x() * point.x() + point.y() * point.y()).sqrt();
+    let around = Vector::new(-point.y(), point.x(), 0f64) * (2f64 * PI);
+    if distance > 0f64 {
+        (
+            around,
+            Vector::new(point.x(), point.y(), 0f64) * (radius / distance),
+        )
+    } else {
+        (around, Vector::new(0f64, 0f64, 0f64))
+    }
+}
+
+/// Finds the closest of the candidate hits behind `param_min`
+pub(crate) fn closest_hit<I>(candidates: I, param_min: f64) -> Option<LocalHit>
+where
+    I: Iterator<Item = LocalHit>,
+{
+    candidates
+        .filter(|(t, _, _)| *t > param_min)
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+/// The span between the first and the last of the candidate hits of a convex object
+pub(crate) fn convex_span(mut candidates: Vec<LocalHit>) -> Option<(LocalHit, LocalHit)> {
+    if candidates.len() < 2 {
+        return None;
+    }
+    candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    Some((candidates[0], candidates[candidates.len() - 1]))
+}
+
+/// A solid cylinder, closed by a disk at each end.
+///
+/// On the mantle U runs around the axis and V from the base (0) to the top (1).
+/// On the caps V is the distance from the axis relative to the radius.
+pub struct Cylinder {
+    frame: Frame,
+    height: f64,
+    radius: f64,
+    material: Box<dyn Material>,
+}
+
+impl Cylinder {
+    pub fn new(base: Vector, top: Vector, radius: f64, material: Box<dyn Material>) -> Self {
+        Self {
+            frame: Frame::new(base, top - base),
+            height: (top - base).length(),
+            radius: radius,
+            material: material,
+        }
+    }
+
+    /// All points where the line through the ray crosses the surface
+    fn hits(&self, ray: &Ray) -> Vec<LocalHit> {
+        let (origin, direction) = self.frame.to_local(ray);
+        let at = |t: f64| origin + direction * t;
+        let mut candidates = Vec::with_capacity(4);
+
+        // The mantle
+        let a = direction.x() * direction.x() + direction.y() * direction.y();
+        let b = 2f64 * (origin.x() * direction.x() + origin.y() * direction.y());
+        let c = origin.x() * origin.x() + origin.y() * origin.y() - self.radius * self.radius;
+        let discriminant = b * b - 4f64 * a * c;
+        if a > 0f64 && discriminant >= 0f64 {
+            for t in [
+                (-b - discriminant.sqrt()) / (2f64 * a),
+                (-b + discriminant.sqrt()) / (2f64 * a),
+            ]
+            .iter()
+            {
+                let point = at(*t);
+                if point.z() >= 0f64 && point.z() <= self.height {
+                    candidates.push((
+                        *t,
+                        Vector::new(point.x() / self.radius, point.y() / self.radius, 0f64),
+                        (
+                            angle_coordinate(point.x(), point.y()),
+                            point.z() / self.height,
+                        ),
+                    ));
+                }
+            }
+        }
+
+        // The caps
+        if direction.z() != 0f64 {
+            for (z, normal) in [(0f64, -1f64), (self.height, 1f64)].iter() {
+                let t = (z - origin.z()) / direction.z();
+                let point = at(t);
+                let distance = (point.x() * point.x() + point.y() * point.y()).sqrt();
+                if distance <= self.radius {
+                    candidates.push((
+                        t,
+                        Vector::new(0f64, 0f64, *normal),
+                        (
+                            angle_coordinate(point.x(), point.y()),
+                            distance / self.radius,
+                        ),
+                    ));
+                }
+            }
+        }
+
+        candidates
+    }
+
+    fn intersection_at(&self, ray: &Ray, (t, normal, (u, v)): LocalHit) -> Intersection<'_> {
+        let (origin, direction) = self.frame.to_local(ray);
+        let (dpdu, mut dpdv) = polar_tangents(origin + direction * t, self.radius);
+        if normal.z() == 0f64 {
+            // On the mantle v goes up along the axis
+            dpdv = Vector::new(0f64, 0f64, self.height);
+        }
+        Intersection::new(
+            ray.point_at_parameter(t),
+            self as &dyn Object,
+            t,
+            self.frame.to_world(normal),
+        )
+        .with_uv(u, v)
+        .with_tangents(self.frame.to_world(dpdu), self.frame.to_world(dpdv))
+    }
+}
+
+impl Object for Cylinder {
+    fn material(&self) -> &Box<dyn Material> {
+        &self.material
+    }
+}
+
+impl Intersect for Cylinder {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection<'_>> {
+        closest_hit(self.hits(ray).into_iter(), param_min).map(|hit| self.intersection_at(ray, hit))
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        convex_span(self.hits(ray))
+            .map(|(enter, exit)| {
+                Span::new(
+                    self.intersection_at(ray, enter),
+                    self.intersection_at(ray, exit),
+                )
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let top = self.frame.origin + self.frame.w * self.height;
+        Some(
+            disk_bounds(self.frame.origin, self.frame.w, self.radius).union(&disk_bounds(
+                top,
+                self.frame.w,
+                self.radius,
+            )),
+        )
+    }
+}
+
+unsafe impl Sync for Cylinder {}
+
+#[test]
+fn test_intersect() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::primitives::vec::Color;
+
+    // Upright with radius 1 from y = 0 to y = 2
+    let cylinder = Cylinder::new(
+        Vector::new(0f64, 0f64, 0f64),
+        Vector::new(0f64, 2f64, 0f64),
+        1f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    );
+
+    let hit = cylinder
+        .intersect(
+            &Ray::new(Vector::new(5f64, 1.5, 0f64), Vector::new(-1f64, 0f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert!((hit.ray_parameter - 4f64).abs() < 1e-12);
+    assert!((hit.normal - Vector::new(1f64, 0f64, 0f64)).length() < 1e-12);
+    assert!((hit.uv.1 - 0.75).abs() < 1e-12);
+
+    // The top cap
+    let hit = cylinder
+        .intersect(
+            &Ray::new(Vector::new(0.5, 5f64, 0f64), Vector::new(0f64, -1f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert!((hit.ray_parameter - 3f64).abs() < 1e-12);
+    assert!((hit.normal - Vector::new(0f64, 1f64, 0f64)).length() < 1e-12);
+
+    // From the inside the mantle is hit with an outward normal
+    let hit = cylinder
+        .intersect(
+            &Ray::new(Vector::new(0f64, 1f64, 0f64), Vector::new(0f64, 0f64, 1f64)),
+            0.001,
+        )
+        .unwrap();
+    assert!((hit.ray_parameter - 1f64).abs() < 1e-12);
+    assert!((hit.normal - Vector::new(0f64, 0f64, 1f64)).length() < 1e-12);
+
+    // A grazing ray touches the mantle, a slightly offset one misses
+    let hit = cylinder
+        .intersect(
+            &Ray::new(Vector::new(-5f64, 1f64, 1f64), Vector::new(1f64, 0f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert!((hit.ray_parameter - 5f64).abs() < 1e-6);
+    assert!(cylinder
+        .intersect(
+            &Ray::new(Vector::new(-5f64, 1f64, 1.001), Vector::new(1f64, 0f64, 0f64)),
+            0.001
+        )
+        .is_none());
+
+    let bounding_box = cylinder.bounding_box().unwrap();
+    assert!((bounding_box.min - Vector::new(-1f64, 0f64, -1f64)).length() < 1e-12);
+    assert!((bounding_box.max - Vector::new(1f64, 2f64, 1f64)).length() < 1e-12);
+}
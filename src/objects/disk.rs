@@ -0,0 +1,137 @@
+use crate::materials::Material;
+use crate::objects::traits::Intersect;
+use crate::objects::Object;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::Intersection;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+use std::f64::consts::PI;
+
+/// A flat, round disk.
+///
+/// U runs around the center and V from the center (0) to the rim (1).
+pub struct Disk {
+    center: Vector,
+    normal: Vector,
+    radius: f64,
+    tangent_u: Vector,
+    tangent_v: Vector,
+    material: Box<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Vector, normal: Vector, radius: f64, material: Box<dyn Material>) -> Self {
+        let normal = normal.normalize();
+        let (tangent_u, tangent_v) = normal.orthonormal_basis();
+        Self {
+            center: center,
+            normal: normal,
+            radius: radius,
+            tangent_u: tangent_u,
+            tangent_v: tangent_v,
+            material: material,
+        }
+    }
+}
+
+/// The bounding box of a disk, which is thin along the normal
+pub(crate) fn disk_bounds(center: Vector, normal: Vector, radius: f64) -> Aabb {
+    let extent = Vector::new(
+        radius * (1f64 - normal.x() * normal.x()).max(0f64).sqrt(),
+        radius * (1f64 - normal.y() * normal.y()).max(0f64).sqrt(),
+        radius * (1f64 - normal.z() * normal.z()).max(0f64).sqrt(),
+    );
+    Aabb::new(center - extent, center + extent)
+}
+
+impl Object for Disk {
+    fn material(&self) -> &Box<dyn Material> {
+        &self.material
+    }
+}
+
+impl Intersect for Disk {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection<'_>> {
+        let denominator = ray.direction.dot(&self.normal);
+        if denominator == 0f64 {
+            return None;
+        }
+        let t = (self.center - ray.origin).dot(&self.normal) / denominator;
+        if t <= param_min {
+            return None;
+        }
+        let position = ray.point_at_parameter(t);
+        let offset = position - self.center;
+        let distance = offset.length();
+        if distance > self.radius {
+            return None;
+        }
+        let angle = offset.dot(&self.tangent_v).atan2(offset.dot(&self.tangent_u));
+        let (sin, cos) = angle.sin_cos();
+        let radial = (self.tangent_u * cos) + (self.tangent_v * sin);
+        let around = (self.tangent_v * cos) - (self.tangent_u * sin);
+        Some(
+            Intersection::new(position, self as &dyn Object, t, self.normal)
+                .with_uv(0.5 + angle / (2f64 * PI), distance / self.radius)
+                .with_tangents(around * (2f64 * PI * distance), radial * self.radius),
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(disk_bounds(self.center, self.normal, self.radius))
+    }
+
+    fn sample_surface(&self, u1: f64, u2: f64, time: f64) -> Option<(Intersection<'_>, f64)> {
+        let distance = self.radius * u1.sqrt();
+        let (sin, cos) = (2f64 * PI * u2).sin_cos();
+        let point = self.center + (self.tangent_u * (distance * cos)) + (self.tangent_v * (distance * sin));
+        let ray = Ray::new_at_time(point + self.normal, -self.normal, time);
+        let intersection = self.intersect(&ray, 0f64)?;
+        Some((intersection, PI * self.radius * self.radius))
+    }
+}
+
+unsafe impl Sync for Disk {}
+
+#[test]
+fn test_intersect() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::primitives::vec::Color;
+
+    let disk = Disk::new(
+        Vector::new(0f64, 0f64, -5f64),
+        Vector::new(0f64, 0f64, 1f64),
+        2f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    );
+
+    let hit = disk
+        .intersect(
+            &Ray::new(Vector::new(1f64, 0f64, 0f64), Vector::new(0f64, 0f64, -1f64)),
+            0.001,
+        )
+        .unwrap();
+    assert_eq!(hit.ray_parameter, 5f64);
+    assert_eq!(hit.normal, Vector::new(0f64, 0f64, 1f64));
+    assert_eq!(hit.uv.1, 0.5);
+
+    // Outside of the rim
+    assert!(disk
+        .intersect(
+            &Ray::new(Vector::new(2.1, 0f64, 0f64), Vector::new(0f64, 0f64, -1f64)),
+            0.001
+        )
+        .is_none());
+    // Grazing along the disk plane
+    assert!(disk
+        .intersect(
+            &Ray::new(Vector::new(-5f64, 0f64, -5f64), Vector::new(1f64, 0f64, 0f64)),
+            0.001
+        )
+        .is_none());
+
+    let bounding_box = disk.bounding_box().unwrap();
+    assert_eq!(bounding_box.min, Vector::new(-2f64, -2f64, -5f64));
+    assert_eq!(bounding_box.max, Vector::new(2f64, 2f64, -5f64));
+}
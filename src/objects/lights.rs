@@ -0,0 +1,143 @@
+use crate::objects::Object;
+use crate::primitives::distribution::Distribution1D;
+use crate::primitives::intersection::Intersection;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::{Color, Vector};
+use crate::spectrum::Channels;
+use crate::primitives::sampler;
+
+use rand::Rng;
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+/// How many points of every surface are checked for emission
+const EMISSION_SAMPLES: usize = 4;
+
+/// The emissive objects of a scene that paths can start from, picked proportionally to their power.
+///
+/// Only objects that support [`sample_surface`](crate::objects::traits::Intersect::sample_surface)
+/// are found, by checking a few points of their surface for emission.
+pub(crate) struct Lights {
+    /// The index of every light in the objects of the scene, with its surface area
+    lights: Vec<(usize, f64)>,
+    distribution: Option<Distribution1D>,
+    /// The position in `lights` of every light by the address of its object
+    by_address: HashMap<usize, usize>,
+}
+
+impl Lights {
+    pub(crate) fn new(objects: &[Box<dyn Object + Sync>]) -> Self {
+        let mut lights = Vec::new();
+        let mut powers = Vec::new();
+        let mut by_address = HashMap::new();
+        for (i, object) in objects.iter().enumerate() {
+            let mut emission = 0f64;
+            let mut area = 0f64;
+            for j in 0..EMISSION_SAMPLES * EMISSION_SAMPLES {
+                let u1 = ((j / EMISSION_SAMPLES) as f64 + 0.5) / EMISSION_SAMPLES as f64;
+                let u2 = ((j % EMISSION_SAMPLES) as f64 + 0.5) / EMISSION_SAMPLES as f64;
+                if let Some((intersection, surface_area)) = object.sample_surface(u1, u2, 0f64) {
+                    emission += emitted(&intersection, &Channels::Rgb).luminance().max(0f64);
+                    area = surface_area;
+                }
+            }
+            if emission > 0f64 && area > 0f64 {
+                by_address.insert(address(object.as_ref()), lights.len());
+                lights.push((i, area));
+                powers.push(emission * area);
+            }
+        }
+        Self {
+            lights: lights,
+            distribution: if powers.is_empty() {
+                None
+            } else {
+                Some(Distribution1D::new(powers))
+            },
+            by_address: by_address,
+        }
+    }
+
+    /// Picks a light with the uniform random number `u`.
+    ///
+    /// Returns the index of its object in the scene and the probability of picking it, or `None`
+    /// if there are no lights.
+    pub(crate) fn sample(&self, u: f64) -> Option<(usize, f64)> {
+        let distribution = self.distribution.as_ref()?;
+        let (_, pdf, offset) = distribution.sample(u);
+        Some((self.lights[offset].0, pdf / distribution.count() as f64))
+    }
+
+    /// The probability density, per area, of starting a path at a point of `object`
+    pub(crate) fn pdf(&self, object: &dyn Object) -> f64 {
+        match (&self.distribution, self.by_address.get(&address(object))) {
+            (Some(distribution), Some(light)) => {
+                let probability = distribution.pdf((*light as f64 + 0.5) / distribution.count() as f64)
+                    / distribution.count() as f64;
+                probability / self.lights[*light].1
+            }
+            _ => 0f64,
+        }
+    }
+}
+
+/// The radiance that the surface emits at an intersection, which is the same in all directions
+pub(crate) fn emitted(intersection: &Intersection, channels: &Channels) -> Color {
+    let material = intersection.object.material();
+    // Look at the point head on
+    let ray = Ray::new_at_time(
+        intersection.position + intersection.normal,
+        -intersection.normal,
+        0f64,
+    );
+    let scatter = material.scatter_channels(&ray, intersection, channels);
+    material.emission(&scatter, channels)
+}
+
+/// Samples a direction that a surface with the geometric `normal` emits light in. Emissive surfaces
+/// emit from both sides.
+///
+/// Returns the unit direction and its probability density per solid angle.
+pub(crate) fn sample_emission(normal: Vector) -> (Vector, f64) {
+    let side = if sampler::rng().gen::<f64>() < 0.5 { normal } else { -normal };
+    let direction = (side + Vector::random_on_unit_sphere()).normalize();
+    (direction, emission_pdf(normal, direction))
+}
+
+/// The probability density, per solid angle, with which [`sample_emission`] returns the unit vector `direction`
+pub(crate) fn emission_pdf(normal: Vector, direction: Vector) -> f64 {
+    normal.dot(&direction).abs() / (2f64 * PI)
+}
+
+/// Identifies an object by its address
+fn address(object: &dyn Object) -> usize {
+    object as *const dyn Object as *const () as usize
+}
+
+#[test]
+fn test_lights() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::objects::sphere::Sphere;
+    use crate::primitives::vec::Vector;
+
+    let material = |radiation: f64| {
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::from_linear(radiation, radiation, radiation)))
+    };
+    let objects: Vec<Box<dyn Object + Sync>> = vec![
+        Box::new(Sphere::new(Vector::new(0f64, 0f64, 0f64), 1f64, material(0f64))),
+        Box::new(Sphere::new(Vector::new(3f64, 0f64, 0f64), 1f64, material(1f64))),
+        Box::new(Sphere::new(Vector::new(6f64, 0f64, 0f64), 2f64, material(1f64))),
+    ];
+    let lights = Lights::new(&objects);
+
+    // The larger light has four times the power
+    assert_eq!(lights.pdf(objects[0].as_ref()), 0f64);
+    let area = 4f64 * std::f64::consts::PI;
+    assert!((lights.pdf(objects[1].as_ref()) - 0.2 / area).abs() < 1e-9);
+    assert!((lights.pdf(objects[2].as_ref()) - 0.8 / (4f64 * area)).abs() < 1e-9);
+    let (light, probability) = lights.sample(0.1).unwrap();
+    assert_eq!(light, 1);
+    assert!((probability - 0.2).abs() < 1e-9);
+    assert_eq!(lights.sample(0.5).map(|(i, _)| i), Some(2));
+}
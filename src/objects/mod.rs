@@ -1,10 +1,28 @@
 pub mod sphere;
 pub mod traits;
 pub mod scene;
+pub mod motion;
+pub mod bvh;
+pub mod transformed;
+pub mod plane;
+pub mod disk;
+pub mod axis_aligned_box;
+pub mod cylinder;
+pub mod cone;
+pub mod csg;
+pub mod sdf;
+pub mod volume;
+pub mod lights;
 
 use crate::materials::Material;
+use crate::media::Medium;
 use crate::objects::traits::Intersect;
 
 pub trait Object: Intersect {
     fn material(&self) -> &Box<dyn Material>;
+
+    /// The medium filling the inside of the object, if it is a closed surface around one
+    fn medium(&self) -> Option<&dyn Medium> {
+        None
+    }
 }
\ No newline at end of file
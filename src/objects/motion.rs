@@ -0,0 +1,172 @@
+use crate::materials::Material;
+use crate::objects::traits::Intersect;
+use crate::objects::Object;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::{Intersection, Span};
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+/// The translation of an object at one point in time
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    pub time: f64,
+    pub offset: Vector,
+}
+
+impl Keyframe {
+    pub fn new(time: f64, offset: Vector) -> Self {
+        Self {
+            time: time,
+            offset: offset,
+        }
+    }
+}
+
+/// A translation over time that is interpolated linearly between keyframes.
+///
+/// Before the first and after the last keyframe the object rests at the respective offset.
+#[derive(Debug, Clone)]
+pub struct Motion {
+    keyframes: Vec<Keyframe>,
+}
+
+impl Motion {
+    /// An object that does not move
+    pub fn none() -> Self {
+        Self {
+            keyframes: Vec::new(),
+        }
+    }
+
+    /// Moves from `start_offset` at `start_time` to `end_offset` at `end_time`
+    pub fn linear(start_time: f64, start_offset: Vector, end_time: f64, end_offset: Vector) -> Self {
+        Self::keyframed(vec![
+            Keyframe::new(start_time, start_offset),
+            Keyframe::new(end_time, end_offset),
+        ])
+    }
+
+    pub fn keyframed(mut keyframes: Vec<Keyframe>) -> Self {
+        keyframes.sort_by(|a, b| {
+            a.time
+                .partial_cmp(&b.time)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Self {
+            keyframes: keyframes,
+        }
+    }
+
+    pub fn offset_at(&self, time: f64) -> Vector {
+        match self.keyframes.iter().position(|keyframe| keyframe.time > time) {
+            None => self
+                .keyframes
+                .last()
+                .map(|keyframe| keyframe.offset)
+                .unwrap_or_else(|| Vector::new(0f64, 0f64, 0f64)),
+            Some(0) => self.keyframes[0].offset,
+            Some(i) => {
+                let before = self.keyframes[i - 1];
+                let after = self.keyframes[i];
+                let fraction = (time - before.time) / (after.time - before.time);
+                before.offset + (after.offset - before.offset) * fraction
+            }
+        }
+    }
+
+    /// Grows a bounding box of the resting object so that it covers the whole motion.
+    ///
+    /// Since the motion is linear between keyframes, the boxes at the keyframes suffice.
+    pub fn sweep(&self, bounding_box: &Aabb) -> Aabb {
+        self.keyframes
+            .iter()
+            .fold(None, |swept: Option<Aabb>, keyframe| {
+                let moved = bounding_box.translate(keyframe.offset);
+                Some(swept.map_or(moved, |swept| swept.union(&moved)))
+            })
+            .unwrap_or(*bounding_box)
+    }
+}
+
+/// Any object that moves along a [`Motion`] during the shutter interval
+pub struct Moving {
+    object: Box<dyn Object>,
+    motion: Motion,
+}
+
+impl Moving {
+    pub fn new(object: Box<dyn Object>, motion: Motion) -> Self {
+        Self {
+            object: object,
+            motion: motion,
+        }
+    }
+}
+
+impl Moving {
+    fn to_local(ray: &Ray, offset: Vector) -> Ray {
+        Ray::new_at_time(ray.origin - offset, ray.direction, ray.time)
+    }
+
+    fn to_world(mut intersection: Intersection, offset: Vector) -> Intersection {
+        intersection.position = intersection.position + offset;
+        intersection
+    }
+}
+
+impl Object for Moving {
+    fn material(&self) -> &Box<dyn Material> {
+        self.object.material()
+    }
+}
+
+impl Intersect for Moving {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection<'_>> {
+        let offset = self.motion.offset_at(ray.time);
+        self.object
+            .intersect(&Self::to_local(ray, offset), param_min)
+            .map(|intersection| Self::to_world(intersection, offset))
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        let offset = self.motion.offset_at(ray.time);
+        self.object
+            .spans(&Self::to_local(ray, offset))
+            .into_iter()
+            .map(|span| {
+                Span::new(
+                    Self::to_world(span.enter, offset),
+                    Self::to_world(span.exit, offset),
+                )
+            })
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.object
+            .bounding_box()
+            .map(|bounding_box| self.motion.sweep(&bounding_box))
+    }
+}
+
+unsafe impl Sync for Moving {}
+
+#[test]
+fn test_keyframes() {
+    let motion = Motion::keyframed(vec![
+        Keyframe::new(1f64, Vector::new(2f64, 0f64, 0f64)),
+        Keyframe::new(0f64, Vector::new(0f64, 0f64, 0f64)),
+        Keyframe::new(2f64, Vector::new(2f64, 4f64, 0f64)),
+    ]);
+    assert_eq!(motion.offset_at(-1f64), Vector::new(0f64, 0f64, 0f64));
+    assert_eq!(motion.offset_at(0.5), Vector::new(1f64, 0f64, 0f64));
+    assert_eq!(motion.offset_at(1.5), Vector::new(2f64, 2f64, 0f64));
+    assert_eq!(motion.offset_at(3f64), Vector::new(2f64, 4f64, 0f64));
+
+    let swept = motion.sweep(&Aabb::new(
+        Vector::new(-1f64, -1f64, -1f64),
+        Vector::new(1f64, 1f64, 1f64),
+    ));
+    assert_eq!(swept.min, Vector::new(-1f64, -1f64, -1f64));
+    assert_eq!(swept.max, Vector::new(3f64, 5f64, 1f64));
+}
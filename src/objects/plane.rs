@@ -0,0 +1,134 @@
+use crate::materials::Material;
+use crate::objects::traits::Intersect;
+use crate::objects::Object;
+use crate::primitives::intersection::{Intersection, Span};
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+/// An infinite plane through `point`.
+///
+/// As a solid it is the half-space behind the plane, i.e. opposite to the normal.
+/// The UV coordinates are world space distances from `point` along two tangents of the plane.
+pub struct Plane {
+    point: Vector,
+    normal: Vector,
+    tangent_u: Vector,
+    tangent_v: Vector,
+    material: Box<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Vector, normal: Vector, material: Box<dyn Material>) -> Self {
+        let normal = normal.normalize();
+        let (tangent_u, tangent_v) = normal.orthonormal_basis();
+        Self {
+            point: point,
+            normal: normal,
+            tangent_u: tangent_u,
+            tangent_v: tangent_v,
+            material: material,
+        }
+    }
+}
+
+impl Plane {
+    fn intersection_at(&self, ray: &Ray, t: f64) -> Intersection<'_> {
+        let position = ray.point_at_parameter(t);
+        let offset = position - self.point;
+        Intersection::new(position, self as &dyn Object, t, self.normal)
+            .with_uv(offset.dot(&self.tangent_u), offset.dot(&self.tangent_v))
+            .with_tangents(self.tangent_u, self.tangent_v)
+    }
+}
+
+impl Object for Plane {
+    fn material(&self) -> &Box<dyn Material> {
+        &self.material
+    }
+}
+
+impl Intersect for Plane {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection<'_>> {
+        let denominator = ray.direction.dot(&self.normal);
+        if denominator == 0f64 {
+            // The ray runs parallel to the plane
+            return None;
+        }
+        let t = (self.point - ray.origin).dot(&self.normal) / denominator;
+        if t > param_min {
+            Some(self.intersection_at(ray, t))
+        } else {
+            None
+        }
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        let denominator = ray.direction.dot(&self.normal);
+        let behind = (ray.origin - self.point).dot(&self.normal) < 0f64;
+        let (enter, exit) = if denominator == 0f64 {
+            if !behind {
+                return Vec::new();
+            }
+            (f64::NEG_INFINITY, f64::INFINITY)
+        } else {
+            let t = (self.point - ray.origin).dot(&self.normal) / denominator;
+            if denominator > 0f64 {
+                (f64::NEG_INFINITY, t)
+            } else {
+                (t, f64::INFINITY)
+            }
+        };
+        vec![Span::new(
+            self.intersection_at(ray, enter),
+            self.intersection_at(ray, exit),
+        )]
+    }
+}
+
+unsafe impl Sync for Plane {}
+
+#[test]
+fn test_intersect() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::primitives::vec::Color;
+
+    let plane = Plane::new(
+        Vector::new(0f64, -1f64, 0f64),
+        Vector::new(0f64, 2f64, 0f64),
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    );
+
+    let hit = plane
+        .intersect(
+            &Ray::new(Vector::new(3f64, 1f64, 0f64), Vector::new(0f64, -1f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert_eq!(hit.ray_parameter, 2f64);
+    assert_eq!(hit.position, Vector::new(3f64, -1f64, 0f64));
+    assert_eq!(hit.normal, Vector::new(0f64, 1f64, 0f64));
+    assert!(((hit.uv.0 * hit.uv.0 + hit.uv.1 * hit.uv.1).sqrt() - 3f64).abs() < 1e-12);
+
+    // Rays from below see the same normal
+    let hit = plane
+        .intersect(
+            &Ray::new(Vector::new(0f64, -5f64, 0f64), Vector::new(0f64, 1f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert_eq!(hit.normal, Vector::new(0f64, 1f64, 0f64));
+
+    // Grazing rays parallel to the plane and rays pointing away miss
+    assert!(plane
+        .intersect(
+            &Ray::new(Vector::new(0f64, -1f64, 0f64), Vector::new(1f64, 0f64, 0f64)),
+            0.001
+        )
+        .is_none());
+    assert!(plane
+        .intersect(
+            &Ray::new(Vector::new(0f64, 1f64, 0f64), Vector::new(1f64, 1f64, 0f64)),
+            0.001
+        )
+        .is_none());
+}
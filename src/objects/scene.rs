@@ -6,16 +6,64 @@ use std::cmp::Ordering::Equal;
 use crate::objects::Object;
 use crate::materials::Material;
 use crate::cameras::Camera;
+use crate::objects::bvh::Bvh;
+use crate::objects::lights::Lights;
+use crate::environment::Environment;
+use crate::media::Medium;
+use crate::primitives::vec::Vector;
+use crate::primitives::distribution::power_heuristic;
+use crate::spectrum::{Channels, Wavelengths};
+use crate::primitives::sampler;
+use crate::renderer::stats::{self, Counter};
+use rand::Rng;
+use std::sync::OnceLock;
 
 
 
-const EPSILON: f64 = f64::MIN_POSITIVE *10000f64;
+pub(crate) const EPSILON: f64 = f64::MIN_POSITIVE *10000f64;
+/// The most surfaces bounding media that a shadow ray passes
+const MAX_BOUNDARIES: usize = 64;
 
 pub struct Scene<'a> {
     objects: Vec<Box<dyn Object + Sync>>,
     pub camera: &'a (dyn Camera + Sync),
-    pub sky_color: Color,
+    sky_color: Color,
+    environment: Box<dyn Environment>,
     ray_shooting_offset: f64,
+    medium: Option<Box<dyn Medium>>,
+    spectral: bool,
+    acceleration: OnceLock<Acceleration>,
+    lights: OnceLock<Lights>,
+}
+
+/// The media of the closed objects that a path is inside of, innermost last, together with the
+/// addresses of the objects to recognize them when the path leaves
+pub(crate) type MediumStack<'s> = Vec<(usize, &'s dyn Medium)>;
+
+/// What a path carries from one ray to the next
+#[derive(Clone)]
+pub(crate) struct PathState<'s> {
+    pub(crate) media: MediumStack<'s>,
+    /// The weight of the light that the path carries up to the ray
+    pub(crate) throughput: Color,
+    /// What the color channels along the path stand for
+    pub(crate) channels: Channels,
+}
+
+impl<'s> PathState<'s> {
+    pub(crate) fn attenuate(&self, factor: Color) -> Self {
+        Self {
+            media: self.media.clone(),
+            throughput: self.throughput * factor,
+            channels: self.channels,
+        }
+    }
+}
+
+/// The bounding volume hierarchy over all bounded objects and the indices of all unbounded ones
+struct Acceleration {
+    bvh: Bvh,
+    unbounded: Vec<usize>,
 }
 
 impl<'a> Scene<'a> {
@@ -25,40 +73,499 @@ impl<'a> Scene<'a> {
             objects: Vec::new(),
             camera: camera,
             sky_color: sky_color,
+            environment: Box::new(sky_color),
             ray_shooting_offset: ray_shooting_offset,
+            medium: None,
+            spectral: false,
+            acceleration: OnceLock::new(),
+            lights: OnceLock::new(),
         }
     }
 
     pub fn add_object(&mut self, obj: Box<dyn Object + Sync>) {
         self.objects.push(obj);
+        self.acceleration = OnceLock::new();
+        self.lights = OnceLock::new();
+    }
+
+    /// The uniform sky color that the scene was created with. It only lights the scene until
+    /// [`set_environment`](Scene::set_environment) replaces it.
+    pub fn sky_color(&self) -> Color {
+        self.sky_color
     }
 
+    /// Replaces the uniform sky color with another environment, e.g. an HDR environment map or a
+    /// daylight sky with the sun behind the camera:
+    ///
+    /// ```ignore
+    /// scene.set_environment(Box::new(PhysicalSky::new(0.6, 2.5, 3.)));
+    /// ```
+    pub fn set_environment(&mut self, environment: Box<dyn Environment>) {
+        self.environment = environment;
+    }
+
+    /// Fills all space outside of closed objects with `medium`, like fog or haze.
+    ///
+    /// An unbounded medium absorbs or scatters all light from the environment, so such scenes have
+    /// to be lit by emissive objects.
+    pub fn set_medium(&mut self, medium: Box<dyn Medium>) {
+        self.medium = Some(medium);
+    }
+
+    /// Renders with sampled wavelengths instead of RGB, so that the colors of lights and
+    /// surfaces interact like spectra. Colors are upsampled to smooth spectra.
+    ///
+    /// ```ignore
+    /// scene.set_spectral(true);
+    /// ```
+    pub fn set_spectral(&mut self, spectral: bool) {
+        self.spectral = spectral;
+    }
+
+    /// The linear sRGB radiance arriving along the ray
     pub fn trace_ray(&self, ray: &Ray, max_depth: u64) -> Color {
+        stats::count(Counter::Paths, 1);
+        let channels = self.sample_channels();
+        let path = PathState {
+            media: Vec::new(),
+            throughput: Color::WHITE,
+            channels: channels,
+        };
+        channels.to_rgb(self.trace(ray, max_depth, None, &path))
+    }
+
+    /// What the color channels of a new path stand for
+    pub(crate) fn sample_channels(&self) -> Channels {
+        if self.spectral {
+            Channels::Spectral(Wavelengths::sample(sampler::rng().gen()))
+        } else {
+            Channels::Rgb
+        }
+    }
+
+    pub(crate) fn environment(&self) -> &dyn Environment {
+        self.environment.as_ref()
+    }
+
+    /// The emissive objects that paths can start from
+    pub(crate) fn lights(&self) -> &Lights {
+        self.lights.get_or_init(|| Lights::new(&self.objects))
+    }
+
+    /// A random point on an emissive object at `time` and its probability density per area
+    pub(crate) fn sample_light(&self, time: f64) -> Option<(Intersection<'_>, f64)> {
+        let mut rng = sampler::rng();
+        let (index, probability) = self.lights().sample(rng.gen())?;
+        let (intersection, area) = self.objects[index].sample_surface(rng.gen(), rng.gen(), time)?;
+        Some((intersection, probability / area))
+    }
+
+    /// `scattering_pdf` is the probability density with which the ray was sampled from a Lambertian
+    /// lobe or a phase function, if it was, so that light reaching it from the environment is weighted
+    /// against next event estimation.
+    fn trace(&self, ray: &Ray, max_depth: u64, scattering_pdf: Option<f64>, path: &PathState) -> Color {
         if max_depth == 0 {
             //println!("Depth exhaustion");
-            Color::BLACK
-        } else {
-            if let Some(intersection) = self.shoot_ray(ray) {
-                let (recursive_ray, reflective_color, additive_color) =
-                    intersection.object.material().scatter(ray, &intersection);
-                let recursive_ray = Ray::new(recursive_ray.origin + recursive_ray.direction* EPSILON, recursive_ray.direction);
-                let color = additive_color + (reflective_color * self.trace_ray(&recursive_ray, max_depth - 1));
-                //println!("Depth: {}, Color: {:?}", max_depth, color);
-                color
+            return Color::BLACK;
+        }
+        let channels = &path.channels;
+        let hit = self.shoot_ray(ray);
+
+        // Light may scatter in the medium before it reaches the surface
+        let mut transmission = Color::WHITE;
+        if let Some(medium) = self.current_medium(&path.media) {
+            let t_max = hit.as_ref().map_or(f64::INFINITY, |intersection| intersection.ray_parameter);
+            let (scattered, weight) = medium.sample(ray, t_max, path.throughput, channels);
+            if let Some(t) = scattered {
+                return weight * self.scatter_in_medium(ray, t, medium, max_depth, &path.attenuate(weight));
+            }
+            transmission = weight;
+        }
+        if transmission == Color::BLACK {
+            return Color::BLACK;
+        }
+
+        let color = if let Some(intersection) = hit {
+            let material = intersection.object.material();
+            let scatter = material.scatter_channels(ray, &intersection, channels);
+            let emission = material.emission(&scatter, channels);
+            // Everything that the scatter decided for the hero wavelength holds for it alone
+            let (next_channels, dispersion) = if scatter.dispersed {
+                channels.terminate_secondary()
             } else {
-                self.sky_color
+                (*channels, Color::WHITE)
+            };
+            let attenuation = channels.reflectance(scatter.attenuation) * dispersion;
+            let mut next_path = path.attenuate(transmission * attenuation);
+            next_path.channels = next_channels;
+            if let Some(media) = Self::cross(&path.media, &intersection, ray.direction, scatter.ray.direction) {
+                next_path.media = media;
+            }
+            let recursive_ray = Ray::new_at_time(scatter.ray.origin + scatter.ray.direction* EPSILON, scatter.ray.direction, ray.time);
+            if material.is_boundary() {
+                // Passing the boundary of a medium is not a bounce
+                return transmission * (emission
+                    + attenuation * self.trace(&recursive_ray, max_depth, scattering_pdf, &next_path));
+            }
+            stats::count(Counter::Bounces, 1);
+            // Only sample the environment directly if the recursive ray can still reach it
+            let direct = match &scatter.diffuse {
+                Some(diffuse) if max_depth > 1 => {
+                    self.sample_environment(intersection.position, path, ray.time, |direction| {
+                        // The light has to arrive on the side of the lobe, also with respect to the geometry
+                        let geometric_side = intersection.normal.dot(&direction) * intersection.normal.dot(&diffuse.normal);
+                        if geometric_side <= 0f64 {
+                            return None;
+                        }
+                        Some((channels.reflectance(diffuse.eval(direction)) * dispersion, diffuse.pdf(direction)))
+                    })
+                }
+                _ => Color::BLACK,
+            };
+            let recursive_pdf = match &scatter.diffuse {
+                Some(diffuse) if scatter.sampled_diffuse => {
+                    Some(diffuse.pdf(recursive_ray.direction.normalize()))
+                }
+                _ => None,
+            };
+            let color = emission
+                + direct
+                + (attenuation * self.trace(&recursive_ray, max_depth - 1, recursive_pdf, &next_path));
+            //println!("Depth: {}, Color: {:?}", max_depth, color);
+            color
+        } else {
+            let direction = ray.direction.normalize();
+            let radiance = channels.illuminant(self.environment.radiance(direction));
+            match scattering_pdf {
+                Some(pdf) => radiance * power_heuristic(pdf, self.environment.pdf(direction)),
+                None => radiance,
+            }
+        };
+        transmission * color
+    }
+
+    /// Continues a path that scatters in `medium` at the ray parameter `t`
+    fn scatter_in_medium(&self, ray: &Ray, t: f64, medium: &dyn Medium, max_depth: u64, path: &PathState) -> Color {
+        stats::count(Counter::Bounces, 1);
+        let mut rng = sampler::rng();
+        let position = ray.point_at_parameter(t);
+        let incoming = ray.direction.normalize();
+        let phase = medium.phase();
+        let direct = if max_depth > 1 {
+            self.sample_environment(position, path, ray.time, |direction| {
+                let pdf = phase.pdf(incoming, direction);
+                Some((Color::WHITE * pdf, pdf))
+            })
+        } else {
+            Color::BLACK
+        };
+        let direction = phase.sample(incoming, rng.gen(), rng.gen());
+        let recursive_ray = Ray::new_at_time(position, direction, ray.time);
+        direct + self.trace(&recursive_ray, max_depth - 1, Some(phase.pdf(incoming, direction)), path)
+    }
+
+    /// Next event estimation: the light arriving at `position` directly from the environment.
+    ///
+    /// `lobe` gives the scattering function times the cosine, if any, and the probability density
+    /// of sampling a unit direction, or `None` if light cannot arrive from it.
+    pub(crate) fn sample_environment<F>(&self, position: Vector, path: &PathState, time: f64, lobe: F) -> Color
+    where
+        F: Fn(Vector) -> Option<(Color, f64)>,
+    {
+        let mut rng = sampler::rng();
+        let (direction, radiance, pdf) = match self.environment.sample(rng.gen(), rng.gen()) {
+            Some(sample) => sample,
+            None => return Color::BLACK,
+        };
+        let (value, lobe_pdf) = match lobe(direction) {
+            Some(lobe) => lobe,
+            None => return Color::BLACK,
+        };
+        if pdf <= 0f64 || lobe_pdf == 0f64 {
+            return Color::BLACK;
+        }
+        let shadow_ray = Ray::new_at_time(position + direction * EPSILON, direction, time);
+        let transmittance = self.transmittance(&shadow_ray, f64::INFINITY, path);
+        if transmittance == Color::BLACK {
+            return Color::BLACK;
+        }
+        let radiance = path.channels.illuminant(radiance);
+        value * radiance * transmittance * (power_heuristic(pdf, lobe_pdf) / pdf)
+    }
+
+    /// The fraction of light that reaches the origin of the ray from the ray parameter `t_max`, which
+    /// is infinite for light from the environment, passing through media and the surfaces bounding
+    /// them but blocked by any other surface
+    pub(crate) fn transmittance(&self, ray: &Ray, t_max: f64, path: &PathState) -> Color {
+        let mut ray = *ray;
+        let mut t_max = t_max;
+        let mut media = path.media.clone();
+        let mut transmittance = Color::WHITE;
+        // Bounds the number of boundaries passed, in case a ray gets stuck on one
+        for _ in 0..MAX_BOUNDARIES {
+            let hit = self
+                .shoot_ray(&ray)
+                .filter(|intersection| intersection.ray_parameter < t_max);
+            if let Some(medium) = self.current_medium(&media) {
+                let t_end = hit.as_ref().map_or(t_max, |intersection| intersection.ray_parameter);
+                transmittance = transmittance * medium.transmittance(&ray, t_end, &path.channels);
+            }
+            match hit {
+                None => return transmittance,
+                Some(intersection) if intersection.object.material().is_boundary() => {
+                    if let Some(crossed) = Self::cross(&media, &intersection, ray.direction, ray.direction) {
+                        media = crossed;
+                    }
+                    t_max -= intersection.ray_parameter + EPSILON;
+                    ray = Ray::new_at_time(intersection.position + ray.direction * EPSILON, ray.direction, ray.time);
+                }
+                Some(_) => return Color::BLACK,
             }
         }
+        Color::BLACK
+    }
+
+    /// The medium that a ray inside of `media` travels through
+    pub(crate) fn current_medium<'s>(&'s self, media: &MediumStack<'s>) -> Option<&'s dyn Medium> {
+        media
+            .last()
+            .map(|(_, medium)| *medium)
+            .or(self.medium.as_deref())
+    }
+
+    /// The media after a ray with direction `incoming` left the surface of `intersection` in
+    /// direction `outgoing`, or `None` if they stay the same
+    pub(crate) fn cross<'s>(
+        media: &MediumStack<'s>,
+        intersection: &Intersection<'s>,
+        incoming: Vector,
+        outgoing: Vector,
+    ) -> Option<MediumStack<'s>> {
+        let medium = intersection.object.medium()?;
+        let normal = intersection.normal;
+        // Only rays that pass through the surface change the medium
+        if normal.dot(&incoming) * normal.dot(&outgoing) <= 0f64 {
+            return None;
+        }
+        let id = intersection.object as *const dyn Object as *const () as usize;
+        let mut media = media.clone();
+        media.retain(|(other, _)| *other != id);
+        if normal.dot(&outgoing) < 0f64 {
+            media.push((id, medium));
+        }
+        Some(media)
     }
 
-    fn shoot_ray(&self, ray: &Ray) -> Option<Intersection> {
+    /// The index of a top level object of the scene
+    pub(crate) fn object_index(&self, object: &dyn Object) -> Option<usize> {
         self.objects
             .iter()
-            .filter_map(|obj| obj.intersect(&ray, self.ray_shooting_offset))
+            .position(|other| std::ptr::addr_eq(other.as_ref() as *const dyn Object, object as *const dyn Object))
+    }
+
+    pub(crate) fn shoot_ray(&self, ray: &Ray) -> Option<Intersection> {
+        let acceleration = self.acceleration.get_or_init(|| self.build_acceleration());
+        stats::count(Counter::Rays, 1);
+        stats::count(Counter::IntersectionTests, acceleration.unbounded.len() as u64);
+        let bounded = acceleration.bvh.intersect(ray, self.ray_shooting_offset, |i| {
+            stats::count(Counter::IntersectionTests, 1);
+            self.objects[i].intersect(ray, self.ray_shooting_offset)
+        });
+        acceleration
+            .unbounded
+            .iter()
+            .filter_map(|i| self.objects[*i].intersect(ray, self.ray_shooting_offset))
+            .chain(bounded)
             .min_by(|a, b| {
                 a.ray_parameter
                     .partial_cmp(&b.ray_parameter)
                     .unwrap_or(Equal)
             })
     }
+
+    fn build_acceleration(&self) -> Acceleration {
+        let mut bounded = Vec::new();
+        let mut unbounded = Vec::new();
+        for (i, obj) in self.objects.iter().enumerate() {
+            match obj.bounding_box() {
+                Some(bounding_box) => bounded.push((i, bounding_box)),
+                None => unbounded.push(i),
+            }
+        }
+        Acceleration {
+            bvh: Bvh::new(bounded),
+            unbounded: unbounded,
+        }
+    }
+}
+
+#[test]
+fn test_furnace() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::environment::map::EnvironmentMap;
+    use crate::materials::phong::PseudoPhong;
+    use crate::objects::sphere::Sphere;
+    use crate::primitives::vec::Vector;
+
+    let camera = test_camera();
+    let white = Color::from_linear(1f64, 1f64, 1f64);
+    let mut scene = Scene::new(&camera, white, 0.0001);
+    scene.add_object(Box::new(Sphere::new(
+        Vector::new(0f64, 0f64, -3f64),
+        1f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::from_linear(0.5, 0.5, 0.5), Color::BLACK)),
+    )));
+
+    // A convex diffuse object in a uniform environment reflects the environment times its
+    // reflectance, with or without sampling the environment directly
+    let ray = Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0.1, 0.2, -1f64));
+    let average = |scene: &Scene| {
+        let samples = 20000;
+        (0..samples)
+            .map(|_| scene.trace_ray(&ray, 5).r())
+            .sum::<f64>()
+            / samples as f64
+    };
+    assert!((average(&scene) - 0.5).abs() < 0.02);
+    scene.set_environment(Box::new(EnvironmentMap::new(4, 2, vec![white; 8])));
+    assert!((average(&scene) - 0.5).abs() < 0.02);
+}
+
+#[test]
+fn test_spectral() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::materials::light::Light;
+    use crate::materials::dispersion::RefractiveIndex;
+    use crate::materials::phong::PseudoPhong;
+    use crate::materials::phong_with_refraction::PseudoPhongRefraction;
+    use crate::objects::sphere::Sphere;
+    use crate::spectrum::blackbody::Blackbody;
+
+    let camera = test_camera();
+    let mut scene = Scene::new(&camera, Color::from_linear(1f64, 1f64, 1f64), 0.0001);
+    scene.set_spectral(true);
+    scene.add_object(Box::new(Sphere::new(
+        Vector::new(0f64, 0f64, -3f64),
+        1f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::from_linear(0.6, 0.4, 0.2), Color::BLACK)),
+    )));
+    let lamp = Light::new(Box::new(Blackbody::new(2700f64)), 2f64);
+    let lamp_color = crate::spectrum::to_rgb(&Blackbody::new(2700f64)) * 2f64;
+    scene.add_object(Box::new(Sphere::new(Vector::new(0f64, 3f64, -3f64), 1f64, Box::new(lamp))));
+
+    let average = |direction: Vector| {
+        let ray = Ray::new(Vector::new(0f64, 0f64, 0f64), direction);
+        let samples = 20000;
+        (0..samples)
+            .map(|_| scene.trace_ray(&ray, 5))
+            .fold(Color::BLACK, |sum, color| sum + color)
+            / samples as f64
+    };
+    let tolerance = Color::from_linear(0.03, 0.03, 0.03);
+
+    // A diffuse object in a white environment reflects its color, apart from the light of the lamp
+    let below = average(Vector::new(0f64, -0.3, -1f64));
+    assert!((below - Color::from_linear(0.6, 0.4, 0.2)).abs().less_than(tolerance));
+
+    // Lights with a spectrum keep their color
+    let lamp = average(Vector::new(0f64, 1f64, -1f64));
+    assert!((lamp - lamp_color).abs().less_than(tolerance * 3f64));
+
+    // Dispersive glass splits white light into wavelengths, but does not tint it on average
+    let mut glass = PseudoPhongRefraction::new(1f64, 0f64, 1.5, Color::WHITE, Color::BLACK);
+    glass.set_refractive_index(RefractiveIndex::bk7());
+    let mut scene = Scene::new(&camera, Color::WHITE, 0.0001);
+    scene.set_spectral(true);
+    scene.add_object(Box::new(Sphere::new(Vector::new(0f64, 0f64, -3f64), 1f64, Box::new(glass))));
+    let ray = Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0.2, 0.1, -1f64));
+    let samples = 20000;
+    let glass = (0..samples)
+        .map(|_| scene.trace_ray(&ray, 20))
+        .fold(Color::BLACK, |sum, color| sum + color)
+        / samples as f64;
+    assert!((glass - Color::WHITE).abs().less_than(Color::from_linear(0.1, 0.1, 0.1)));
+}
+
+#[test]
+fn test_fog() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::environment::map::EnvironmentMap;
+    use crate::materials::transparent::Transparent;
+    use crate::media::homogeneous::Homogeneous;
+    use crate::media::HenyeyGreenstein;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::volume::Volume;
+
+    let camera = test_camera();
+    let fog = |absorption: f64, scattering: f64| {
+        Box::new(Volume::new(
+            Box::new(Sphere::new(Vector::new(0f64, 0f64, -3f64), 1f64, Box::new(Transparent))),
+            Box::new(Homogeneous::new(
+                Color::from_linear(absorption, absorption, absorption),
+                Color::from_linear(scattering, scattering, scattering),
+                HenyeyGreenstein::new(0.5),
+            )),
+        ))
+    };
+    let ray = Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0f64, 0f64, -1f64));
+    let average = |scene: &Scene| {
+        let samples = 20000;
+        (0..samples)
+            .map(|_| scene.trace_ray(&ray, 50).r())
+            .sum::<f64>()
+            / samples as f64
+    };
+    let white = Color::from_linear(1f64, 1f64, 1f64);
+
+    // Absorbing fog attenuates the environment behind it by Beer-Lambert's law
+    let mut scene = Scene::new(&camera, white, 0.0001);
+    scene.add_object(fog(0.5, 0f64));
+    assert!((average(&scene) - (-1f64).exp()).abs() < 0.01);
+
+    // Fog that only scatters neither gains nor loses light in a uniform environment, with or
+    // without sampling the environment directly
+    let mut scene = Scene::new(&camera, white, 0.0001);
+    scene.add_object(fog(0f64, 2f64));
+    assert!((average(&scene) - 1f64).abs() < 0.02);
+    scene.set_environment(Box::new(EnvironmentMap::new(4, 2, vec![white; 8])));
+    assert!((average(&scene) - 1f64).abs() < 0.02);
+}
+
+#[test]
+fn test_cloud() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::materials::transparent::Transparent;
+    use crate::media::heterogeneous::Heterogeneous;
+    use crate::media::voxel::VoxelGrid;
+    use crate::media::HenyeyGreenstein;
+    use crate::objects::axis_aligned_box::AxisAlignedBox;
+    use crate::objects::volume::Volume;
+    use crate::primitives::aabb::Aabb;
+
+    let camera = test_camera();
+    let white = Color::from_linear(1f64, 1f64, 1f64);
+    let mut scene = Scene::new(&camera, white, 0.0001);
+
+    // An absorbing cloud of density one in the half with x > 0
+    let (min, max) = (Vector::new(-1f64, -1f64, -4f64), Vector::new(1f64, 1f64, -2f64));
+    let grid = VoxelGrid::from_fn(8, 8, 8, Aabb::new(min, max), |position| {
+        if position.x() > 0f64 { 1f64 } else { 0f64 }
+    });
+    scene.add_object(Box::new(Volume::new(
+        Box::new(AxisAlignedBox::new(min, max, Box::new(Transparent))),
+        Box::new(Heterogeneous::new(
+            Box::new(grid),
+            Color::from_linear(0.5, 0.5, 0.5),
+            Color::BLACK,
+            HenyeyGreenstein::isotropic(),
+        )),
+    )));
+
+    let through = |x: f64| {
+        let ray = Ray::new(Vector::new(x, 0f64, 0f64), Vector::new(0f64, 0f64, -1f64));
+        let samples = 10000;
+        (0..samples).map(|_| scene.trace_ray(&ray, 5).r()).sum::<f64>() / samples as f64
+    };
+    assert_eq!(through(-0.5), 1f64);
+    assert!((through(0.5) - (-1f64).exp()).abs() < 0.02);
 }
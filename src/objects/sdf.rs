@@ -0,0 +1,339 @@
+use crate::materials::Material;
+use crate::objects::traits::Intersect;
+use crate::objects::Object;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::Intersection;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+/// A signed distance function: negative inside of the shape, positive outside, zero on its surface.
+///
+/// The value must never overestimate the distance to the surface. Closures are distance fields, too.
+pub trait DistanceField: Sync + Send {
+    fn distance(&self, point: Vector) -> f64;
+}
+
+impl<F> DistanceField for F
+where
+    F: Fn(Vector) -> f64 + Sync + Send,
+{
+    fn distance(&self, point: Vector) -> f64 {
+        self(point)
+    }
+}
+
+/// An object whose surface is the zero set of a distance field, rendered by sphere tracing.
+///
+/// The bounding box has to contain the whole surface; it also limits how far rays are traced.
+/// Distance fields that may overestimate the distance, like [`Twist`], need a smaller step scale.
+pub struct Sdf {
+    field: Box<dyn DistanceField>,
+    bounds: Aabb,
+    material: Box<dyn Material>,
+    max_steps: usize,
+    step_scale: f64,
+    epsilon: f64,
+}
+
+impl Sdf {
+    pub fn new(field: Box<dyn DistanceField>, bounds: Aabb, material: Box<dyn Material>) -> Self {
+        Self {
+            field: field,
+            bounds: bounds,
+            material: material,
+            max_steps: 512,
+            step_scale: 1f64,
+            epsilon: 1e-5,
+        }
+    }
+
+    /// The number of steps after which a ray is considered to miss the surface
+    pub fn set_max_steps(&mut self, max_steps: usize) {
+        self.max_steps = max_steps;
+    }
+
+    /// The fraction of the distance to the surface that is stepped at once
+    pub fn set_step_scale(&mut self, step_scale: f64) {
+        self.step_scale = step_scale;
+    }
+
+    /// The distance from the surface at which a ray counts as hitting it
+    pub fn set_epsilon(&mut self, epsilon: f64) {
+        self.epsilon = epsilon;
+    }
+
+    /// The normalized gradient of the distance field
+    fn normal(&self, point: Vector) -> Vector {
+        let h = self.epsilon;
+        let derivative = |offset: Vector| {
+            self.field.distance(point + offset) - self.field.distance(point - offset)
+        };
+        Vector::new(
+            derivative(Vector::new(h, 0f64, 0f64)),
+            derivative(Vector::new(0f64, h, 0f64)),
+            derivative(Vector::new(0f64, 0f64, h)),
+        )
+        .normalize()
+    }
+}
+
+impl Object for Sdf {
+    fn material(&self) -> &Box<dyn Material> {
+        &self.material
+    }
+}
+
+impl Intersect for Sdf {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection<'_>> {
+        let (start, end) = self.bounds.clip(ray, param_min, f64::INFINITY)?;
+        let speed = ray.direction.length();
+        let mut t = start;
+        let mut steps = 0;
+
+        // Leave the surface that the ray might start on, e.g. after a bounce
+        let mut distance = self.field.distance(ray.point_at_parameter(t));
+        while distance.abs() < self.epsilon {
+            t += 2f64 * self.epsilon / speed;
+            steps += 1;
+            if t > end || steps >= self.max_steps {
+                return None;
+            }
+            distance = self.field.distance(ray.point_at_parameter(t));
+        }
+
+        // Rays starting inside of the shape look for the surface from below
+        let side = distance.signum();
+        while steps < self.max_steps && t <= end {
+            let position = ray.point_at_parameter(t);
+            let distance = side * self.field.distance(position);
+            if distance < self.epsilon {
+                return Some(Intersection::new(
+                    position,
+                    self as &dyn Object,
+                    t,
+                    self.normal(position),
+                ));
+            }
+            t += distance * self.step_scale / speed;
+            steps += 1;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
+
+unsafe impl Sync for Sdf {}
+
+/// A sphere around the origin
+pub struct SphereField {
+    pub radius: f64,
+}
+
+impl DistanceField for SphereField {
+    fn distance(&self, point: Vector) -> f64 {
+        point.length() - self.radius
+    }
+}
+
+/// A box centered at the origin
+pub struct BoxField {
+    pub half_size: Vector,
+}
+
+impl DistanceField for BoxField {
+    fn distance(&self, point: Vector) -> f64 {
+        let q = Vector::new(
+            point.x().abs() - self.half_size.x(),
+            point.y().abs() - self.half_size.y(),
+            point.z().abs() - self.half_size.z(),
+        );
+        let outside = Vector::new(q.x().max(0f64), q.y().max(0f64), q.z().max(0f64)).length();
+        let inside = q.x().max(q.y()).max(q.z()).min(0f64);
+        outside + inside
+    }
+}
+
+/// A torus around the y axis
+pub struct TorusField {
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl DistanceField for TorusField {
+    fn distance(&self, point: Vector) -> f64 {
+        let ring = (point.x() * point.x() + point.z() * point.z()).sqrt() - self.major_radius;
+        (ring * ring + point.y() * point.y()).sqrt() - self.minor_radius
+    }
+}
+
+/// A capped cylinder along the y axis, centered at the origin
+pub struct CylinderField {
+    pub radius: f64,
+    pub half_height: f64,
+}
+
+impl DistanceField for CylinderField {
+    fn distance(&self, point: Vector) -> f64 {
+        let radial = (point.x() * point.x() + point.z() * point.z()).sqrt() - self.radius;
+        let axial = point.y().abs() - self.half_height;
+        let outside = (radial.max(0f64).powi(2) + axial.max(0f64).powi(2)).sqrt();
+        outside + radial.max(axial).min(0f64)
+    }
+}
+
+/// The union of two fields, blended over a distance of about `smoothness`
+pub struct SmoothUnion {
+    pub a: Box<dyn DistanceField>,
+    pub b: Box<dyn DistanceField>,
+    pub smoothness: f64,
+}
+
+impl DistanceField for SmoothUnion {
+    fn distance(&self, point: Vector) -> f64 {
+        let a = self.a.distance(point);
+        let b = self.b.distance(point);
+        if self.smoothness <= 0f64 {
+            return a.min(b);
+        }
+        let h = (0.5 + 0.5 * (b - a) / self.smoothness).clamp(0f64, 1f64);
+        b + (a - b) * h - self.smoothness * h * (1f64 - h)
+    }
+}
+
+/// Twists a field around the y axis by `rate` radians per unit of height.
+///
+/// Twisting stretches space, so objects using it need a step scale below one.
+pub struct Twist {
+    pub field: Box<dyn DistanceField>,
+    pub rate: f64,
+}
+
+impl DistanceField for Twist {
+    fn distance(&self, point: Vector) -> f64 {
+        let (sin, cos) = (self.rate * point.y()).sin_cos();
+        self.field.distance(Vector::new(
+            cos * point.x() - sin * point.z(),
+            point.y(),
+            sin * point.x() + cos * point.z(),
+        ))
+    }
+}
+
+/// Repeats a field infinitely with the given period along each axis. A period of zero disables
+/// the repetition along that axis. The field should fit into a single cell around the origin.
+pub struct Repetition {
+    pub field: Box<dyn DistanceField>,
+    pub period: Vector,
+}
+
+impl DistanceField for Repetition {
+    fn distance(&self, point: Vector) -> f64 {
+        let repeat = |value: f64, period: f64| {
+            if period > 0f64 {
+                value - period * (value / period).round()
+            } else {
+                value
+            }
+        };
+        self.field.distance(Vector::new(
+            repeat(point.x(), self.period.x()),
+            repeat(point.y(), self.period.y()),
+            repeat(point.z(), self.period.z()),
+        ))
+    }
+}
+
+/// Rounds off the edges of a field by growing it by `radius`
+pub struct Rounded {
+    pub field: Box<dyn DistanceField>,
+    pub radius: f64,
+}
+
+impl DistanceField for Rounded {
+    fn distance(&self, point: Vector) -> f64 {
+        self.field.distance(point) - self.radius
+    }
+}
+
+#[test]
+fn test_sphere_tracing() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::primitives::vec::Color;
+
+    let sdf = Sdf::new(
+        Box::new(SphereField { radius: 1f64 }),
+        Aabb::new(
+            Vector::new(-1f64, -1f64, -1f64),
+            Vector::new(1f64, 1f64, 1f64),
+        ),
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    );
+
+    let hit = sdf
+        .intersect(
+            &Ray::new(Vector::new(0.5, 0f64, 5f64), Vector::new(0f64, 0f64, -2f64)),
+            0.001,
+        )
+        .unwrap();
+    let expected = Vector::new(0.5, 0f64, 0.75f64.sqrt());
+    assert!((hit.position - expected).length() < 1e-4);
+    assert!((hit.normal - expected).length() < 1e-4);
+
+    // From the inside the surface is found, too
+    let hit = sdf
+        .intersect(
+            &Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(1f64, 0f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert!((hit.ray_parameter - 1f64).abs() < 1e-4);
+    assert!((hit.normal - Vector::new(1f64, 0f64, 0f64)).length() < 1e-4);
+
+    // Leaving the surface does not hit it again
+    assert!(sdf
+        .intersect(
+            &Ray::new(hit.position, Vector::new(1f64, 0f64, 0f64)),
+            0.0001
+        )
+        .is_none());
+}
+
+#[test]
+fn test_combinators() {
+    let union = SmoothUnion {
+        a: Box::new(SphereField { radius: 1f64 }),
+        b: Box::new(|p: Vector| (p - Vector::new(3f64, 0f64, 0f64)).length() - 1f64),
+        smoothness: 0.5,
+    };
+    // Far from the seam the union is unchanged, near it the gap gets filled
+    assert!((union.distance(Vector::new(-2f64, 0f64, 0f64)) - 1f64).abs() < 1e-12);
+    assert!(union.distance(Vector::new(1.5, 0f64, 0f64)) < 0.5);
+
+    let repeated = Repetition {
+        field: Box::new(SphereField { radius: 1f64 }),
+        period: Vector::new(10f64, 0f64, 0f64),
+    };
+    assert!((repeated.distance(Vector::new(21f64, 0f64, 0f64)) - 0f64).abs() < 1e-12);
+    assert!((repeated.distance(Vector::new(20f64, 3f64, 0f64)) - 2f64).abs() < 1e-12);
+
+    let rounded = Rounded {
+        field: Box::new(BoxField {
+            half_size: Vector::new(1f64, 1f64, 1f64),
+        }),
+        radius: 0.5,
+    };
+    assert!((rounded.distance(Vector::new(2f64, 2f64, 0f64)) - (2f64.sqrt() - 0.5)).abs() < 1e-12);
+
+    let twisted = Twist {
+        field: Box::new(BoxField {
+            half_size: Vector::new(2f64, 10f64, 0.5),
+        }),
+        rate: std::f64::consts::PI / 2f64,
+    };
+    // A quarter turn at a height of one swaps the long and the short side of the box
+    assert!(twisted.distance(Vector::new(0f64, 1f64, 1.5)) < 0f64);
+    assert!(twisted.distance(Vector::new(1.5, 1f64, 0f64)) > 0f64);
+}
@@ -1,15 +1,19 @@
 use crate::objects::Object;
+use crate::objects::motion::Motion;
 use crate::objects::traits::Intersect;
-use crate::primitives::intersection::Intersection;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::{Intersection, Span};
 use crate::primitives::ray::Ray;
 use crate::primitives::vec::Vector;
+use std::f64::consts::PI;
 use std::option::Option;
 use crate::materials::Material;
 
 pub struct Sphere {
     center: Vector,
     radius: f64,
-    material: Box<dyn Material>
+    material: Box<dyn Material>,
+    motion: Motion,
 }
 
 impl Sphere {
@@ -18,21 +22,24 @@ impl Sphere {
             center: center,
             radius: radius,
             material: material,
+            motion: Motion::none(),
         }
     }
-}
 
-impl Object for Sphere {
-    fn material(&self) -> &Box<dyn Material>{
-        &self.material
+    /// Moves the center of the sphere during the shutter interval
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.motion = motion;
     }
-}
 
-impl Intersect for Sphere {
-    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection> {
+    fn center_at(&self, time: f64) -> Vector {
+        self.center + self.motion.offset_at(time)
+    }
+
+    /// Both ray parameters at which the ray crosses the surface, in ascending order
+    fn roots(&self, ray: &Ray, center: Vector) -> Option<(f64, f64)> {
         let a = ray.direction.dot(&ray.direction);
-        let b = (ray.direction * 2f64).dot(&(ray.origin - self.center));
-        let c = (ray.origin - self.center).dot(&(ray.origin - self.center))
+        let b = (ray.direction * 2f64).dot(&(ray.origin - center));
+        let c = (ray.origin - center).dot(&(ray.origin - center))
             - (self.radius * self.radius);
 
         let discriminant = (b * b) - (4f64 * a * c);
@@ -41,54 +48,116 @@ impl Intersect for Sphere {
         } else {
             let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
             let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
+            Some((t1.min(t2), t1.max(t2)))
+        }
+    }
 
-            if t1 < t2 {
-                if t1 > param_min {
-                    let intersection_position = ray.point_at_parameter(t1);
-                    let intersection_normal = (intersection_position - self.center).normalize();
-                    Some(Intersection::new(
-                        intersection_position,
-                        self as &dyn Object,
-                        t1,
-                        intersection_normal,
-                    ))
-                } else if t2 > param_min {
-                    let intersection_position = ray.point_at_parameter(t2);
-                    let intersection_normal = (intersection_position - self.center).normalize();
-                    Some(Intersection::new(
-                        intersection_position,
-                        self as &dyn Object,
-                        t2,
-                        intersection_normal,
-                    ))
-                } else {
-                    None
-                }
-            } else {
-                if t2 > param_min {
-                    let intersection_position = ray.point_at_parameter(t2);
-                    let intersection_normal = (intersection_position - self.center).normalize();
-                    Some(Intersection::new(
-                        intersection_position,
-                        self as &dyn Object,
-                        t2,
-                        intersection_normal,
-                    ))
-                } else if t1 > param_min {
-                    let intersection_position = ray.point_at_parameter(t1);
-                    let intersection_normal = (intersection_position - self.center).normalize();
-                    Some(Intersection::new(
-                        intersection_position,
-                        self as &dyn Object,
-                        t1,
-                        intersection_normal,
-                    ))
-                } else {
-                    None
-                }
-            }
+    fn intersection_at(&self, ray: &Ray, t: f64, center: Vector) -> Intersection<'_> {
+        let position = ray.point_at_parameter(t);
+        let normal = (position - center).normalize();
+        // Longitude and colatitude, starting at the north pole
+        let u = 0.5 + normal.z().atan2(normal.x()) / (2f64 * PI);
+        let v = normal.y().clamp(-1f64, 1f64).acos() / PI;
+        // The derivatives along the parallel and the meridian, which vanish at the poles
+        let ring = (normal.x() * normal.x() + normal.z() * normal.z()).sqrt();
+        let dpdu = Vector::new(-normal.z(), 0f64, normal.x()) * (2f64 * PI * self.radius);
+        let dpdv = if ring > 0f64 {
+            Vector::new(
+                normal.y() * normal.x() / ring,
+                -ring,
+                normal.y() * normal.z() / ring,
+            ) * (PI * self.radius)
+        } else {
+            Vector::new(0f64, 0f64, 0f64)
+        };
+        Intersection::new(position, self as &dyn Object, t, normal)
+            .with_uv(u, v)
+            .with_tangents(dpdu, dpdv)
+    }
+}
+
+impl Object for Sphere {
+    fn material(&self) -> &Box<dyn Material>{
+        &self.material
+    }
+}
+
+impl Intersect for Sphere {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection> {
+        let center = self.center_at(ray.time);
+        let (t1, t2) = self.roots(ray, center)?;
+        if t1 > param_min {
+            Some(self.intersection_at(ray, t1, center))
+        } else if t2 > param_min {
+            Some(self.intersection_at(ray, t2, center))
+        } else {
+            None
         }
     }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        let center = self.center_at(ray.time);
+        self.roots(ray, center)
+            .map(|(t1, t2)| {
+                Span::new(
+                    self.intersection_at(ray, t1, center),
+                    self.intersection_at(ray, t2, center),
+                )
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let extent = Vector::new(self.radius, self.radius, self.radius);
+        Some(
+            self.motion
+                .sweep(&Aabb::new(self.center - extent, self.center + extent)),
+        )
+    }
+
+    fn sample_surface(&self, u1: f64, u2: f64, time: f64) -> Option<(Intersection<'_>, f64)> {
+        let center = self.center_at(time);
+        let z = 1f64 - 2f64 * u1;
+        let radius = (1f64 - z * z).max(0f64).sqrt();
+        let (sin_phi, cos_phi) = (2f64 * PI * u2).sin_cos();
+        let normal = Vector::new(radius * cos_phi, radius * sin_phi, z);
+        // Hit the point head on from outside to get its surface parameterization
+        let ray = Ray::new_at_time(center + normal * (2f64 * self.radius), -normal, time);
+        Some((
+            self.intersection_at(&ray, self.radius, center),
+            4f64 * PI * self.radius * self.radius,
+        ))
+    }
 }
 
-unsafe impl Sync for Sphere {}
\ No newline at end of file
+unsafe impl Sync for Sphere {}
+
+#[test]
+fn test_tangents() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::primitives::vec::Color;
+
+    let sphere = Sphere::new(
+        Vector::new(1f64, 2f64, 3f64),
+        2f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    );
+    let hit_at = |target: Vector| {
+        let origin = Vector::new(1f64, 2f64, 3f64);
+        sphere
+            .intersect(&Ray::new(origin, target - origin), 0.001)
+            .unwrap()
+    };
+    let hit = hit_at(Vector::new(2f64, 3f64, 4f64));
+
+    // Stepping along the tangents changes the uv by the step
+    let delta = 1e-6;
+    let (u, v) = hit.uv;
+    let (u_step, _) = hit_at(hit.position + hit.dpdu * delta).uv;
+    let (_, v_step) = hit_at(hit.position + hit.dpdv * delta).uv;
+    assert!((u_step - u - delta).abs() < 1e-9);
+    assert!((v_step - v - delta).abs() < 1e-9);
+    assert!(hit.dpdu.dot(&hit.normal).abs() < 1e-12);
+    assert!(hit.dpdv.dot(&hit.normal).abs() < 1e-12);
+}
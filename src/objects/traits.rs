@@ -1,6 +1,28 @@
-use crate::primitives::intersection::Intersection;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::{Intersection, Span};
 use crate::primitives::ray::Ray;
 
 pub trait Intersect {
     fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection>;
+
+    /// All spans in which the whole line through the ray, including negative ray parameters,
+    /// lies inside of the object, sorted by ray parameter.
+    ///
+    /// Objects that do not enclose a volume have no spans.
+    fn spans(&self, _ray: &Ray) -> Vec<Span<'_>> {
+        Vec::new()
+    }
+
+    /// A box containing the object over the whole shutter interval, `None` if it is unbounded
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+
+    /// Maps two uniform random numbers to a point distributed uniformly over the surface at `time`,
+    /// so that integrators can start paths on emissive objects.
+    ///
+    /// Returns the point and the area of the surface, or `None` if the object does not support it.
+    fn sample_surface(&self, _u1: f64, _u2: f64, _time: f64) -> Option<(Intersection<'_>, f64)> {
+        None
+    }
 }
@@ -0,0 +1,162 @@
+use crate::materials::Material;
+use crate::media::Medium;
+use crate::objects::traits::Intersect;
+use crate::objects::Object;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::{Intersection, Span};
+use crate::primitives::matrix::Matrix4;
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+use std::sync::Arc;
+
+/// An object placed in the world by an affine transformation.
+///
+/// The wrapped object is shared, so the same geometry can be instanced many times with
+/// different transformations and, optionally, different materials without being copied.
+pub struct Transformed {
+    object: Arc<dyn Object>,
+    transform: Matrix4,
+    inverse: Matrix4,
+    normal_matrix: Matrix4,
+    material: Option<Box<dyn Material>>,
+}
+
+impl Transformed {
+    /// Places `object` with `transform`, which maps object space to world space.
+    ///
+    /// Panics if the transformation is not invertible.
+    pub fn new(object: Arc<dyn Object>, transform: Matrix4) -> Self {
+        let inverse = transform
+            .inverse()
+            .expect("object transformations have to be invertible");
+        Self {
+            object: object,
+            transform: transform,
+            inverse: inverse,
+            normal_matrix: inverse.transpose(),
+            material: None,
+        }
+    }
+
+    /// Renders this instance with `material` instead of the material of the wrapped object
+    pub fn set_material(&mut self, material: Box<dyn Material>) {
+        self.material = Some(material);
+    }
+}
+
+impl Transformed {
+    fn to_local(&self, ray: &Ray) -> Ray {
+        // The direction is not normalized, so ray parameters are the same in both spaces
+        Ray::new_at_time(
+            self.inverse.transform_point(ray.origin),
+            self.inverse.transform_vector(ray.direction),
+            ray.time,
+        )
+    }
+
+    fn to_world<'a>(&'a self, mut intersection: Intersection<'a>) -> Intersection<'a> {
+        intersection.position = self.transform.transform_point(intersection.position);
+        intersection.normal = self
+            .normal_matrix
+            .transform_vector(intersection.normal)
+            .normalize();
+        intersection.shading_normal = self
+            .normal_matrix
+            .transform_vector(intersection.shading_normal)
+            .normalize();
+        intersection.dpdu = self.transform.transform_vector(intersection.dpdu);
+        intersection.dpdv = self.transform.transform_vector(intersection.dpdv);
+        if self.material.is_some() {
+            intersection.object = self as &dyn Object;
+        }
+        intersection
+    }
+}
+
+impl Object for Transformed {
+    fn material(&self) -> &Box<dyn Material> {
+        self.material
+            .as_ref()
+            .unwrap_or_else(|| self.object.material())
+    }
+
+    fn medium(&self) -> Option<&dyn Medium> {
+        self.object.medium()
+    }
+}
+
+impl Intersect for Transformed {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection<'_>> {
+        self.object
+            .intersect(&self.to_local(ray), param_min)
+            .map(|intersection| self.to_world(intersection))
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        self.object
+            .spans(&self.to_local(ray))
+            .into_iter()
+            .map(|span| Span::new(self.to_world(span.enter), self.to_world(span.exit)))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.object.bounding_box().map(|bounding_box| {
+            (0..8)
+                .map(|corner| {
+                    let pick = |bit: usize, axis: usize| {
+                        if corner & bit == 0 {
+                            bounding_box.min.axis(axis)
+                        } else {
+                            bounding_box.max.axis(axis)
+                        }
+                    };
+                    let point = self
+                        .transform
+                        .transform_point(Vector::new(pick(1, 0), pick(2, 1), pick(4, 2)));
+                    Aabb::new(point, point)
+                })
+                .fold(None, |all: Option<Aabb>, corner| {
+                    Some(all.map_or(corner, |all| all.union(&corner)))
+                })
+                .unwrap()
+        })
+    }
+}
+
+unsafe impl Sync for Transformed {}
+
+#[test]
+#[allow(clippy::arc_with_non_send_sync)]
+fn test_scaled_sphere() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::objects::sphere::Sphere;
+    use crate::primitives::vec::Color;
+
+    let sphere: Arc<dyn Object> = Arc::new(Sphere::new(
+        Vector::new(0f64, 0f64, 0f64),
+        1f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    ));
+    // An ellipsoid with half axes 2, 1, 1 centered at (0, 0, -10)
+    let ellipsoid = Transformed::new(
+        sphere,
+        Matrix4::translation(Vector::new(0f64, 0f64, -10f64))
+            * Matrix4::scaling(Vector::new(2f64, 1f64, 1f64)),
+    );
+
+    let hit = ellipsoid
+        .intersect(
+            &Ray::new(Vector::new(5f64, 0f64, -10f64), Vector::new(-1f64, 0f64, 0f64)),
+            0.001,
+        )
+        .unwrap();
+    assert!((hit.ray_parameter - 3f64).abs() < 1e-9);
+    assert!((hit.position - Vector::new(2f64, 0f64, -10f64)).length() < 1e-9);
+    assert!((hit.normal - Vector::new(1f64, 0f64, 0f64)).length() < 1e-9);
+
+    let bounding_box = ellipsoid.bounding_box().unwrap();
+    assert_eq!(bounding_box.min, Vector::new(-2f64, -1f64, -11f64));
+    assert_eq!(bounding_box.max, Vector::new(2f64, 1f64, -9f64));
+}
@@ -0,0 +1,64 @@
+use crate::materials::Material;
+use crate::media::Medium;
+use crate::objects::traits::Intersect;
+use crate::objects::Object;
+use crate::primitives::aabb::Aabb;
+use crate::primitives::intersection::{Intersection, Span};
+use crate::primitives::ray::Ray;
+
+/// A closed object filled with a participating medium.
+///
+/// The surface keeps the material of the object, so glass filled with a murky medium still refracts.
+/// Fog or smoke without a visible surface uses the [`Transparent`](crate::materials::transparent::Transparent)
+/// material for the object.
+pub struct Volume {
+    object: Box<dyn Object>,
+    medium: Box<dyn Medium>,
+}
+
+impl Volume {
+    pub fn new(object: Box<dyn Object>, medium: Box<dyn Medium>) -> Self {
+        Self {
+            object: object,
+            medium: medium,
+        }
+    }
+
+    fn claim<'a>(&'a self, mut intersection: Intersection<'a>) -> Intersection<'a> {
+        // Integrators find the medium through the object of the intersection
+        intersection.object = self as &dyn Object;
+        intersection
+    }
+}
+
+impl Object for Volume {
+    fn material(&self) -> &Box<dyn Material> {
+        self.object.material()
+    }
+
+    fn medium(&self) -> Option<&dyn Medium> {
+        Some(self.medium.as_ref())
+    }
+}
+
+impl Intersect for Volume {
+    fn intersect(&self, ray: &Ray, param_min: f64) -> Option<Intersection<'_>> {
+        self.object
+            .intersect(ray, param_min)
+            .map(|intersection| self.claim(intersection))
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span<'_>> {
+        self.object
+            .spans(ray)
+            .into_iter()
+            .map(|span| Span::new(self.claim(span.enter), self.claim(span.exit)))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.object.bounding_box()
+    }
+}
+
+unsafe impl Sync for Volume {}
@@ -0,0 +1,97 @@
+use crate::primitives::ray::Ray;
+use crate::primitives::vec::Vector;
+
+/// An axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl Aabb {
+    pub fn new(min: Vector, max: Vector) -> Self {
+        Self { min: min, max: max }
+    }
+
+    /// The smallest box containing both boxes
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::new(
+            Vector::new(
+                self.min.x().min(other.min.x()),
+                self.min.y().min(other.min.y()),
+                self.min.z().min(other.min.z()),
+            ),
+            Vector::new(
+                self.max.x().max(other.max.x()),
+                self.max.y().max(other.max.y()),
+                self.max.z().max(other.max.z()),
+            ),
+        )
+    }
+
+    /// The box in which both boxes overlap. It is inverted if they do not overlap.
+    pub fn intersection(&self, other: &Aabb) -> Aabb {
+        Aabb::new(
+            Vector::new(
+                self.min.x().max(other.min.x()),
+                self.min.y().max(other.min.y()),
+                self.min.z().max(other.min.z()),
+            ),
+            Vector::new(
+                self.max.x().min(other.max.x()),
+                self.max.y().min(other.max.y()),
+                self.max.z().min(other.max.z()),
+            ),
+        )
+    }
+
+    pub fn translate(&self, offset: Vector) -> Aabb {
+        Aabb::new(self.min + offset, self.max + offset)
+    }
+
+    pub fn center(&self) -> Vector {
+        (self.min + self.max) / 2f64
+    }
+
+    /// The index of the axis along which the box is the longest
+    pub fn longest_axis(&self) -> usize {
+        let size = self.max - self.min;
+        if size.x() >= size.y() && size.x() >= size.z() {
+            0
+        } else if size.y() >= size.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Returns the ray parameter at which the ray enters the box, if it does so within (param_min, param_max)
+    pub fn hit(&self, ray: &Ray, param_min: f64, param_max: f64) -> Option<f64> {
+        self.clip(ray, param_min, param_max).map(|(enter, _)| enter)
+    }
+
+    /// Restricts the range of ray parameters (param_min, param_max) to the part inside of the box
+    pub fn clip(&self, ray: &Ray, param_min: f64, param_max: f64) -> Option<(f64, f64)> {
+        let mut t_min = param_min;
+        let mut t_max = param_max;
+        for axis in 0..3 {
+            let inverse = 1f64 / ray.direction.axis(axis);
+            let mut t0 = (self.min.axis(axis) - ray.origin.axis(axis)) * inverse;
+            let mut t1 = (self.max.axis(axis) - ray.origin.axis(axis)) * inverse;
+            if inverse < 0f64 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // NaNs from 0 * infinity leave the bounds untouched
+            if t0 > t_min {
+                t_min = t0;
+            }
+            if t1 < t_max {
+                t_max = t1;
+            }
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
@@ -0,0 +1,136 @@
+/// A piecewise constant distribution over [0, 1) that can be sampled proportionally to its values
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+    values: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    pub fn new(values: Vec<f64>) -> Self {
+        let count = values.len();
+        let mut cdf = Vec::with_capacity(count + 1);
+        cdf.push(0f64);
+        for i in 0..count {
+            cdf.push(cdf[i] + values[i].abs() / count as f64);
+        }
+        let integral = cdf[count];
+        if integral == 0f64 {
+            // Fall back to a uniform distribution
+            for (i, c) in cdf.iter_mut().enumerate() {
+                *c = i as f64 / count as f64;
+            }
+        } else {
+            for c in cdf.iter_mut() {
+                *c /= integral;
+            }
+        }
+        Self {
+            values: values,
+            cdf: cdf,
+            integral: integral,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The integral of the (unnormalized) function over [0, 1)
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Maps the uniform random number `u` to a point in [0, 1).
+    ///
+    /// Returns the point, its probability density and the index of the segment it lies in.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        // Find the last cdf entry that is <= u, skipping segments without any weight
+        let offset = (self.cdf.partition_point(|c| *c <= u).max(1) - 1).min(self.count() - 1);
+
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0f64 {
+            (u - self.cdf[offset]) / width
+        } else {
+            0f64
+        };
+        (
+            ((offset as f64 + du) / self.count() as f64).min(1f64 - f64::EPSILON),
+            self.pdf_of_segment(offset),
+            offset,
+        )
+    }
+
+    /// The probability density of sampling a point in [0, 1)
+    pub fn pdf(&self, x: f64) -> f64 {
+        let offset = ((x * self.count() as f64) as usize).min(self.count() - 1);
+        self.pdf_of_segment(offset)
+    }
+
+    fn pdf_of_segment(&self, offset: usize) -> f64 {
+        if self.integral == 0f64 {
+            1f64
+        } else {
+            self.values[offset].abs() / self.integral
+        }
+    }
+}
+
+/// A piecewise constant distribution over [0, 1)^2, stored row by row
+#[derive(Debug, Clone)]
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(values: &[f64], width: usize, height: usize) -> Self {
+        let rows: Vec<Distribution1D> = values
+            .chunks(width)
+            .take(height)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(rows.iter().map(|row| row.integral()).collect());
+        Self {
+            rows: rows,
+            marginal: marginal,
+        }
+    }
+
+    /// Maps two uniform random numbers to a point (x, y) in [0, 1)^2 and its probability density
+    pub fn sample(&self, u1: f64, u2: f64) -> ((f64, f64), f64) {
+        let (y, pdf_y, row) = self.marginal.sample(u2);
+        let (x, pdf_x, _) = self.rows[row].sample(u1);
+        ((x, y), pdf_x * pdf_y)
+    }
+
+    pub fn pdf(&self, x: f64, y: f64) -> f64 {
+        let row = ((y * self.rows.len() as f64) as usize).min(self.rows.len() - 1);
+        self.marginal.pdf(y) * self.rows[row].pdf(x)
+    }
+}
+
+/// The multiple importance sampling weight of a sample drawn with probability density `pdf`,
+/// when the same point could have been drawn by another strategy with density `other_pdf`
+pub fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    if pdf == 0f64 {
+        0f64
+    } else {
+        pdf * pdf / (pdf * pdf + other_pdf * other_pdf)
+    }
+}
+
+#[test]
+fn test_distribution_2d() {
+    // Only the lower right cell has any weight
+    let distribution = Distribution2D::new(&[0f64, 0f64, 0f64, 4f64], 2, 2);
+    for i in 0..10 {
+        let u = i as f64 / 10f64;
+        let ((x, y), pdf) = distribution.sample(u, 1f64 - u);
+        assert!((0.5..1f64).contains(&x));
+        assert!((0.5..1f64).contains(&y));
+        assert_eq!(pdf, 4f64);
+        assert_eq!(distribution.pdf(x, y), 4f64);
+    }
+    assert_eq!(distribution.pdf(0.25, 0.25), 0f64);
+}
@@ -1,20 +1,119 @@
 use crate::objects::Object;
 use crate::primitives::vec::Vector;
 
+#[derive(Clone)]
 pub struct Intersection<'a> {
     pub position: Vector,
     pub object: &'a dyn Object,
     pub ray_parameter: f64,
+    /// The geometric normal, which points out of the object
     pub normal: Vector,
+    /// The normal used for shading, e.g. perturbed by a normal map. Defaults to the geometric normal.
+    pub shading_normal: Vector,
+    /// The surface parameterization of the object at the intersection
+    pub uv: (f64, f64),
+    /// The partial derivatives of the position with respect to u and v
+    pub dpdu: Vector,
+    pub dpdv: Vector,
 }
 
 impl<'a> Intersection<'a> {
     pub fn new(position: Vector, object: &'a dyn Object, ray_parameter: f64, normal: Vector) -> Self {
+        let (dpdu, dpdv) = normal.orthonormal_basis();
         Self {
             position: position,
             object: object,
             ray_parameter: ray_parameter,
             normal: normal,
+            shading_normal: normal,
+            uv: (0f64, 0f64),
+            dpdu: dpdu,
+            dpdv: dpdv,
         }
     }
+
+    pub fn with_uv(mut self, u: f64, v: f64) -> Self {
+        self.uv = (u, v);
+        self
+    }
+
+    /// Sets the tangents of the surface parameterization.
+    ///
+    /// Degenerate tangents, e.g. at the poles of a sphere, are ignored and an arbitrary tangent frame is kept.
+    pub fn with_tangents(mut self, dpdu: Vector, dpdv: Vector) -> Self {
+        if dpdu.cross(&dpdv).length() > 0f64 {
+            self.dpdu = dpdu;
+            self.dpdv = dpdv;
+        }
+        self
+    }
+
+    /// The shading normal, flipped if the ray with `incoming` direction hits the back of the surface
+    pub fn facing_shading_normal(&self, incoming: Vector) -> Vector {
+        if incoming.dot(&self.normal) > 0f64 {
+            -self.shading_normal
+        } else {
+            self.shading_normal
+        }
+    }
+
+    /// Mirrors `direction` at the geometric surface if it does not leave on the intended side.
+    ///
+    /// Shading normals that differ from the geometric normal can send reflected rays into the
+    /// surface or transmitted rays out of it, which would leak light through the surface.
+    pub fn keep_side(&self, incoming: Vector, direction: Vector, transmitted: bool) -> Vector {
+        let from_front = incoming.dot(&self.normal) < 0f64;
+        let towards_front = direction.dot(&self.normal) > 0f64;
+        if towards_front == (from_front != transmitted) {
+            direction
+        } else {
+            direction - self.normal * (2f64 * direction.dot(&self.normal))
+        }
+    }
+}
+
+/// A part of a ray that lies inside of a solid object, from where the ray enters it to where it exits it.
+///
+/// Both normals point out of the object. Unbounded solids have spans with infinite ray parameters.
+#[derive(Clone)]
+pub struct Span<'a> {
+    pub enter: Intersection<'a>,
+    pub exit: Intersection<'a>,
+}
+
+impl<'a> Span<'a> {
+    pub fn new(enter: Intersection<'a>, exit: Intersection<'a>) -> Self {
+        Self {
+            enter: enter,
+            exit: exit,
+        }
+    }
+}
+
+#[test]
+fn test_keep_side() {
+    use crate::materials::phong::PseudoPhong;
+    use crate::objects::sphere::Sphere;
+    use crate::primitives::vec::Color;
+
+    let sphere = Sphere::new(
+        Vector::new(0f64, 0f64, 0f64),
+        1f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    );
+    let intersection = Intersection::new(
+        Vector::new(0f64, 0f64, 1f64),
+        &sphere,
+        1f64,
+        Vector::new(0f64, 0f64, 1f64),
+    );
+    let incoming = Vector::new(0f64, 0f64, -1f64);
+    let into_surface = Vector::new(1f64, 0f64, -1f64);
+    let out_of_surface = Vector::new(1f64, 0f64, 1f64);
+
+    assert_eq!(intersection.keep_side(incoming, out_of_surface, false), out_of_surface);
+    assert_eq!(intersection.keep_side(incoming, into_surface, false), out_of_surface);
+    assert_eq!(intersection.keep_side(incoming, into_surface, true), into_surface);
+    // From the inside everything is mirrored
+    assert_eq!(intersection.keep_side(-incoming, out_of_surface, false), into_surface);
 }
@@ -0,0 +1,157 @@
+use crate::primitives::vec::Vector;
+
+use std::ops::Mul;
+
+/// A 4x4 matrix for affine transformations of points, vectors and normals, stored row by row
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4([[f64; 4]; 4]);
+
+impl Matrix4 {
+    pub fn new(rows: [[f64; 4]; 4]) -> Self {
+        Self(rows)
+    }
+
+    pub fn identity() -> Self {
+        Self::scaling(Vector::new(1f64, 1f64, 1f64))
+    }
+
+    pub fn translation(offset: Vector) -> Self {
+        Self([
+            [1f64, 0f64, 0f64, offset.x()],
+            [0f64, 1f64, 0f64, offset.y()],
+            [0f64, 0f64, 1f64, offset.z()],
+            [0f64, 0f64, 0f64, 1f64],
+        ])
+    }
+
+    pub fn scaling(factors: Vector) -> Self {
+        Self([
+            [factors.x(), 0f64, 0f64, 0f64],
+            [0f64, factors.y(), 0f64, 0f64],
+            [0f64, 0f64, factors.z(), 0f64],
+            [0f64, 0f64, 0f64, 1f64],
+        ])
+    }
+
+    /// A counterclockwise rotation by `angle` radians around `axis` (right-hand rule)
+    pub fn rotation(axis: Vector, angle: f64) -> Self {
+        let axis = axis.normalize();
+        let (x, y, z) = (axis.x(), axis.y(), axis.z());
+        let (sin, cos) = angle.sin_cos();
+        let t = 1f64 - cos;
+        Self([
+            [t * x * x + cos, t * x * y - sin * z, t * x * z + sin * y, 0f64],
+            [t * x * y + sin * z, t * y * y + cos, t * y * z - sin * x, 0f64],
+            [t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos, 0f64],
+            [0f64, 0f64, 0f64, 1f64],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = [[0f64; 4]; 4];
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.0[j][i];
+            }
+        }
+        Self(result)
+    }
+
+    /// The inverse matrix, `None` if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        // Gauss-Jordan elimination with partial pivoting
+        let mut m = self.0;
+        let mut inverse = Self::identity().0;
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|a, b| {
+                    m[*a][column]
+                        .abs()
+                        .partial_cmp(&m[*b][column].abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap();
+            if m[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            m.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let factor = 1f64 / m[column][column];
+            for j in 0..4 {
+                m[column][j] *= factor;
+                inverse[column][j] *= factor;
+            }
+            for row in 0..4 {
+                if row != column {
+                    let factor = m[row][column];
+                    for j in 0..4 {
+                        m[row][j] -= factor * m[column][j];
+                        inverse[row][j] -= factor * inverse[column][j];
+                    }
+                }
+            }
+        }
+        Some(Self(inverse))
+    }
+
+    pub fn transform_point(&self, point: Vector) -> Vector {
+        let m = &self.0;
+        let x = m[0][0] * point.x() + m[0][1] * point.y() + m[0][2] * point.z() + m[0][3];
+        let y = m[1][0] * point.x() + m[1][1] * point.y() + m[1][2] * point.z() + m[1][3];
+        let z = m[2][0] * point.x() + m[2][1] * point.y() + m[2][2] * point.z() + m[2][3];
+        let w = m[3][0] * point.x() + m[3][1] * point.y() + m[3][2] * point.z() + m[3][3];
+        if w == 1f64 {
+            Vector::new(x, y, z)
+        } else {
+            Vector::new(x / w, y / w, z / w)
+        }
+    }
+
+    /// Transforms a direction, ignoring the translation
+    pub fn transform_vector(&self, vector: Vector) -> Vector {
+        let m = &self.0;
+        Vector::new(
+            m[0][0] * vector.x() + m[0][1] * vector.y() + m[0][2] * vector.z(),
+            m[1][0] * vector.x() + m[1][1] * vector.y() + m[1][2] * vector.z(),
+            m[2][0] * vector.x() + m[2][1] * vector.y() + m[2][2] * vector.z(),
+        )
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+
+    /// Applies `rhs` first and `self` second
+    fn mul(self, rhs: Self) -> Self {
+        let mut result = [[0f64; 4]; 4];
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.0[i][k] * rhs.0[k][j]).sum();
+            }
+        }
+        Self(result)
+    }
+}
+
+#[test]
+fn test_inverse() {
+    let matrix = Matrix4::translation(Vector::new(1f64, -2f64, 3f64))
+        * Matrix4::rotation(Vector::new(1f64, 1f64, 0f64), 0.7)
+        * Matrix4::scaling(Vector::new(2f64, 3f64, 0.5));
+    let inverse = matrix.inverse().unwrap();
+
+    let point = Vector::new(0.3, -4f64, 7f64);
+    let round_trip = inverse.transform_point(matrix.transform_point(point));
+    assert!((round_trip - point).length() < 1e-12);
+
+    let product = matrix * inverse;
+    for i in 0..4 {
+        for j in 0..4 {
+            let expected = if i == j { 1f64 } else { 0f64 };
+            assert!((product.0[i][j] - expected).abs() < 1e-12);
+        }
+    }
+
+    assert_eq!(Matrix4::scaling(Vector::new(1f64, 0f64, 1f64)).inverse(), None);
+}
@@ -1,3 +1,7 @@
 pub mod vec;
 pub mod ray;
-pub mod intersection;
\ No newline at end of file
+pub mod intersection;
+pub mod distribution;
+pub mod aabb;
+pub mod matrix;
+pub mod sampler;
\ No newline at end of file
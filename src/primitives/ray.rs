@@ -4,19 +4,26 @@ use crate::primitives::vec::Vector;
 pub struct Ray {
     pub origin: Vector,
     pub direction: Vector,
+    /// The point in time within the camera shutter interval that the ray was shot at
+    pub time: f64,
 }
 
 impl PartialEq for Ray {
     fn eq(&self, other: &Ray) -> bool {
-        self.origin == other.origin && self.direction == other.direction
+        self.origin == other.origin && self.direction == other.direction && self.time == other.time
     }
 }
 
 impl Ray {
     pub fn new(origin: Vector, direction: Vector) -> Ray {
+        Self::new_at_time(origin, direction, 0f64)
+    }
+
+    pub fn new_at_time(origin: Vector, direction: Vector, time: f64) -> Ray {
         Ray {
             origin: origin,
             direction: direction,
+            time: time,
         }
     }
 
@@ -0,0 +1,249 @@
+use rand::rngs::StdRng;
+use rand::{thread_rng, Error, Rng, RngCore, SeedableRng};
+use std::cell::RefCell;
+use std::f64::consts::PI;
+
+thread_local! {
+    /// The primary sample that the paths traced on this thread draw their random numbers from, if any
+    static CURRENT: RefCell<Option<PrimarySample>> = const { RefCell::new(None) };
+    /// The generator that the paths traced on this thread draw their random numbers from otherwise, if seeded
+    static SEEDED: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+/// The random numbers that paths are sampled with.
+///
+/// They come from [`thread_rng`], unless the path is traced within [`with_primary_sample`] or
+/// [`with_seed`]. Everything
+/// that samples a path draws from here, so that Metropolis light transport can mutate the path.
+pub fn rng() -> SampleRng {
+    SampleRng
+}
+
+/// The random number generator returned by [`rng`]
+#[derive(Debug, Clone, Copy)]
+pub struct SampleRng;
+
+impl RngCore for SampleRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let value = CURRENT.with(|current| current.borrow_mut().as_mut().map(|sample| sample.next_value()));
+        match value {
+            // Uniform floats are made from the highest bits, so this gives back the coordinate
+            Some(value) => (value * 2f64.powi(64)) as u64,
+            None => SEEDED
+                .with(|seeded| seeded.borrow_mut().as_mut().map(|rng| rng.next_u64()))
+                .unwrap_or_else(|| thread_rng().next_u64()),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Runs `f` with the random numbers of [`rng`] on this thread drawn from `sample`, one coordinate each.
+///
+/// Returns the sample back together with the result of `f`.
+pub fn with_primary_sample<F, R>(sample: PrimarySample, f: F) -> (PrimarySample, R)
+where
+    F: FnOnce() -> R,
+{
+    let previous = CURRENT.with(|current| current.replace(Some(sample)));
+    let result = f();
+    let sample = CURRENT.with(|current| current.replace(previous)).unwrap();
+    (sample, result)
+}
+
+/// Runs `f` with the random numbers of [`rng`] on this thread drawn from a generator seeded with
+/// `seed`, so that what `f` samples is reproducible. With `None` they keep coming from [`thread_rng`].
+pub fn with_seed<F, R>(seed: Option<u64>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let seed = match seed {
+        Some(seed) => seed,
+        None => return f(),
+    };
+    let previous = SEEDED.with(|seeded| seeded.replace(Some(StdRng::seed_from_u64(seed))));
+    let result = f();
+    SEEDED.with(|seeded| seeded.replace(previous));
+    result
+}
+
+/// The seed of the `index`th of independent streams of random numbers derived from `seed`, which
+/// scrambles both with SplitMix64 so that neighbouring seeds and indices give unrelated streams
+pub fn stream_seed(seed: u64, index: u64) -> u64 {
+    let mix = |mut z: u64| {
+        z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    mix(mix(seed) ^ index)
+}
+
+/// A coordinate of a primary sample, with its value before the current mutation
+#[derive(Debug, Clone, Copy)]
+struct Coordinate {
+    value: f64,
+    /// The iteration that the value was last changed in
+    modified: u64,
+    backup_value: f64,
+    backup_modified: u64,
+}
+
+/// A point in primary sample space, the unit hypercube of all random numbers that a path is sampled
+/// with, which is mutated by Kelemen-style Metropolis light transport.
+///
+/// Its coordinates are created lazily as the path draws them. Every iteration either replaces all of
+/// them (a large step) or moves each one by a normally distributed offset (a small step). Coordinates
+/// that were not drawn by the last few paths catch up on the mutations they missed when they are used.
+pub struct PrimarySample {
+    rng: StdRng,
+    coordinates: Vec<Coordinate>,
+    /// The coordinate that is drawn next
+    index: usize,
+    iteration: u64,
+    last_large_step: u64,
+    large_step: bool,
+    large_step_probability: f64,
+    mutation_size: f64,
+}
+
+impl PrimarySample {
+    /// Creates a sample whose coordinates are all determined by `seed` until the first mutation.
+    ///
+    /// `mutation_size` is the standard deviation of the offset of a small step.
+    pub fn new(seed: u64, large_step_probability: f64, mutation_size: f64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            coordinates: Vec::new(),
+            index: 0,
+            iteration: 0,
+            last_large_step: 0,
+            large_step: true,
+            large_step_probability: large_step_probability,
+            mutation_size: mutation_size,
+        }
+    }
+
+    /// Whether the current mutation replaces all coordinates
+    pub fn is_large_step(&self) -> bool {
+        self.large_step
+    }
+
+    /// Mutates the sample for the next path
+    pub fn start_iteration(&mut self) {
+        self.iteration += 1;
+        self.large_step = self.rng.gen::<f64>() < self.large_step_probability;
+        self.index = 0;
+    }
+
+    /// Keeps the mutated coordinates
+    pub fn accept(&mut self) {
+        if self.large_step {
+            self.last_large_step = self.iteration;
+        }
+    }
+
+    /// Restores the coordinates from before the mutation
+    pub fn reject(&mut self) {
+        for coordinate in self.coordinates.iter_mut() {
+            if coordinate.modified == self.iteration {
+                coordinate.value = coordinate.backup_value;
+                coordinate.modified = coordinate.backup_modified;
+            }
+        }
+        self.iteration -= 1;
+    }
+
+    /// The next coordinate of the current path
+    pub fn next_value(&mut self) -> f64 {
+        let index = self.index;
+        self.index += 1;
+        if index >= self.coordinates.len() {
+            self.coordinates.resize(
+                index + 1,
+                Coordinate {
+                    value: 0f64,
+                    modified: 0,
+                    backup_value: 0f64,
+                    backup_modified: 0,
+                },
+            );
+        }
+
+        let mut coordinate = self.coordinates[index];
+        // A coordinate that missed the last large step still has to take it
+        if coordinate.modified < self.last_large_step {
+            coordinate.value = self.rng.gen();
+            coordinate.modified = self.last_large_step;
+        }
+        coordinate.backup_value = coordinate.value;
+        coordinate.backup_modified = coordinate.modified;
+        if self.large_step {
+            coordinate.value = self.rng.gen();
+        } else {
+            // All small steps since it was last used add up to a single normal distribution
+            let steps = (self.iteration - coordinate.modified) as f64;
+            let u1 = 1f64 - self.rng.gen::<f64>();
+            let u2 = self.rng.gen::<f64>();
+            let normal = (-2f64 * u1.ln()).sqrt() * (2f64 * PI * u2).cos();
+            coordinate.value += normal * self.mutation_size * steps.sqrt();
+            coordinate.value -= coordinate.value.floor();
+        }
+        coordinate.modified = self.iteration;
+        self.coordinates[index] = coordinate;
+        coordinate.value
+    }
+}
+
+#[test]
+fn test_primary_sample() {
+    let draw = |sample: &mut PrimarySample| (0..4).map(|_| sample.next_value()).collect::<Vec<f64>>();
+
+    // The same seed gives the same first path
+    let mut sample = PrimarySample::new(7, 0f64, 0.01);
+    let first = draw(&mut sample);
+    assert_eq!(first, draw(&mut PrimarySample::new(7, 0f64, 0.01)));
+    assert!(first.iter().all(|value| (0f64..1f64).contains(value)));
+
+    // Small steps stay close, and rejecting them restores the coordinates
+    sample.accept();
+    sample.start_iteration();
+    assert!(!sample.is_large_step());
+    let mutated = draw(&mut sample);
+    for (a, b) in first.iter().zip(mutated.iter()) {
+        let distance = (a - b).abs();
+        assert!(distance.min(1f64 - distance) < 0.1);
+        assert_ne!(a, b);
+    }
+    sample.reject();
+    let restored: Vec<f64> = sample.coordinates.iter().map(|coordinate| coordinate.value).collect();
+    assert_eq!(restored, first);
+
+    // Paths traced with the sample draw their random numbers from it
+    let (_, drawn) = with_primary_sample(PrimarySample::new(7, 0f64, 0.01), || {
+        let mut rng = rng();
+        (0..4).map(|_| rng.gen::<f64>()).collect::<Vec<f64>>()
+    });
+    for (a, b) in first.iter().zip(drawn.iter()) {
+        assert!((a - b).abs() < 1e-12);
+    }
+
+    // Seeded paths draw the same numbers every time, and different streams differ
+    let seeded = |seed: u64| with_seed(Some(seed), || rng().gen::<u64>());
+    assert_eq!(seeded(stream_seed(7, 1)), seeded(stream_seed(7, 1)));
+    assert_ne!(seeded(stream_seed(7, 1)), seeded(stream_seed(7, 2)));
+}
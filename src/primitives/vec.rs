@@ -1,4 +1,5 @@
-use rand::{thread_rng, Rng};
+use crate::primitives::sampler;
+use rand::Rng;
 use std::cmp::PartialEq;
 use std::f64::consts::PI;
 use std::ops::{Add, Div, Mul, Neg, Sub};
@@ -14,17 +15,18 @@ impl Vector {
     pub fn new(x: f64, y: f64, z: f64) -> Vector {
         Self([x, y, z])
     }
+    /// A uniformly distributed direction, so that `normal + random_on_unit_sphere()` is
+    /// distributed like the cosine around the normal. Normalizing a random point in the cube
+    /// around the origin instead would favor the directions towards its corners.
     pub fn random_on_unit_sphere() -> Vector {
-        let mut rng = thread_rng();
-        Self::new(
-            (rng.gen::<f64>() * 2f64) - 1f64,
-            (rng.gen::<f64>() * 2f64) - 1f64,
-            (rng.gen::<f64>() * 2f64) - 1f64,
-        )
-        .normalize()
+        let mut rng = sampler::rng();
+        let z = (rng.gen::<f64>() * 2f64) - 1f64;
+        let angle = rng.gen::<f64>() * 2f64 * PI;
+        let radius = (1f64 - z * z).max(0f64).sqrt();
+        Self::new(radius * angle.cos(), radius * angle.sin(), z)
     }
     pub fn random_in_unit_sphere() -> Vector {
-        let mut rng = thread_rng();
+        let mut rng = sampler::rng();
         let vec = Self::new(
             (rng.gen::<f64>() * 2f64) - 1f64,
             (rng.gen::<f64>() * 2f64) - 1f64,
@@ -46,6 +48,10 @@ impl Vector {
     pub fn z(&self) -> f64 {
         self.0[2]
     }
+    /// The component along the axis with the given index (0 = x, 1 = y, 2 = z)
+    pub fn axis(&self, index: usize) -> f64 {
+        self.0[index]
+    }
     pub fn length(&self) -> f64 {
         (self.x() * self.x() + self.y() * self.y() + self.z() * self.z()).sqrt()
     }
@@ -70,6 +76,17 @@ impl Vector {
         ])
     }
 
+    /// Two unit vectors that form a right-handed orthonormal basis together with this unit vector
+    pub fn orthonormal_basis(&self) -> (Vector, Vector) {
+        let sign = 1f64.copysign(self.z());
+        let a = -1f64 / (sign + self.z());
+        let b = self.x() * self.y() * a;
+        (
+            Self::new(1f64 + sign * self.x() * self.x() * a, sign * b, -sign * self.x()),
+            Self::new(b, sign + self.y() * self.y() * a, -self.y()),
+        )
+    }
+
     pub fn reflect(self, normalized_other: &Self) -> Self {
         self - *normalized_other * (self.dot(normalized_other) * 2f64)
     }
@@ -161,6 +178,11 @@ impl Color {
         Self([r / 255f64, g / 255f64, b / 255f64])
     }
 
+    /// A color from linear channels where one is full intensity, e.g. radiance from an HDR image
+    pub fn from_linear(r: f64, g: f64, b: f64) -> Color {
+        Self([r, g, b])
+    }
+
     pub fn r(&self) -> f64 {
         self.0[0]
     }
@@ -170,6 +192,23 @@ impl Color {
     pub fn b(&self) -> f64 {
         self.0[2]
     }
+    /// The channel with the given index (0 = r, 1 = g, 2 = b)
+    pub fn channel(&self, index: usize) -> f64 {
+        self.0[index]
+    }
+    /// Applies `f` to every channel
+    pub fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Self([f(self.0[0]), f(self.0[1]), f(self.0[2])])
+    }
+    pub fn max_channel(&self) -> f64 {
+        self.0[0].max(self.0[1]).max(self.0[2])
+    }
+    pub fn min_channel(&self) -> f64 {
+        self.0[0].min(self.0[1]).min(self.0[2])
+    }
+    pub fn average(&self) -> f64 {
+        (self.0[0] + self.0[1] + self.0[2]) / 3f64
+    }
     pub fn pow(self, rhs: f64) -> Self {
         Self([
             self.0[0].powf(rhs),
@@ -180,10 +219,15 @@ impl Color {
     pub fn abs(self) -> Self {
         Self([self.0[0].abs(), self.0[1].abs(), self.0[2].abs()])
     }
+    /// The perceived brightness (Rec. 709)
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
     pub fn less_than(&self, rhs: Color) -> bool {
         self.r() < rhs.r() && self.g() < rhs.g() && self.b() < rhs.b()
     }
     pub const BLACK: Self = Self([0f64, 0f64, 0f64]);
+    pub const WHITE: Self = Self([1f64, 1f64, 1f64]);
 }
 
 impl Add for Color {
@@ -221,6 +265,13 @@ impl Mul<Color> for Color {
     }
 }
 
+impl Mul<f64> for Color {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self {
+        Self([self.0[0] * rhs, self.0[1] * rhs, self.0[2] * rhs])
+    }
+}
+
 impl Div<f64> for Color {
     type Output = Self;
     fn div(self, rhs: f64) -> Self {
@@ -0,0 +1,120 @@
+use super::{
+    cancel::CancellationToken,
+    progress::{ProgressObserver, Silent, Tracker},
+    raw::{RawDot, RawImage},
+    settings::RenderSettings,
+    stats::{self, RenderStats},
+    RenderError, Renderer,
+};
+use crate::integrators::bidirectional::Bidirectional;
+use crate::objects::scene::Scene;
+use crate::primitives::sampler;
+use crate::primitives::vec::Color;
+
+use rayon::prelude::*;
+use std::sync::Mutex;
+
+/// Renders with [`Bidirectional`] path tracing instead of [`Scene::trace_ray`].
+///
+/// Light paths splat onto any pixel, so every pixel ends up with a single dot of its final color.
+/// Every vertex of a camera path is connected with every vertex of a light path, so a large maximal
+/// depth is expensive. It converges faster than the other renderers on caustics, e.g. below glass:
+///
+/// ```ignore
+/// let mut renderer = BidirectionalRenderer::new(&scene, settings);
+/// ```
+pub struct BidirectionalRenderer<'a> {
+    scene: &'a Scene<'a>,
+    settings: RenderSettings,
+    progress: Box<dyn ProgressObserver>,
+    cancellation: CancellationToken,
+}
+
+impl<'a> BidirectionalRenderer<'a> {
+    fn trace_image(&self, img: &mut RawImage) {
+        let integrator = Bidirectional::new(self.scene);
+        let samples_per_pixel = self.settings.sampler.count(self.settings.samples_per_pixel);
+        let (width, height) = (img.width, img.height);
+        let splats: Vec<Mutex<Color>> = (0..width * height).map(|_| Mutex::new(Color::BLACK)).collect();
+
+        let phase = stats::phase("tracing");
+        let tracker = Tracker::new(self.progress.as_ref(), width);
+
+        let columns: Vec<Vec<Color>> = (0..width)
+            .into_par_iter()
+            .map(|pixel_x| {
+                if self.cancellation.is_cancelled() {
+                    return Vec::new();
+                }
+                let mut splat = |x: f64, y: f64, color: Color| {
+                    // Light that lands outside of the crop window is lost
+                    if let Some((splat_x, splat_y)) = self.settings.film_pixel(x, y, width, height) {
+                        let mut pixel = splats[splat_x * height + splat_y].lock().unwrap();
+                        *pixel = *pixel + color;
+                    }
+                };
+                let column = (0..height)
+                    .map(|pixel_y| {
+                        let index = (pixel_x * height + pixel_y) as u64;
+                        sampler::with_seed(self.settings.seed_for(0, index), || {
+                            let mut sum = Color::BLACK;
+                            for (x, y) in self.settings.film_samples(pixel_x, pixel_y, width, height, samples_per_pixel) {
+                                sum = sum + integrator.sample(x, y, self.settings.max_depth, &mut splat);
+                            }
+                            stats::count_samples(samples_per_pixel);
+                            sum / samples_per_pixel as f64
+                        })
+                    })
+                    .collect();
+                tracker.advance(1, (height * samples_per_pixel) as u64);
+                column
+            })
+            .collect();
+        drop(phase);
+
+        // Every pixel traced one light path per sample, which splatted anywhere on the film, so the
+        // splats are divided by the light paths per area of a pixel. Light paths of columns that were
+        // skipped after cancelling are missing everywhere.
+        let done = columns.iter().filter(|column| !column.is_empty()).count();
+        let pixel_area = self.settings.crop.area() / (width * height) as f64;
+        let samples = (samples_per_pixel * done * height) as f64 * pixel_area;
+        for (pixel_x, column) in columns.iter().enumerate() {
+            for (pixel_y, color) in column.iter().enumerate() {
+                let splatted = *splats[pixel_x * height + pixel_y].lock().unwrap();
+                img.pixel(pixel_x, pixel_y)
+                    .lock()
+                    .unwrap()
+                    .add_dot(RawDot::new(pixel_x as f64, pixel_y as f64, *color + splatted / samples));
+            }
+        }
+        tracker.finish();
+    }
+}
+
+impl<'a> Renderer<'a> for BidirectionalRenderer<'a> {
+    fn new(scene: &'a Scene, settings: RenderSettings) -> Self {
+        Self {
+            scene: scene,
+            settings: settings,
+            progress: Box::new(Silent),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    fn render(&self, img: &mut RawImage) -> Result<RenderStats, RenderError> {
+        self.settings.require_light("bidirectional path tracing")?;
+        self.settings.run(img, &self.cancellation, |img| self.trace_image(img))
+    }
+
+    fn set_progress_observer(&mut self, observer: Box<dyn ProgressObserver>) {
+        self.progress = observer;
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+}
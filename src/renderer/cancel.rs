@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Stops a render from another thread.
+///
+/// Renderers check the token between columns, chains or iterations and return early once it is
+/// cancelled. The image then holds what was rendered so far, and pixels that were not rendered yet are black.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all renders that share this token, or a clone of it
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[test]
+fn test_cancellation() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::objects::scene::Scene;
+    use crate::primitives::vec::Color;
+    use crate::renderer::fixed_samples::FixedSamplesRenderer;
+    use crate::renderer::progress::{Progress, ProgressObserver};
+    use crate::renderer::raw::RawImage;
+    use crate::renderer::settings::RenderSettings;
+    use crate::renderer::{RenderError, Renderer};
+
+    /// Cancels the render once the first column is done
+    struct CancelAfterFirst(CancellationToken);
+    impl ProgressObserver for CancelAfterFirst {
+        fn update(&self, _progress: &Progress) {
+            self.0.cancel();
+        }
+    }
+
+    let camera = test_camera();
+    let scene = Scene::new(&camera, Color::WHITE, 0.0001);
+    let token = CancellationToken::new();
+    let settings = RenderSettings {
+        samples_per_pixel: 4,
+        ..RenderSettings::default()
+    };
+    let mut renderer = FixedSamplesRenderer::new(&scene, settings);
+    renderer.set_cancellation_token(token.clone());
+    renderer.set_progress_observer(Box::new(CancelAfterFirst(token.clone())));
+    let mut img = RawImage::new(3, 2);
+
+    // Only the first column is rendered, and the rest of the image is black
+    assert_eq!(renderer.render(&mut img), Err(RenderError::Cancelled));
+    for y in 0..2 {
+        assert_eq!(img.pixel(0, y).lock().unwrap().dots.len(), 4);
+        assert_eq!(img.pixel(0, y).lock().unwrap().color(), Color::WHITE);
+        for x in 1..3 {
+            assert!(img.pixel(x, y).lock().unwrap().dots.is_empty());
+            assert_eq!(img.pixel(x, y).lock().unwrap().color(), Color::BLACK);
+        }
+    }
+}
@@ -1,65 +1,85 @@
 use super::{
+    cancel::CancellationToken,
+    progress::{ProgressObserver, Silent, Tracker},
     raw::{RawDot, RawImage},
-    Renderer,
+    settings::RenderSettings,
+    stats::{self, RenderStats},
+    RenderError, Renderer,
 };
 use crate::cameras::Camera;
 use crate::objects::scene::Scene;
-
-use rand::{thread_rng, Rng};
+use crate::primitives::sampler;
 
 
 pub struct FixedSamplesRenderer<'a> {
     scene: &'a Scene<'a>,
-    samples_per_pixel: usize,
+    settings: RenderSettings,
+    progress: Box<dyn ProgressObserver>,
+    cancellation: CancellationToken,
 }
 
 impl<'a> FixedSamplesRenderer<'a> {
-    pub fn set_samples_per_pixel(&mut self, samples: usize) {
-        self.samples_per_pixel = samples;
+    fn trace_image(&self, img: &mut RawImage) {
+        let _phase = stats::phase("tracing");
+        let tracker = Tracker::new(self.progress.as_ref(), img.width);
+        for pixel_x in 0..img.width {
+            if self.cancellation.is_cancelled() {
+                break;
+            }
+            let mut samples = 0;
+            for pixel_y in 0..img.height {
+                let index = (pixel_x * img.height + pixel_y) as u64;
+                sampler::with_seed(self.settings.seed_for(0, index), || {
+                    let film_samples = self.settings.film_samples(
+                        pixel_x,
+                        pixel_y,
+                        img.width,
+                        img.height,
+                        self.settings.samples_per_pixel,
+                    );
+                    for (x, y) in film_samples.iter() {
+                        let ray = self.scene.camera.get_ray(*x, *y);
+
+                        img.pixel(pixel_x, pixel_y).lock().unwrap().add_dot(RawDot::new(
+                            *x,
+                            *y,
+                            self.settings.integrator.trace(self.scene, &ray, self.settings.max_depth),
+                        ));
+                    }
+                    samples += film_samples.len();
+                    stats::count_samples(film_samples.len());
+                });
+                // pixel.finalize();
+            }
+            tracker.advance(1, samples as u64);
+        }
+        tracker.finish();
     }
 }
 
 impl<'a> Renderer<'a> for FixedSamplesRenderer<'a> {
-    fn new(scene: &'a Scene) -> Self {
+    fn new(scene: &'a Scene, settings: RenderSettings) -> Self {
         Self {
             scene: scene,
-            samples_per_pixel: 1,
+            settings: settings,
+            progress: Box::new(Silent),
+            cancellation: CancellationToken::new(),
         }
     }
 
-    fn render(&self, img: &mut RawImage) {
-        let max_depth = 100;
-        let raster_size = (self.samples_per_pixel as f64).sqrt() as usize;
-        let raster_width: f64 = 1f64 / (raster_size as f64);
-        let mut rng = thread_rng();
-        for pixel_x in 0..img.width {
-            for pixel_y in 0..img.height {
-                if pixel_y == 0 {
-                    println!("Pixel: {} {}", pixel_x, pixel_y);
-                }
-                for i in 0..raster_size {
-                    let left = ((pixel_x as f64) + ((i as f64) * (raster_width as f64)))
-                        / (img.width as f64);
-                    //println!("X: {}, Left: {}", (pixel.x as f64 / img.width as f64), left);
-                    for j in 0..raster_size {
-                        let top = ((pixel_y as f64) + ((j as f64) * (raster_width as f64)))
-                            / (img.height as f64);
+    fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
 
-                        //Generate a random point in that raster
-                        let x: f64 = left + (rng.gen::<f64>() * raster_width / (img.width as f64));
-                        let y: f64 = top + (rng.gen::<f64>() * raster_width / (img.height as f64));
+    fn render(&self, img: &mut RawImage) -> Result<RenderStats, RenderError> {
+        self.settings.run(img, &self.cancellation, |img| self.trace_image(img))
+    }
 
-                        let ray = self.scene.camera.get_ray(x, y);
+    fn set_progress_observer(&mut self, observer: Box<dyn ProgressObserver>) {
+        self.progress = observer;
+    }
 
-                        img.pixel(pixel_x, pixel_y).lock().unwrap().add_dot(RawDot::new(
-                            x,
-                            y,
-                            self.scene.trace_ray(&ray, max_depth),
-                        ));
-                    }
-                }
-                // pixel.finalize();
-            }
-        }
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
     }
 }
@@ -0,0 +1,154 @@
+use super::{
+    cancel::CancellationToken,
+    progress::{ProgressObserver, Silent, Tracker},
+    raw::{RawDot, RawImage},
+    settings::RenderSettings,
+    stats::{self, RenderStats},
+    RenderError, Renderer,
+};
+use crate::integrators::metropolis::Metropolis;
+use crate::objects::scene::Scene;
+use crate::primitives::sampler;
+use crate::primitives::vec::Color;
+
+use atomic_counter::{AtomicCounter, RelaxedCounter};
+
+use rand::Rng;
+use rayon::prelude::*;
+use std::sync::Mutex;
+
+/// Renders with [`Metropolis`] light transport, for light that reaches the camera through narrow
+/// paths that [`Scene::trace_ray`] rarely finds.
+///
+/// The chains start at bootstrap paths picked by their luminance and splat onto any pixel, so every
+/// pixel ends up with a single dot of its final color. The samples per pixel of the settings are
+/// the mean number of mutations per pixel.
+pub struct MetropolisRenderer<'a> {
+    scene: &'a Scene<'a>,
+    settings: RenderSettings,
+    bootstrap_samples: usize,
+    chains: usize,
+    large_step_probability: f64,
+    mutation_size: f64,
+    progress: Box<dyn ProgressObserver>,
+    cancellation: CancellationToken,
+}
+
+impl<'a> MetropolisRenderer<'a> {
+    /// The number of independent paths that estimate the brightness of the image. Defaults to 100000.
+    pub fn set_bootstrap_samples(&mut self, samples: usize) {
+        self.bootstrap_samples = samples;
+    }
+
+    /// The number of Markov chains, which run in parallel. Defaults to 1000.
+    pub fn set_chains(&mut self, chains: usize) {
+        self.chains = chains;
+    }
+
+    /// The probability of sampling an independent path instead of a similar one. Defaults to 0.3.
+    pub fn set_large_step_probability(&mut self, probability: f64) {
+        self.large_step_probability = probability;
+    }
+
+    /// The standard deviation of the offset that small steps move every random number of a path by.
+    /// Larger mutations explore faster but are accepted less often. Defaults to 0.01.
+    pub fn set_mutation_size(&mut self, size: f64) {
+        self.mutation_size = size;
+    }
+
+    fn trace_image(&self, img: &mut RawImage) {
+        let mut integrator = Metropolis::new(
+            self.scene,
+            self.settings.max_depth,
+            self.large_step_probability,
+            self.mutation_size,
+        );
+        integrator.set_crop_window(self.settings.crop);
+        let (width, height) = (img.width, img.height);
+        let phase = stats::phase("bootstrap");
+        let bootstrap = integrator.bootstrap(self.bootstrap_samples);
+        drop(phase);
+        let chains = self.chains.max(1);
+        let mutations = (self.settings.samples_per_pixel * width * height).div_ceil(chains);
+        let splats: Vec<Mutex<Color>> = (0..width * height).map(|_| Mutex::new(Color::BLACK)).collect();
+
+        let phase = stats::phase("chains");
+        let tracker = Tracker::new(self.progress.as_ref(), chains);
+
+        let done = RelaxedCounter::new(0);
+        (0..chains).into_par_iter().for_each(|chain| {
+            if self.cancellation.is_cancelled() {
+                return;
+            }
+            sampler::with_seed(self.settings.seed_for(0, chain as u64), || {
+                // Stratify the starting paths over the bootstrap distribution
+                let u = (chain as f64 + sampler::rng().gen::<f64>()) / chains as f64;
+                let seed = match bootstrap.sample_seed(u) {
+                    Some(seed) => seed,
+                    None => {
+                        done.inc();
+                        tracker.advance(1, 0);
+                        return;
+                    }
+                };
+                let mut splat = |x: f64, y: f64, color: Color| {
+                    if let Some((splat_x, splat_y)) = self.settings.film_pixel(x, y, width, height) {
+                        let mut pixel = splats[splat_x * height + splat_y].lock().unwrap();
+                        *pixel = *pixel + color;
+                    }
+                };
+                integrator.run_chain(seed, mutations, &mut splat);
+                done.inc();
+                tracker.advance(1, mutations as u64);
+            });
+        });
+        drop(phase);
+        tracker.finish();
+
+        if done.get() == 0 {
+            return;
+        }
+        let scale = bootstrap.normalization * (width * height) as f64 / (done.get() * mutations) as f64;
+        for pixel_x in 0..width {
+            for pixel_y in 0..height {
+                let splatted = *splats[pixel_x * height + pixel_y].lock().unwrap();
+                img.pixel(pixel_x, pixel_y)
+                    .lock()
+                    .unwrap()
+                    .add_dot(RawDot::new(pixel_x as f64, pixel_y as f64, splatted * scale));
+            }
+        }
+    }
+}
+
+impl<'a> Renderer<'a> for MetropolisRenderer<'a> {
+    fn new(scene: &'a Scene, settings: RenderSettings) -> Self {
+        Self {
+            scene: scene,
+            settings: settings,
+            bootstrap_samples: 100000,
+            chains: 1000,
+            large_step_probability: 0.3,
+            mutation_size: 0.01,
+            progress: Box::new(Silent),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    fn render(&self, img: &mut RawImage) -> Result<RenderStats, RenderError> {
+        self.settings.require_light("Metropolis light transport")?;
+        self.settings.run(img, &self.cancellation, |img| self.trace_image(img))
+    }
+
+    fn set_progress_observer(&mut self, observer: Box<dyn ProgressObserver>) {
+        self.progress = observer;
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+}
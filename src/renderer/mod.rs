@@ -2,11 +2,61 @@ pub mod raw;
 pub mod fixed_samples;
 pub mod std_div_renderer;
 pub mod combined_renderer;
+pub mod bidirectional;
+pub mod cancel;
+pub mod metropolis;
+pub mod photon_mapping;
+pub mod progress;
+pub mod settings;
+pub mod stats;
 
 use crate::objects::scene::Scene;
+use crate::renderer::cancel::CancellationToken;
+use crate::renderer::progress::ProgressObserver;
 use crate::renderer::raw::RawImage;
+use crate::renderer::settings::RenderSettings;
+use crate::renderer::stats::RenderStats;
+
+use std::error::Error;
+use std::fmt;
 
 pub trait Renderer<'a> {
-    fn new(scene: &'a Scene) -> Self;
-    fn render(&self, raw_image: &mut RawImage);
-}
\ No newline at end of file
+    fn new(scene: &'a Scene, settings: RenderSettings) -> Self;
+
+    fn settings(&self) -> &RenderSettings;
+
+    /// Renders into the image and returns what the render counted
+    fn render(&self, raw_image: &mut RawImage) -> Result<RenderStats, RenderError>;
+
+    /// Reports the progress of renders to `observer`. Renderers are silent by default.
+    fn set_progress_observer(&mut self, observer: Box<dyn ProgressObserver>);
+
+    /// Lets renders be stopped early through `token`, leaving what was rendered so far in the image
+    fn set_cancellation_token(&mut self, token: CancellationToken);
+}
+
+/// Why a render failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderError {
+    /// A setting is out of range, or the image is empty
+    InvalidSettings(String),
+    /// The renderer does not support a setting
+    Unsupported(String),
+    /// The thread pool with the configured number of threads could not be started
+    ThreadPool(String),
+    /// The render was cancelled, and the image holds what was rendered until then
+    Cancelled,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderError::InvalidSettings(reason) => write!(f, "invalid render settings: {}", reason),
+            RenderError::Unsupported(reason) => write!(f, "unsupported render settings: {}", reason),
+            RenderError::ThreadPool(reason) => write!(f, "could not start the render threads: {}", reason),
+            RenderError::Cancelled => write!(f, "the render was cancelled"),
+        }
+    }
+}
+
+impl Error for RenderError {}
@@ -0,0 +1,179 @@
+use super::{
+    cancel::CancellationToken,
+    progress::{ProgressObserver, Silent, Tracker},
+    raw::{RawDot, RawImage},
+    settings::RenderSettings,
+    stats::{self, RenderStats},
+    RenderError, Renderer,
+};
+use crate::integrators::photon::{find_visible_point, trace_photons, PhotonMap, VisiblePoint};
+use crate::objects::scene::Scene;
+use crate::primitives::sampler;
+use crate::primitives::vec::Color;
+
+use rayon::prelude::*;
+use std::f64::consts::PI;
+
+/// Renders with stochastic progressive photon mapping (Hachisuka and Jensen), which converges on
+/// caustics like light focused by glass onto a diffuse floor much faster than path tracing.
+///
+/// Every one of the samples per pixel is an iteration, which traces one path per pixel from the camera
+/// to the first Lambertian lobe it samples, emits photons from the emissive objects and gathers the
+/// photons around every such visible point.
+/// The radius of every pixel starts at the initial radius and shrinks with the photons it finds, so
+/// that the result converges. With an `alpha` of one it stays fixed, which is classic photon mapping
+/// averaged over the iterations.
+///
+/// The environment only lights diffuse surfaces directly, and media are not supported.
+pub struct PhotonMappingRenderer<'a> {
+    scene: &'a Scene<'a>,
+    settings: RenderSettings,
+    photons_per_iteration: usize,
+    initial_radius: f64,
+    alpha: f64,
+    progress: Box<dyn ProgressObserver>,
+    cancellation: CancellationToken,
+}
+
+/// What a pixel has gathered over all iterations
+#[derive(Debug, Clone, Copy)]
+struct Statistics {
+    radius: f64,
+    /// The number of photons that the radius accounts for
+    photons: f64,
+    /// The gathered flux within the radius
+    flux: Color,
+    /// The light that reached the camera without photons
+    direct: Color,
+}
+
+impl<'a> PhotonMappingRenderer<'a> {
+    pub fn set_photons_per_iteration(&mut self, photons: usize) {
+        self.photons_per_iteration = photons;
+    }
+
+    /// The radius around visible points that photons are gathered from in the first iteration,
+    /// in world units. Defaults to 0.1.
+    pub fn set_initial_radius(&mut self, radius: f64) {
+        self.initial_radius = radius;
+    }
+
+    /// The fraction of newly found photons that is kept when the radius shrinks, in (0, 1].
+    /// Smaller values shrink the radius faster. Defaults to 0.7.
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.alpha = alpha;
+    }
+
+    fn trace_image(&self, img: &mut RawImage) {
+        let (width, height) = (img.width, img.height);
+        let mut statistics = vec![
+            Statistics {
+                radius: self.initial_radius,
+                photons: 0f64,
+                flux: Color::BLACK,
+                direct: Color::BLACK,
+            };
+            width * height
+        ];
+
+        let tracker = Tracker::new(self.progress.as_ref(), self.settings.samples_per_pixel);
+        let mut iterations = 0;
+        while iterations < self.settings.samples_per_pixel && !self.cancellation.is_cancelled() {
+            let phase = stats::phase("camera paths");
+            let visible_points: Vec<Option<VisiblePoint>> = statistics
+                .par_iter_mut()
+                .enumerate()
+                .map(|(i, pixel)| {
+                    sampler::with_seed(self.settings.seed_for(2 * iterations as u64, i as u64), || {
+                        let (x, y) = self.settings.film_samples(i / height, i % height, width, height, 1)[0];
+                        let ray = self.scene.camera.get_ray(x, y);
+                        let (direct, visible_point) = find_visible_point(self.scene, &ray, self.settings.max_depth);
+                        pixel.direct = pixel.direct + direct;
+                        visible_point
+                    })
+                })
+                .collect();
+            drop(phase);
+
+            let phase = stats::phase("photon tracing");
+            let map = PhotonMap::new(trace_photons(
+                self.scene,
+                self.photons_per_iteration,
+                self.settings.max_depth,
+                self.settings.seed_for(2 * iterations as u64 + 1, 0),
+            ));
+            drop(phase);
+
+            let _phase = stats::phase("gathering");
+
+            statistics
+                .par_iter_mut()
+                .zip(visible_points.par_iter())
+                .for_each(|(pixel, visible_point)| {
+                    let visible_point = match visible_point {
+                        Some(visible_point) => visible_point,
+                        None => return,
+                    };
+                    let (found, flux) = visible_point.gather(&map, pixel.radius);
+                    if found == 0 {
+                        return;
+                    }
+                    let photons = pixel.photons + self.alpha * found as f64;
+                    let shrink = photons / (pixel.photons + found as f64);
+                    pixel.radius *= shrink.sqrt();
+                    pixel.flux = (pixel.flux + flux) * shrink;
+                    pixel.photons = photons;
+                });
+            tracker.advance(1, (width * height) as u64);
+            iterations += 1;
+        }
+
+        tracker.finish();
+
+        if iterations == 0 {
+            return;
+        }
+        let emitted = (iterations * self.photons_per_iteration) as f64;
+        for (i, pixel) in statistics.iter().enumerate() {
+            let photons = pixel.flux / (emitted * PI * pixel.radius * pixel.radius);
+            let color = pixel.direct / iterations as f64 + photons;
+            let (pixel_x, pixel_y) = (i / height, i % height);
+            stats::count_samples(iterations);
+            img.pixel(pixel_x, pixel_y)
+                .lock()
+                .unwrap()
+                .add_dot(RawDot::new(pixel_x as f64, pixel_y as f64, color));
+        }
+    }
+}
+
+impl<'a> Renderer<'a> for PhotonMappingRenderer<'a> {
+    fn new(scene: &'a Scene, settings: RenderSettings) -> Self {
+        Self {
+            scene: scene,
+            settings: settings,
+            photons_per_iteration: 100000,
+            initial_radius: 0.1,
+            alpha: 0.7,
+            progress: Box::new(Silent),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    fn render(&self, img: &mut RawImage) -> Result<RenderStats, RenderError> {
+        self.settings.require_light("photon mapping")?;
+        self.settings.run(img, &self.cancellation, |img| self.trace_image(img))
+    }
+
+    fn set_progress_observer(&mut self, observer: Box<dyn ProgressObserver>) {
+        self.progress = observer;
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+}
@@ -0,0 +1,217 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How far a render has come
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// The units of work that are done, like columns of pixels or iterations, out of `total`
+    pub done: usize,
+    pub total: usize,
+    pub elapsed: Duration,
+    /// The estimated time until the render is done, once any work is done
+    pub eta: Option<Duration>,
+    /// The samples traced so far, e.g. camera paths or mutations
+    pub samples: u64,
+}
+
+impl Progress {
+    /// The done fraction of the work, in [0, 1]
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1f64
+        } else {
+            self.done as f64 / self.total as f64
+        }
+    }
+
+    pub fn samples_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0f64 {
+            self.samples as f64 / seconds
+        } else {
+            0f64
+        }
+    }
+}
+
+/// Is told about the progress of a render. Renderers call it from all of their threads.
+pub trait ProgressObserver: Sync + Send {
+    /// Called whenever a unit of work is done
+    fn update(&self, progress: &Progress);
+
+    /// Called once when the render is done
+    fn finish(&self, _progress: &Progress) {}
+}
+
+/// Ignores all progress, which renderers default to
+pub struct Silent;
+
+impl ProgressObserver for Silent {
+    fn update(&self, _progress: &Progress) {}
+}
+
+/// Draws a progress bar with the remaining time and the sample rate on one line of the terminal
+pub struct TerminalProgressBar {
+    width: usize,
+    /// When the bar was drawn last, to draw it at most every `interval`
+    last_drawn: Mutex<Option<Instant>>,
+    interval: Duration,
+}
+
+impl TerminalProgressBar {
+    pub fn new() -> Self {
+        Self {
+            width: 40,
+            last_drawn: Mutex::new(None),
+            interval: Duration::from_millis(100),
+        }
+    }
+
+    fn draw(&self, progress: &Progress) {
+        let filled = ((progress.fraction() * self.width as f64) as usize).min(self.width);
+        let eta = match progress.eta {
+            Some(eta) => format_duration(eta),
+            None => "?".to_string(),
+        };
+        let mut stderr = io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r[{}{}] {:>5.1}% {}/{}, {} left, {:.0} samples/s ",
+            "#".repeat(filled),
+            " ".repeat(self.width - filled),
+            progress.fraction() * 100f64,
+            progress.done,
+            progress.total,
+            eta,
+            progress.samples_per_second(),
+        );
+        let _ = stderr.flush();
+    }
+}
+
+impl Default for TerminalProgressBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressObserver for TerminalProgressBar {
+    fn update(&self, progress: &Progress) {
+        let mut last_drawn = self.last_drawn.lock().unwrap();
+        if last_drawn.is_some_and(|last| last.elapsed() < self.interval) {
+            return;
+        }
+        *last_drawn = Some(Instant::now());
+        self.draw(progress);
+    }
+
+    fn finish(&self, progress: &Progress) {
+        self.draw(progress);
+        eprintln!();
+    }
+}
+
+/// Formats a duration like 1h02m03s
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
+}
+
+/// Counts the work of a render and tells an observer about it
+pub(crate) struct Tracker<'o> {
+    observer: &'o dyn ProgressObserver,
+    total: usize,
+    done: AtomicUsize,
+    samples: AtomicU64,
+    start: Instant,
+}
+
+impl<'o> Tracker<'o> {
+    pub(crate) fn new(observer: &'o dyn ProgressObserver, total: usize) -> Self {
+        Self {
+            observer: observer,
+            total: total,
+            done: AtomicUsize::new(0),
+            samples: AtomicU64::new(0),
+            start: Instant::now(),
+        }
+    }
+
+    /// Records that `work` units of work with `samples` samples are done
+    pub(crate) fn advance(&self, work: usize, samples: u64) {
+        let done = self.done.fetch_add(work, Ordering::Relaxed) + work;
+        let samples = self.samples.fetch_add(samples, Ordering::Relaxed) + samples;
+        self.observer.update(&self.progress(done, samples));
+    }
+
+    pub(crate) fn finish(&self) {
+        let progress = self.progress(self.done.load(Ordering::Relaxed), self.samples.load(Ordering::Relaxed));
+        self.observer.finish(&progress);
+    }
+
+    fn progress(&self, done: usize, samples: u64) -> Progress {
+        let elapsed = self.start.elapsed();
+        let eta = if done > 0 {
+            Some(elapsed.mul_f64(self.total.saturating_sub(done) as f64 / done as f64))
+        } else {
+            None
+        };
+        Progress {
+            done: done,
+            total: self.total,
+            elapsed: elapsed,
+            eta: eta,
+            samples: samples,
+        }
+    }
+}
+
+#[test]
+fn test_progress() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::objects::scene::Scene;
+    use crate::primitives::vec::Color;
+    use crate::renderer::combined_renderer::CombinedRenderer;
+    use crate::renderer::raw::RawImage;
+    use crate::renderer::settings::RenderSettings;
+    use crate::renderer::Renderer;
+    use std::sync::Arc;
+
+    struct Recorder(Arc<Mutex<Vec<Progress>>>, Arc<Mutex<Option<Progress>>>);
+    impl ProgressObserver for Recorder {
+        fn update(&self, progress: &Progress) {
+            self.0.lock().unwrap().push(*progress);
+        }
+        fn finish(&self, progress: &Progress) {
+            *self.1.lock().unwrap() = Some(*progress);
+        }
+    }
+
+    let camera = test_camera();
+    let scene = Scene::new(&camera, Color::WHITE, 0.0001);
+    let updates = Arc::new(Mutex::new(Vec::new()));
+    let finished = Arc::new(Mutex::new(None));
+    let settings = RenderSettings {
+        samples_per_pixel: 4,
+        ..RenderSettings::default()
+    };
+    let mut renderer = CombinedRenderer::new(&scene, settings);
+    renderer.second_stage_set_samples_per_pixel(0);
+    renderer.set_progress_observer(Box::new(Recorder(updates.clone(), finished.clone())));
+    renderer.render(&mut RawImage::new(4, 3)).unwrap();
+
+    // Every column is reported once, and the render ends with all of them done
+    let updates = updates.lock().unwrap();
+    assert_eq!(updates.len(), 4);
+    assert!(updates.iter().all(|progress| progress.total == 4 && progress.eta.is_some()));
+    let finished = finished.lock().unwrap().unwrap();
+    assert_eq!((finished.done, finished.total, finished.samples), (4, 4, 4 * 3 * 4));
+    assert_eq!(finished.fraction(), 1f64);
+    assert_eq!(format_duration(Duration::from_secs(3723)), "1h02m03s");
+}
@@ -1,4 +1,5 @@
 use crate::primitives::vec::Color;
+use crate::renderer::settings::CropWindow;
 
 use rayon::prelude::*;
 use std::iter::{Iterator};
@@ -39,7 +40,11 @@ impl RawPixel {
             dots: Vec::new(),
         }
     }
+    /// The color of the pixel, which is black until it has any dots, e.g. when a render was cancelled
     pub fn color(&self) -> Color {
+        if self.dots.is_empty() {
+            return Color::BLACK;
+        }
         self.dots
             .iter()
             .map(|dot| dot.color)
@@ -94,6 +99,33 @@ impl RawImage {
         &self.pixels[x][y]
     }
 
+    /// Replaces the pixels of this image of the whole film within `crop` with those of `rendered`,
+    /// an image of only the crop window, e.g. one re-rendered with more samples.
+    ///
+    /// Every pixel takes the nearest pixel of `rendered`, so they line up when the window is
+    /// [snapped](CropWindow::snap) and `rendered` has as many pixels as the window covers here.
+    /// See [`CropWindow`] for an example.
+    pub fn composite(&mut self, rendered: &RawImage, crop: &CropWindow) {
+        if rendered.width == 0 || rendered.height == 0 {
+            return;
+        }
+        let (xs, ys) = crop.pixels(self.width, self.height);
+        for x in xs {
+            for y in ys.clone() {
+                let film_x = (x as f64 + 0.5) / self.width as f64;
+                let film_y = (y as f64 + 0.5) / self.height as f64;
+                let (u, v) = match crop.window_point(film_x, film_y) {
+                    Some(uv) => uv,
+                    None => continue,
+                };
+                let source_x = ((u * rendered.width as f64) as usize).min(rendered.width - 1);
+                let source_y = ((v * rendered.height as f64) as usize).min(rendered.height - 1);
+                let dots = rendered.pixel(source_x, source_y).lock().unwrap().dots.clone();
+                self.pixels[x][y].get_mut().unwrap().dots = dots;
+            }
+        }
+    }
+
     /*
     pub fn par_iter(&self) -> std::iter::Flatten<std::slice::Iter<'_, std::vec::Vec<RawPixel>>> {
         self.pixels.iter().flatten()
@@ -0,0 +1,435 @@
+use super::{
+    cancel::CancellationToken,
+    raw::RawImage,
+    stats::{self, Collector, RenderStats},
+    RenderError,
+};
+use crate::integrators::debug::{self, DebugView};
+use crate::objects::scene::Scene;
+use crate::primitives::ray::Ray;
+use crate::primitives::sampler::{self, stream_seed};
+use crate::primitives::vec::Color;
+
+use rand::Rng;
+use rayon::ThreadPoolBuilder;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// How a render is configured, which all renderers share. Options that only make sense for a
+/// single algorithm, like the photons per iteration of photon mapping, stay setters of its renderer.
+///
+/// Settings are `Copy`, so several renderers can start from the same settings:
+///
+/// ```ignore
+/// let settings = RenderSettings { samples_per_pixel: 250, seed: Some(0), ..RenderSettings::default() };
+/// let mut renderer = FixedSamplesRenderer::new(&scene, settings);
+/// let mut std_div_renderer = StdDivRenderer::new(&scene, RenderSettings { samples_per_pixel: 2000, ..settings });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderSettings {
+    /// The most bounces of a path. Defaults to 50.
+    pub max_depth: u64,
+    /// The camera paths traced per pixel. Photon mapping traces one every iteration, and Metropolis
+    /// light transport takes it as the mean number of mutations per pixel. Defaults to 16.
+    pub samples_per_pixel: usize,
+    pub integrator: Integrator,
+    pub sampler: Sampler,
+    pub filter: Filter,
+    /// Makes renders reproducible, by drawing the random numbers of every pixel from its own stream
+    /// derived from the seed. Renders with the same seed trace the same paths, so rendering into an
+    /// image again only adds new samples with a different seed. `None` draws new random numbers every time.
+    pub seed: Option<u64>,
+    /// The number of threads that renders run on, or `None` for one per core
+    pub threads: Option<usize>,
+    /// The part of the film that the image shows, whatever its resolution. Renders of a small window
+    /// can be composited back into an image of the whole film with [`RawImage::composite`].
+    /// Defaults to the whole film.
+    pub crop: CropWindow,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            max_depth: 50,
+            samples_per_pixel: 16,
+            integrator: Integrator::Light,
+            sampler: Sampler::Stratified,
+            filter: Filter::Box,
+            seed: None,
+            threads: None,
+            crop: CropWindow::FULL,
+        }
+    }
+}
+
+/// A rectangle of the film in the coordinates of [`Camera::get_ray`](crate::cameras::Camera::get_ray),
+/// from the minima up to the maxima.
+///
+/// Rendering a noisy region again with more samples and putting it back into the image:
+///
+/// ```ignore
+/// let crop = CropWindow::new(0.4, 0.6, 0.3, 0.5).snap(img.width, img.height);
+/// let (xs, ys) = crop.pixels(img.width, img.height);
+/// let mut detail = RawImage::new(xs.len(), ys.len());
+/// let detail_settings = RenderSettings { samples_per_pixel: 10000, crop: crop, ..settings };
+/// CombinedRenderer::new(&scene, detail_settings).render(&mut detail)?;
+/// img.composite(&detail, &crop);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropWindow {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+impl CropWindow {
+    pub const FULL: CropWindow = CropWindow {
+        x_min: 0f64,
+        x_max: 1f64,
+        y_min: 0f64,
+        y_max: 1f64,
+    };
+
+    pub fn new(x_min: f64, x_max: f64, y_min: f64, y_max: f64) -> Self {
+        Self {
+            x_min: x_min,
+            x_max: x_max,
+            y_min: y_min,
+            y_max: y_max,
+        }
+    }
+
+    /// The pixels of an image of the whole film of `width` x `height` whose centers lie within the window
+    pub fn pixels(&self, width: usize, height: usize) -> (Range<usize>, Range<usize>) {
+        let bounds = |min: f64, max: f64, size: usize| {
+            let start = ((min * size as f64 - 0.5).ceil().max(0f64) as usize).min(size);
+            let end = ((max * size as f64 - 0.5).ceil().max(0f64) as usize).min(size);
+            start..end.max(start)
+        };
+        (
+            bounds(self.x_min, self.x_max, width),
+            bounds(self.y_min, self.y_max, height),
+        )
+    }
+
+    /// The window moved to the borders of its [`pixels`](CropWindow::pixels), so that an image of it
+    /// with as many pixels lines up with them
+    pub fn snap(&self, width: usize, height: usize) -> Self {
+        let (xs, ys) = self.pixels(width, height);
+        Self::new(
+            xs.start as f64 / width as f64,
+            xs.end as f64 / width as f64,
+            ys.start as f64 / height as f64,
+            ys.end as f64 / height as f64,
+        )
+    }
+
+    /// The point of the film at `(u, v)` of the unit square over the window
+    pub(crate) fn film_point(&self, u: f64, v: f64) -> (f64, f64) {
+        (
+            self.x_min + u * (self.x_max - self.x_min),
+            self.y_min + v * (self.y_max - self.y_min),
+        )
+    }
+
+    /// Where the point of the film lies in the unit square over the window, if it is within it
+    pub(crate) fn window_point(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let u = (x - self.x_min) / (self.x_max - self.x_min);
+        let v = (y - self.y_min) / (self.y_max - self.y_min);
+        if (0f64..1f64).contains(&u) && (0f64..1f64).contains(&v) {
+            Some((u, v))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn area(&self) -> f64 {
+        (self.x_max - self.x_min) * (self.y_max - self.y_min)
+    }
+}
+
+/// What camera paths compute
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Integrator {
+    /// Light, with the light transport algorithm of the renderer, e.g. path tracing with
+    /// [`Scene::trace_ray`] for the renderers of single camera paths
+    Light,
+    /// A debug view of the geometry, to check the geometry and the framing in seconds before
+    /// rendering light. Only the renderers of single camera paths support it.
+    Debug(DebugView),
+}
+
+impl Integrator {
+    /// What the camera path along the ray computes
+    pub(crate) fn trace(&self, scene: &Scene, ray: &Ray, max_depth: u64) -> Color {
+        match self {
+            Integrator::Light => scene.trace_ray(ray, max_depth),
+            Integrator::Debug(view) => debug::trace(scene, ray, view),
+        }
+    }
+}
+
+/// How the camera samples of a pixel are spread over it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampler {
+    /// One random sample in every cell of a square grid over the pixel, which converges faster than
+    /// independent samples. The samples per pixel are rounded down to a square number.
+    Stratified,
+    /// Independent uniformly distributed samples
+    Independent,
+}
+
+impl Sampler {
+    /// The number of points that are sampled for `samples` samples
+    pub(crate) fn count(&self, samples: usize) -> usize {
+        match self {
+            Sampler::Stratified => ((samples as f64).sqrt() as usize).pow(2),
+            Sampler::Independent => samples,
+        }
+    }
+
+    /// `samples` points in the unit square, or fewer for stratified sampling
+    fn pixel_samples(&self, samples: usize) -> Vec<(f64, f64)> {
+        let mut rng = sampler::rng();
+        match self {
+            Sampler::Stratified => {
+                let raster_size = (samples as f64).sqrt() as usize;
+                let raster_width = 1f64 / raster_size as f64;
+                (0..self.count(samples))
+                    .map(|cell| {
+                        let (i, j) = (cell / raster_size, cell % raster_size);
+                        (
+                            (i as f64 + rng.gen::<f64>()) * raster_width,
+                            (j as f64 + rng.gen::<f64>()) * raster_width,
+                        )
+                    })
+                    .collect()
+            }
+            Sampler::Independent => (0..samples).map(|_| (rng.gen(), rng.gen())).collect(),
+        }
+    }
+}
+
+/// The pixel reconstruction filter, which camera samples are distributed like, so that every
+/// sample has the same weight
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Samples spread evenly over the pixel
+    Box,
+    /// Samples within `radius` pixels around the center of the pixel, most of them close to it,
+    /// which blurs a little and aliases less than the box filter
+    Tent { radius: f64 },
+}
+
+impl Filter {
+    /// Warps a point of the unit square to an offset from the center of a pixel, in pixels
+    fn offset(&self, u: (f64, f64)) -> (f64, f64) {
+        match self {
+            Filter::Box => (u.0 - 0.5, u.1 - 0.5),
+            Filter::Tent { radius } => {
+                let tent = |u: f64| {
+                    if u < 0.5 {
+                        radius * ((2f64 * u).sqrt() - 1f64)
+                    } else {
+                        radius * (1f64 - (2f64 - 2f64 * u).sqrt())
+                    }
+                };
+                (tent(u.0), tent(u.1))
+            }
+        }
+    }
+}
+
+impl RenderSettings {
+    /// The points of the film in [0, 1]² to trace the camera paths of a pixel of an image of the crop
+    /// window through, for `samples` samples
+    pub(crate) fn film_samples(
+        &self,
+        pixel_x: usize,
+        pixel_y: usize,
+        width: usize,
+        height: usize,
+        samples: usize,
+    ) -> Vec<(f64, f64)> {
+        self.sampler
+            .pixel_samples(samples)
+            .into_iter()
+            .map(|u| {
+                let (dx, dy) = self.filter.offset(u);
+                self.crop.film_point(
+                    (pixel_x as f64 + 0.5 + dx) / width as f64,
+                    (pixel_y as f64 + 0.5 + dy) / height as f64,
+                )
+            })
+            .collect()
+    }
+
+    /// The pixel of an image of the crop window that a point of the film lands on, for splatting
+    pub(crate) fn film_pixel(&self, x: f64, y: f64, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (u, v) = self.crop.window_point(x, y)?;
+        Some((
+            ((u * width as f64) as usize).min(width - 1),
+            ((v * height as f64) as usize).min(height - 1),
+        ))
+    }
+
+    /// The seed of the random numbers of the `index`th pixel or path of the `pass`th pass over the
+    /// image, if the render is seeded
+    pub(crate) fn seed_for(&self, pass: u64, index: u64) -> Option<u64> {
+        self.seed.map(|seed| stream_seed(stream_seed(seed, pass), index))
+    }
+
+    /// Fails with a debug view, for renderers with their own light transport algorithm
+    pub(crate) fn require_light(&self, renderer: &str) -> Result<(), RenderError> {
+        match self.integrator {
+            Integrator::Light => Ok(()),
+            Integrator::Debug(_) => Err(RenderError::Unsupported(format!("{} does not render debug views", renderer))),
+        }
+    }
+
+    /// Checks the settings and the image, then renders into it on the configured number of threads.
+    ///
+    /// Returns what the render counted, on a thread pool of its own that counts into a collector of
+    /// the render.
+    pub(crate) fn run<F>(&self, img: &mut RawImage, cancellation: &CancellationToken, render: F) -> Result<RenderStats, RenderError>
+    where
+        F: FnOnce(&mut RawImage) + Send,
+    {
+        if self.samples_per_pixel == 0 {
+            return Err(RenderError::InvalidSettings("no samples per pixel".to_string()));
+        }
+        if self.threads == Some(0) {
+            return Err(RenderError::InvalidSettings("no threads".to_string()));
+        }
+        if let Filter::Tent { radius } = self.filter {
+            if radius.is_nan() || radius <= 0f64 {
+                return Err(RenderError::InvalidSettings(format!("tent filter radius {}", radius)));
+            }
+        }
+        let crop = self.crop;
+        if !(0f64 <= crop.x_min && crop.x_min < crop.x_max && crop.x_max <= 1f64)
+            || !(0f64 <= crop.y_min && crop.y_min < crop.y_max && crop.y_max <= 1f64)
+        {
+            return Err(RenderError::InvalidSettings(format!("crop window {:?}", crop)));
+        }
+        if img.width == 0 || img.height == 0 {
+            return Err(RenderError::InvalidSettings(format!("empty image of {}x{}", img.width, img.height)));
+        }
+
+        let collector = Arc::new(Collector::new());
+        let installed = collector.clone();
+        let mut pool = ThreadPoolBuilder::new().start_handler(move |_| stats::install(Some(installed.clone())));
+        if let Some(threads) = self.threads {
+            pool = pool.num_threads(threads);
+        }
+        pool.build()
+            .map_err(|error| RenderError::ThreadPool(error.to_string()))?
+            .install(|| render(img));
+        if cancellation.is_cancelled() {
+            return Err(RenderError::Cancelled);
+        }
+        Ok(collector.take())
+    }
+}
+
+#[test]
+fn test_render_settings() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::renderer::bidirectional::BidirectionalRenderer;
+    use crate::renderer::fixed_samples::FixedSamplesRenderer;
+    use crate::renderer::Renderer;
+    use std::collections::BTreeMap;
+
+    let camera = test_camera();
+    let scene = Scene::new(&camera, Color::WHITE, 0.0001);
+    let settings = RenderSettings {
+        samples_per_pixel: 3,
+        sampler: Sampler::Independent,
+        filter: Filter::Tent { radius: 1f64 },
+        seed: Some(7),
+        threads: Some(2),
+        ..RenderSettings::default()
+    };
+    let render = |settings: RenderSettings| {
+        let mut img = RawImage::new(3, 2);
+        let stats = FixedSamplesRenderer::new(&scene, settings).render(&mut img).unwrap();
+        // Every camera path leaves the empty scene right away
+        assert_eq!(stats.samples_per_pixel, BTreeMap::from([(3, 6)]));
+        assert_eq!((stats.paths, stats.rays, stats.bounces), (18, 18, 0));
+        let dots: Vec<(f64, f64)> = (0..6)
+            .flat_map(|i| img.pixel(i / 2, i % 2).lock().unwrap().dots.clone())
+            .map(|dot| (dot.x, dot.y))
+            .collect();
+        assert_eq!(dots.len(), 18);
+        dots
+    };
+
+    // The same seed samples the same points, within the radius of the filter around the pixels
+    let dots = render(settings);
+    assert_eq!(dots, render(settings));
+    assert_ne!(dots, render(RenderSettings { seed: Some(8), ..settings }));
+    for (i, (x, y)) in dots.iter().enumerate() {
+        let pixel = i / 3;
+        assert!((x * 3f64 - (pixel / 2) as f64 - 0.5).abs() <= 1f64);
+        assert!((y * 2f64 - (pixel % 2) as f64 - 0.5).abs() <= 1f64);
+    }
+    assert_eq!(Sampler::Stratified.pixel_samples(5).len(), Sampler::Stratified.count(5));
+
+    let mut img = RawImage::new(3, 2);
+    let invalid = RenderSettings { samples_per_pixel: 0, ..settings };
+    assert!(matches!(
+        FixedSamplesRenderer::new(&scene, invalid).render(&mut img),
+        Err(RenderError::InvalidSettings(_))
+    ));
+    let debug = RenderSettings { integrator: Integrator::Debug(DebugView::Normals), ..settings };
+    assert!(matches!(
+        BidirectionalRenderer::new(&scene, debug).render(&mut img),
+        Err(RenderError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_crop_window() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::renderer::fixed_samples::FixedSamplesRenderer;
+    use crate::renderer::Renderer;
+
+    let camera = test_camera();
+    let scene = Scene::new(&camera, Color::WHITE, 0.0001);
+
+    // The window covers the pixels 2 to 4 and 2 to 3 of an image of 8x4 of the whole film
+    let crop = CropWindow::new(0.3, 0.6, 0.5, 1f64).snap(8, 4);
+    assert_eq!(crop.pixels(8, 4), (2..5, 2..4));
+    assert_eq!(crop, CropWindow::new(0.25, 0.625, 0.5, 1f64));
+
+    let mut full = RawImage::new(8, 4);
+    let settings = RenderSettings { samples_per_pixel: 1, ..RenderSettings::default() };
+    FixedSamplesRenderer::new(&scene, settings).render(&mut full).unwrap();
+    let mut detail = RawImage::new(3, 2);
+    let cropped = RenderSettings { samples_per_pixel: 4, crop: crop, ..settings };
+    FixedSamplesRenderer::new(&scene, cropped).render(&mut detail).unwrap();
+
+    // The samples of the crop lie within the pixels of the whole film that it replaces
+    full.composite(&detail, &crop);
+    for x in 0..8 {
+        for y in 0..4 {
+            let pixel = full.pixel(x, y).lock().unwrap();
+            if (2..5).contains(&x) && (2..4).contains(&y) {
+                assert_eq!(pixel.dots.len(), 4);
+                for dot in pixel.dots.iter() {
+                    assert!((x as f64..x as f64 + 1f64).contains(&(dot.x * 8f64)));
+                    assert!((y as f64..y as f64 + 1f64).contains(&(dot.y * 4f64)));
+                }
+            } else {
+                assert_eq!(pixel.dots.len(), 1);
+            }
+        }
+    }
+
+    let invalid = RenderSettings { crop: CropWindow::new(0.5, 0.5, 0f64, 1f64), ..settings };
+    assert!(matches!(
+        FixedSamplesRenderer::new(&scene, invalid).render(&mut detail),
+        Err(RenderError::InvalidSettings(_))
+    ));
+}
@@ -0,0 +1,287 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// What is counted while rendering
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Counter {
+    Rays,
+    IntersectionTests,
+    BvhNodes,
+    Paths,
+    Bounces,
+}
+
+const COUNTERS: usize = 5;
+/// Threads add to different shards so that they don't fight over the same cache line
+const SHARDS: usize = 64;
+
+#[repr(align(64))]
+struct Shard([AtomicU64; COUNTERS]);
+
+/// Everything counted by the threads that it is installed on, see [`install`]
+pub(crate) struct Collector {
+    counts: [Shard; SHARDS],
+    /// How many pixels were rendered with how many samples
+    samples: Mutex<BTreeMap<usize, u64>>,
+    phases: Mutex<Vec<(&'static str, Duration)>>,
+}
+
+/// Collects what is counted outside of renders, for [`RenderStats::collect`]
+static PROCESS: Collector = Collector::new();
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed) % SHARDS;
+    /// The collector of the render that this thread works for, if any
+    static CURRENT: RefCell<Option<Arc<Collector>>> = const { RefCell::new(None) };
+}
+
+impl Collector {
+    pub(crate) const fn new() -> Self {
+        Self {
+            counts: [const { Shard([const { AtomicU64::new(0) }; COUNTERS]) }; SHARDS],
+            samples: Mutex::new(BTreeMap::new()),
+            phases: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Takes everything counted so far
+    pub(crate) fn take(&self) -> RenderStats {
+        let mut totals = [0u64; COUNTERS];
+        for shard in self.counts.iter() {
+            for (total, count) in totals.iter_mut().zip(shard.0.iter()) {
+                *total += count.swap(0, Ordering::Relaxed);
+            }
+        }
+        RenderStats {
+            rays: totals[Counter::Rays as usize],
+            intersection_tests: totals[Counter::IntersectionTests as usize],
+            bvh_nodes: totals[Counter::BvhNodes as usize],
+            paths: totals[Counter::Paths as usize],
+            bounces: totals[Counter::Bounces as usize],
+            samples_per_pixel: std::mem::take(&mut *self.samples.lock().unwrap()),
+            phases: self
+                .phases
+                .lock()
+                .unwrap()
+                .drain(..)
+                .map(|(name, duration)| (name.to_string(), duration))
+                .collect(),
+        }
+    }
+}
+
+/// Makes this thread count into `collector`, or into the collector of the process with `None`
+pub(crate) fn install(collector: Option<Arc<Collector>>) {
+    CURRENT.with(|current| *current.borrow_mut() = collector);
+}
+
+fn with_collector<R>(f: impl FnOnce(&Collector) -> R) -> R {
+    CURRENT.with(|current| match current.borrow().as_ref() {
+        Some(collector) => f(collector),
+        None => f(&PROCESS),
+    })
+}
+
+/// Adds `amount` to a counter of the render of this thread
+pub(crate) fn count(counter: Counter, amount: u64) {
+    with_collector(|collector| {
+        SHARD.with(|shard| collector.counts[*shard].0[counter as usize].fetch_add(amount, Ordering::Relaxed))
+    });
+}
+
+/// Records that a pixel was rendered with `samples` samples
+pub(crate) fn count_samples(samples: usize) {
+    with_collector(|collector| *collector.samples.lock().unwrap().entry(samples).or_insert(0) += 1);
+}
+
+/// Measures the wall clock time of a phase of rendering until the returned guard is dropped.
+/// Phases with the same name add up.
+pub(crate) fn phase(name: &'static str) -> Phase {
+    Phase {
+        name: name,
+        start: Instant::now(),
+    }
+}
+
+/// A phase of rendering that is being measured, see [`phase`]
+pub(crate) struct Phase {
+    name: &'static str,
+    start: Instant,
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        with_collector(|collector| {
+            let mut phases = collector.phases.lock().unwrap();
+            match phases.iter_mut().find(|(name, _)| *name == self.name) {
+                Some((_, duration)) => *duration += elapsed,
+                None => phases.push((self.name, elapsed)),
+            }
+        });
+    }
+}
+
+/// Where the time of rendering went.
+///
+/// Every render counts on its own threads, so renders that run at the same time are counted
+/// apart. Bounces are only counted for paths of [`Scene::trace_ray`](crate::objects::scene::Scene::trace_ray).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderStats {
+    /// Rays shot into the scene, including shadow rays
+    pub rays: u64,
+    /// Rays tested against single objects
+    pub intersection_tests: u64,
+    /// Nodes of bounding volume hierarchies whose bounds were tested
+    pub bvh_nodes: u64,
+    pub paths: u64,
+    /// The surfaces and media that paths scattered at
+    pub bounces: u64,
+    /// How many pixels were rendered with how many samples, by the number of samples
+    pub samples_per_pixel: BTreeMap<usize, u64>,
+    /// The wall clock time of the phases of rendering, in the order they first started
+    pub phases: Vec<(String, Duration)>,
+}
+
+impl RenderStats {
+    /// Takes everything counted outside of renders since the last call, or since the process
+    /// started. Renders return what they counted themselves.
+    pub fn collect() -> Self {
+        PROCESS.take()
+    }
+
+    /// The mean number of bounces per path
+    pub fn average_path_length(&self) -> f64 {
+        if self.paths == 0 {
+            0f64
+        } else {
+            self.bounces as f64 / self.paths as f64
+        }
+    }
+
+    /// The mean number of samples per pixel
+    pub fn average_samples_per_pixel(&self) -> f64 {
+        let pixels: u64 = self.samples_per_pixel.values().sum();
+        if pixels == 0 {
+            return 0f64;
+        }
+        let samples: u64 = self
+            .samples_per_pixel
+            .iter()
+            .map(|(samples, count)| *samples as u64 * count)
+            .sum();
+        samples as f64 / pixels as f64
+    }
+
+    pub fn to_json(&self) -> String {
+        let samples: Vec<String> = self
+            .samples_per_pixel
+            .iter()
+            .map(|(samples, pixels)| format!("\"{}\": {}", samples, pixels))
+            .collect();
+        let phases: Vec<String> = self
+            .phases
+            .iter()
+            .map(|(name, duration)| format!("\"{}\": {}", escape(name), duration.as_secs_f64()))
+            .collect();
+        format!(
+            "{{\n  \"rays\": {},\n  \"intersection_tests\": {},\n  \"bvh_nodes\": {},\n  \"paths\": {},\n  \
+             \"bounces\": {},\n  \"average_path_length\": {},\n  \"average_samples_per_pixel\": {},\n  \
+             \"samples_per_pixel\": {{{}}},\n  \"phase_seconds\": {{{}}}\n}}\n",
+            self.rays,
+            self.intersection_tests,
+            self.bvh_nodes,
+            self.paths,
+            self.bounces,
+            self.average_path_length(),
+            self.average_samples_per_pixel(),
+            samples.join(", "),
+            phases.join(", "),
+        )
+    }
+
+    pub fn save_json(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Rays: {}", self.rays)?;
+        writeln!(f, "Intersection tests: {}", self.intersection_tests)?;
+        writeln!(f, "BVH nodes visited: {}", self.bvh_nodes)?;
+        writeln!(f, "Paths: {}, average length: {:.2}", self.paths, self.average_path_length())?;
+        let (min, max) = (
+            self.samples_per_pixel.keys().next().copied().unwrap_or(0),
+            self.samples_per_pixel.keys().last().copied().unwrap_or(0),
+        );
+        writeln!(
+            f,
+            "Samples per pixel: {:.2} on average, from {} to {}",
+            self.average_samples_per_pixel(),
+            min,
+            max
+        )?;
+        for (name, duration) in self.phases.iter() {
+            writeln!(f, "{}: {:.3}s", name, duration.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+/// Escapes a string for a JSON string literal
+fn escape(string: &str) -> String {
+    string.chars().flat_map(|c| match c {
+        '"' | '\\' => vec!['\\', c],
+        '\n' => vec!['\\', 'n'],
+        _ => vec![c],
+    }).collect()
+}
+
+#[test]
+fn test_render_stats() {
+    use crate::cameras::pinhole::test_camera;
+    use crate::materials::phong::PseudoPhong;
+    use crate::objects::scene::Scene;
+    use crate::objects::sphere::Sphere;
+    use crate::primitives::ray::Ray;
+    use crate::primitives::vec::{Color, Vector};
+
+    let camera = test_camera();
+    let mut scene = Scene::new(&camera, Color::WHITE, 0.0001);
+    scene.add_object(Box::new(Sphere::new(
+        Vector::new(0f64, 0f64, -3f64),
+        1f64,
+        Box::new(PseudoPhong::new(0f64, 0f64, Color::BLACK, Color::BLACK)),
+    )));
+
+    // Only what this thread counts while the collector is installed is counted
+    let collector = Arc::new(Collector::new());
+    install(Some(collector.clone()));
+    {
+        let _phase = phase("tracing");
+        let ray = Ray::new(Vector::new(0f64, 0f64, 0f64), Vector::new(0f64, 0f64, -1f64));
+        for _ in 0..10 {
+            scene.trace_ray(&ray, 1);
+        }
+        count_samples(10);
+    }
+    install(None);
+    let stats = collector.take();
+    assert_eq!((stats.rays, stats.intersection_tests, stats.bvh_nodes), (10, 10, 10));
+    assert_eq!((stats.paths, stats.bounces), (10, 10));
+    assert_eq!(stats.samples_per_pixel, BTreeMap::from([(10, 1)]));
+    assert_eq!(stats.phases.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!["tracing"]);
+    assert_eq!(collector.take(), RenderStats::default());
+    let json = stats.to_json();
+    assert!(json.contains(&format!("\"rays\": {},", stats.rays)));
+    assert!(json.contains("\"phase_seconds\": {"));
+}
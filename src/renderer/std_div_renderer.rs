@@ -1,86 +1,98 @@
 use super::{
+    cancel::CancellationToken,
+    progress::{ProgressObserver, Silent, Tracker},
     raw::{RawDot, RawImage},
-    Renderer,
+    settings::RenderSettings,
+    stats::{self, RenderStats},
+    RenderError, Renderer,
 };
 use crate::cameras::Camera;
 use crate::objects::scene::Scene;
 
+use crate::primitives::sampler;
 use crate::primitives::vec::Color;
-use rand::{thread_rng, Rng};
 
 
 pub struct StdDivRenderer<'a> {
     scene: &'a Scene<'a>,
-    samples_per_pixel: usize,
+    settings: RenderSettings,
     min_std_div: Color,
+    progress: Box<dyn ProgressObserver>,
+    cancellation: CancellationToken,
 }
 
 impl<'a> StdDivRenderer<'a> {
-    pub fn set_samples_per_pixel(&mut self, samples: usize) {
-        self.samples_per_pixel = samples;
-    }
     pub fn set_min_std_div(&mut self, std_div: Color) {
         self.min_std_div = std_div;
     }
-}
 
-impl<'a> Renderer<'a> for StdDivRenderer<'a> {
-    fn new(scene: &'a Scene) -> Self {
-        Self {
-            scene: scene,
-            samples_per_pixel: 1,
-            min_std_div: Color::BLACK,
-        }
-    }
-
-    fn render(&self, img: &mut RawImage) {
-        let max_depth = 100;
-        let raster_size = (self.samples_per_pixel as f64).sqrt() as usize;
-        let raster_width: f64 = 1f64 / (raster_size as f64);
-        let mut rng = thread_rng();
-        let mut changed_pixels = 0;
-        let mut unchanged_pixels = 0;
+    fn trace_image(&self, img: &mut RawImage) {
+        let _phase = stats::phase("tracing");
+        let tracker = Tracker::new(self.progress.as_ref(), img.width);
         for pixel_x in 0..img.width {
+            if self.cancellation.is_cancelled() {
+                break;
+            }
+            let mut samples = 0;
             for pixel_y in 0..img.height {
-                if pixel_y == 0 {
-                    println!("StdDivPixel: {} {}", pixel_x, pixel_y);
-                }
                 if !img.pixel(pixel_x, pixel_y).lock().unwrap().std_div().less_than(self.min_std_div) {
-                    changed_pixels += 1;
-
-                    for i in 0..raster_size {
-                        let left = ((pixel_x as f64) + ((i as f64) * (raster_width as f64)))
-                            / (img.width as f64);
-                        //println!("X: {}, Left: {}", (pixel.x as f64 / img.width as f64), left);
-                        for j in 0..raster_size {
-                            let top = ((pixel_y as f64) + ((j as f64) * (raster_width as f64)))
-                                / (img.height as f64);
+                    let index = (pixel_x * img.height + pixel_y) as u64;
+                    sampler::with_seed(self.settings.seed_for(0, index), || {
+                        let film_samples = self.settings.film_samples(
+                            pixel_x,
+                            pixel_y,
+                            img.width,
+                            img.height,
+                            self.settings.samples_per_pixel,
+                        );
+                        samples += film_samples.len();
+                        stats::count_samples(film_samples.len());
 
-                            //Generate a random point in that raster
-                            let x: f64 =
-                                left + (rng.gen::<f64>() * raster_width / (img.width as f64));
-                            let y: f64 =
-                                top + (rng.gen::<f64>() * raster_width / (img.height as f64));
-
-                            let ray = self.scene.camera.get_ray(x, y);
+                        for (x, y) in film_samples.iter() {
+                            let ray = self.scene.camera.get_ray(*x, *y);
 
                             img.pixel(pixel_x, pixel_y).lock().unwrap().add_dot(RawDot::new(
-                                x,
-                                y,
-                                self.scene.trace_ray(&ray, max_depth),
+                                *x,
+                                *y,
+                                self.settings.integrator.trace(self.scene, &ray, self.settings.max_depth),
                             ));
                         }
-                    }
+                    });
                 } else {
-                    unchanged_pixels += 1;
+                    stats::count_samples(0);
                 }
                  img.pixel(pixel_x, pixel_y).lock().unwrap().finalize();
             }
+            tracker.advance(1, samples as u64);
         }
-        println!(
-            "unchanged pixels: {}, changed pixels: {}",
-            unchanged_pixels, changed_pixels
-        );
-        
+        tracker.finish();
+    }
+}
+
+impl<'a> Renderer<'a> for StdDivRenderer<'a> {
+    fn new(scene: &'a Scene, settings: RenderSettings) -> Self {
+        Self {
+            scene: scene,
+            settings: settings,
+            min_std_div: Color::BLACK,
+            progress: Box::new(Silent),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    fn render(&self, img: &mut RawImage) -> Result<RenderStats, RenderError> {
+        self.settings.run(img, &self.cancellation, |img| self.trace_image(img))
+    }
+
+    fn set_progress_observer(&mut self, observer: Box<dyn ProgressObserver>) {
+        self.progress = observer;
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
     }
 }
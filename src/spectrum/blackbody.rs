@@ -0,0 +1,37 @@
+use crate::spectrum::Spectrum;
+
+/// Planck's constant times the speed of light divided by Boltzmann's constant, in meter kelvin
+const SECOND_RADIATION_CONSTANT: f64 = 1.438_776_877e-2;
+/// Wien's displacement constant in meter kelvin
+const WIEN_DISPLACEMENT: f64 = 2.897_771_955e-3;
+
+/// The light emitted by a black body at a temperature in kelvin, like an incandescent bulb at
+/// about 2700 K or the sun at about 5800 K.
+///
+/// The spectrum is normalized to one at its peak, so that the temperature only changes the color.
+#[derive(Debug, Clone, Copy)]
+pub struct Blackbody {
+    temperature: f64,
+    peak: f64,
+}
+
+impl Blackbody {
+    pub fn new(temperature: f64) -> Self {
+        let temperature = temperature.max(1f64);
+        Self {
+            temperature: temperature,
+            peak: planck(WIEN_DISPLACEMENT / temperature, temperature),
+        }
+    }
+}
+
+/// Planck's law up to a constant factor, for a wavelength in meters
+fn planck(wavelength: f64, temperature: f64) -> f64 {
+    1f64 / (wavelength.powi(5) * ((SECOND_RADIATION_CONSTANT / (wavelength * temperature)).exp() - 1f64))
+}
+
+impl Spectrum for Blackbody {
+    fn value(&self, wavelength: f64) -> f64 {
+        planck(wavelength * 1e-9, self.temperature) / self.peak
+    }
+}
@@ -0,0 +1,336 @@
+pub mod blackbody;
+pub mod tabulated;
+
+use crate::primitives::matrix::Matrix4;
+use crate::primitives::vec::{Color, Vector};
+use crate::spectrum::blackbody::Blackbody;
+
+use std::sync::OnceLock;
+
+/// The shortest wavelength in nanometers that is rendered
+pub const WAVELENGTH_MIN: f64 = 360f64;
+/// The longest wavelength in nanometers that is rendered
+pub const WAVELENGTH_MAX: f64 = 830f64;
+/// The color temperature of the light that is rendered as white, close to daylight (D65)
+const WHITE_TEMPERATURE: f64 = 6504f64;
+
+/// A spectral distribution of light or of a reflectance over the wavelength in nanometers.
+///
+/// Closures are spectra, too.
+pub trait Spectrum: Sync + Send {
+    fn value(&self, wavelength: f64) -> f64;
+}
+
+impl<F> Spectrum for F
+where
+    F: Fn(f64) -> f64 + Sync + Send,
+{
+    fn value(&self, wavelength: f64) -> f64 {
+        self(wavelength)
+    }
+}
+
+/// The wavelengths that a path carries: a uniformly sampled hero wavelength and two more at
+/// equal distances, wrapping around the visible range.
+///
+/// Each path thus estimates three points of the spectrum at the cost of one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wavelengths {
+    wavelengths: [f64; 3],
+    /// Whether only the hero wavelength still carries light
+    single: bool,
+}
+
+impl Wavelengths {
+    /// Maps a uniform random number to the hero wavelength
+    pub fn sample(u: f64) -> Self {
+        let range = WAVELENGTH_MAX - WAVELENGTH_MIN;
+        Self {
+            wavelengths: std::array::from_fn(|i| {
+                WAVELENGTH_MIN + (u + i as f64 / 3f64).fract() * range
+            }),
+            single: false,
+        }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.wavelengths[0]
+    }
+
+    pub fn get(&self, index: usize) -> f64 {
+        self.wavelengths[index]
+    }
+
+    /// Drops all but the hero wavelength, e.g. when dispersion sends each wavelength in a
+    /// different direction.
+    ///
+    /// Returns the remaining wavelengths and the factor that the channels of the rest of the path
+    /// have to be weighted with, which moves the light of all wavelengths to the hero.
+    pub fn terminate_secondary(&self) -> (Self, Color) {
+        if self.single {
+            (*self, Color::from_linear(1f64, 0f64, 0f64))
+        } else {
+            let single = Self {
+                wavelengths: self.wavelengths,
+                single: true,
+            };
+            (single, Color::from_linear(3f64, 0f64, 0f64))
+        }
+    }
+
+    /// The values of `spectrum` at the wavelengths, one per color channel
+    pub fn sample_spectrum(&self, spectrum: &dyn Spectrum) -> Color {
+        Color::from_linear(
+            spectrum.value(self.wavelengths[0]),
+            spectrum.value(self.wavelengths[1]),
+            spectrum.value(self.wavelengths[2]),
+        )
+    }
+
+    /// Estimates the linear sRGB color of a spectrum from its values at the wavelengths
+    pub fn to_rgb(&self, values: Color) -> Color {
+        // Every wavelength has the uniform probability density 1 / range
+        let range = WAVELENGTH_MAX - WAVELENGTH_MIN;
+        let xyz = (0..3).fold([0f64; 3], |xyz, i| {
+            let matching = color_matching(self.wavelengths[i]);
+            std::array::from_fn(|j| xyz[j] + values.channel(i) * matching[j] * range / 3f64)
+        });
+        balanced_rgb(xyz)
+    }
+}
+
+/// What the three channels of the colors along a path stand for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channels {
+    /// Linear sRGB
+    Rgb,
+    /// The values of spectra at the given wavelengths
+    Spectral(Wavelengths),
+}
+
+impl Channels {
+    /// Converts a reflectance, transmittance or coefficient given in linear sRGB to the channels
+    pub fn reflectance(&self, rgb: Color) -> Color {
+        match self {
+            Channels::Rgb => rgb,
+            Channels::Spectral(wavelengths) => {
+                let upper = rgb.max_channel().max(0f64);
+                wavelengths.sample_spectrum(&|wavelength| {
+                    upsample(rgb, wavelength).clamp(0f64, upper)
+                })
+            }
+        }
+    }
+
+    /// Converts emitted radiance given in linear sRGB to the channels
+    pub fn illuminant(&self, rgb: Color) -> Color {
+        match self {
+            Channels::Rgb => rgb,
+            Channels::Spectral(wavelengths) => wavelengths.sample_spectrum(&|wavelength| {
+                upsample(rgb, wavelength).max(0f64) * white(wavelength)
+            }),
+        }
+    }
+
+    /// The values of a spectrum of emitted radiance in the channels
+    pub fn spectrum(&self, spectrum: &dyn Spectrum) -> Color {
+        match self {
+            Channels::Rgb => to_rgb(spectrum),
+            Channels::Spectral(wavelengths) => wavelengths.sample_spectrum(spectrum),
+        }
+    }
+
+    /// The hero wavelength of spectral channels
+    pub fn hero(&self) -> Option<f64> {
+        match self {
+            Channels::Rgb => None,
+            Channels::Spectral(wavelengths) => Some(wavelengths.hero()),
+        }
+    }
+
+    /// Drops all but the hero wavelength, see [`Wavelengths::terminate_secondary`]
+    pub fn terminate_secondary(&self) -> (Self, Color) {
+        match self {
+            Channels::Rgb => (Channels::Rgb, Color::WHITE),
+            Channels::Spectral(wavelengths) => {
+                let (wavelengths, factor) = wavelengths.terminate_secondary();
+                (Channels::Spectral(wavelengths), factor)
+            }
+        }
+    }
+
+    /// Converts the channels to linear sRGB for the film
+    pub fn to_rgb(&self, values: Color) -> Color {
+        match self {
+            Channels::Rgb => values,
+            Channels::Spectral(wavelengths) => wavelengths.to_rgb(values),
+        }
+    }
+}
+
+/// The linear sRGB color of a spectrum of emitted radiance.
+///
+/// Colors are white balanced, so that light with the spectrum of a black body at 6504 K is white.
+pub fn to_rgb(spectrum: &dyn Spectrum) -> Color {
+    balanced_rgb(integrate_xyz(|wavelength| spectrum.value(wavelength)))
+}
+
+/// The CIE 1931 XYZ color of a spectrum, normalized so that a constant spectrum of one has Y = 1
+pub fn to_xyz(spectrum: &dyn Spectrum) -> [f64; 3] {
+    integrate_xyz(|wavelength| spectrum.value(wavelength))
+}
+
+/// The CIE 1931 standard observer color matching functions x̄, ȳ and z̄, divided by the integral of ȳ
+fn color_matching(wavelength: f64) -> [f64; 3] {
+    let [x, y, z] = cie_1931(wavelength);
+    let y_integral = tables().y_integral;
+    [x / y_integral, y / y_integral, z / y_integral]
+}
+
+/// The color matching functions as fitted with piecewise Gaussians by Wyman, Sloan and Shirley
+fn cie_1931(wavelength: f64) -> [f64; 3] {
+    let gaussian = |mean: f64, below: f64, above: f64| {
+        let deviation = if wavelength < mean { below } else { above };
+        (-0.5 * ((wavelength - mean) / deviation).powi(2)).exp()
+    };
+    [
+        1.056 * gaussian(599.8, 37.9, 31.0) + 0.362 * gaussian(442.0, 16.0, 26.7)
+            - 0.065 * gaussian(501.1, 20.4, 26.2),
+        0.821 * gaussian(568.8, 46.9, 40.5) + 0.286 * gaussian(530.9, 16.3, 31.1),
+        1.217 * gaussian(437.0, 11.8, 36.0) + 0.681 * gaussian(459.0, 26.0, 13.8),
+    ]
+}
+
+/// Integrates a spectrum against the color matching functions in steps of one nanometer
+fn integrate_xyz(spectrum: impl Fn(f64) -> f64) -> [f64; 3] {
+    let y_integral = tables().y_integral;
+    integrate_cie_1931(spectrum).map(|value| value / y_integral)
+}
+
+fn integrate_cie_1931(spectrum: impl Fn(f64) -> f64) -> [f64; 3] {
+    let steps = (WAVELENGTH_MAX - WAVELENGTH_MIN) as usize;
+    (0..steps).fold([0f64; 3], |xyz, i| {
+        let wavelength = WAVELENGTH_MIN + i as f64 + 0.5;
+        let value = spectrum(wavelength);
+        let matching = cie_1931(wavelength);
+        std::array::from_fn(|j| xyz[j] + value * matching[j])
+    })
+}
+
+/// Converts CIE 1931 XYZ to linear sRGB without white balancing
+pub(crate) fn xyz_to_rgb([x, y, z]: [f64; 3]) -> Color {
+    Color::from_linear(
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    )
+}
+
+fn balanced_rgb(xyz: [f64; 3]) -> Color {
+    xyz_to_rgb(xyz) * tables().white_balance
+}
+
+/// The spectrum of white light
+fn white(wavelength: f64) -> f64 {
+    tables().white.value(wavelength)
+}
+
+/// The three smooth bands that spectra are upsampled from, which add up to one everywhere
+fn bands(wavelength: f64) -> [f64; 3] {
+    let smoothstep = |from: f64, to: f64| {
+        let t = ((wavelength - from) / (to - from)).clamp(0f64, 1f64);
+        t * t * (3f64 - 2f64 * t)
+    };
+    let red = smoothstep(570f64, 610f64);
+    let blue = 1f64 - smoothstep(470f64, 510f64);
+    [red, 1f64 - red - blue, blue]
+}
+
+/// A smooth spectrum whose color under white light is `rgb`
+fn upsample(rgb: Color, wavelength: f64) -> f64 {
+    let weights = tables()
+        .inverse_bands
+        .transform_vector(Vector::new(rgb.r(), rgb.g(), rgb.b()));
+    let bands = bands(wavelength);
+    (0..3).map(|i| weights.axis(i) * bands[i]).sum()
+}
+
+struct Tables {
+    y_integral: f64,
+    white: Blackbody,
+    white_balance: Color,
+    /// Maps a color to the weights of the bands that reproduce it under white light
+    inverse_bands: Matrix4,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let y_integral = integrate_cie_1931(|_| 1f64)[1];
+        let white = Blackbody::new(WHITE_TEMPERATURE);
+        let rgb = |spectrum: &dyn Fn(f64) -> f64| {
+            xyz_to_rgb(integrate_cie_1931(spectrum).map(|value| value / y_integral))
+        };
+        let white_rgb = rgb(&|wavelength| white.value(wavelength));
+        let white_balance = white_rgb.map(|value| 1f64 / value);
+        let columns: [Color; 3] = std::array::from_fn(|j| {
+            rgb(&|wavelength| bands(wavelength)[j] * white.value(wavelength)) * white_balance
+        });
+        let bands_to_rgb = Matrix4::new([
+            [columns[0].r(), columns[1].r(), columns[2].r(), 0f64],
+            [columns[0].g(), columns[1].g(), columns[2].g(), 0f64],
+            [columns[0].b(), columns[1].b(), columns[2].b(), 0f64],
+            [0f64, 0f64, 0f64, 1f64],
+        ]);
+        Tables {
+            y_integral: y_integral,
+            white: white,
+            white_balance: white_balance,
+            inverse_bands: bands_to_rgb
+                .inverse()
+                .expect("the bands reproduce every color"),
+        }
+    })
+}
+
+#[test]
+fn test_upsampling() {
+    // White light stays white, and colors survive the round trip through a spectrum
+    let white_rgb = to_rgb(&|wavelength| white(wavelength));
+    let tolerance = Color::from_linear(1e-9, 1e-9, 1e-9);
+    assert!((white_rgb - Color::WHITE).abs().less_than(tolerance));
+    for rgb in [
+        Color::from_linear(0.5, 0.5, 0.5),
+        Color::from_linear(0.8, 0.3, 0.2),
+        Color::from_linear(0.1, 0.6, 0.3),
+    ]
+    .iter()
+    {
+        let illuminant = to_rgb(&|wavelength| upsample(*rgb, wavelength) * white(wavelength));
+        assert!((illuminant - *rgb).abs().less_than(tolerance));
+    }
+
+    // Reflectances stay physically plausible
+    let channels = Channels::Spectral(Wavelengths::sample(0.3));
+    let red = channels.reflectance(Color::from_linear(1f64, 0f64, 0f64));
+    assert!(red.less_than(Color::from_linear(1.0001, 1.0001, 1.0001)) && red.min_channel() >= 0f64);
+    let white = channels.reflectance(Color::WHITE);
+    assert!((white - Color::WHITE).abs().less_than(Color::from_linear(1e-9, 1e-9, 1e-9)));
+
+    // Hot black bodies are bluer than cool ones
+    let candle = to_rgb(&Blackbody::new(1900f64));
+    let sky = to_rgb(&Blackbody::new(10000f64));
+    assert!(candle.r() > candle.b() && sky.b() > sky.r());
+
+    // Averaging over random wavelengths estimates the color of a spectrum
+    let samples = 10000;
+    let blackbody = Blackbody::new(3000f64);
+    let estimate = (0..samples)
+        .map(|i| {
+            let wavelengths = Wavelengths::sample((i as f64 + 0.5) / samples as f64);
+            wavelengths.to_rgb(wavelengths.sample_spectrum(&blackbody))
+        })
+        .fold(Color::BLACK, |sum, color| sum + color)
+        / samples as f64;
+    assert!((estimate - to_rgb(&blackbody)).abs().less_than(Color::from_linear(1e-3, 1e-3, 1e-3)));
+}
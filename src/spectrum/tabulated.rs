@@ -0,0 +1,58 @@
+use crate::spectrum::Spectrum;
+
+/// A measured spectrum, interpolated linearly between samples and zero outside of them
+#[derive(Debug, Clone)]
+pub struct Tabulated {
+    samples: Vec<(f64, f64)>,
+}
+
+impl Tabulated {
+    /// Creates a spectrum from pairs of a wavelength in nanometers and a value
+    pub fn new(mut samples: Vec<(f64, f64)>) -> Self {
+        samples.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Self { samples: samples }
+    }
+
+    /// Creates a spectrum from values at equally spaced wavelengths from `first` to `last`
+    pub fn uniform(first: f64, last: f64, values: &[f64]) -> Self {
+        let step = if values.len() > 1 {
+            (last - first) / (values.len() - 1) as f64
+        } else {
+            0f64
+        };
+        Self::new(
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| (first + i as f64 * step, *value))
+                .collect(),
+        )
+    }
+}
+
+impl Spectrum for Tabulated {
+    fn value(&self, wavelength: f64) -> f64 {
+        let next = self
+            .samples
+            .partition_point(|(sample, _)| *sample < wavelength);
+        match (next.checked_sub(1).map(|i| self.samples[i]), self.samples.get(next)) {
+            (Some((w0, v0)), Some(&(w1, v1))) => v0 + (v1 - v0) * (wavelength - w0) / (w1 - w0),
+            (None, Some(&(w1, v1))) if w1 == wavelength => v1,
+            _ => 0f64,
+        }
+    }
+}
+
+#[test]
+fn test_tabulated() {
+    let spectrum = Tabulated::new(vec![(500f64, 1f64), (400f64, 0f64), (600f64, 3f64)]);
+    assert_eq!(spectrum.value(450f64), 0.5);
+    assert_eq!(spectrum.value(550f64), 2f64);
+    assert_eq!(spectrum.value(400f64), 0f64);
+    assert_eq!(spectrum.value(600f64), 3f64);
+    assert_eq!(spectrum.value(350f64), 0f64);
+    assert_eq!(spectrum.value(650f64), 0f64);
+
+    let uniform = Tabulated::uniform(400f64, 700f64, &[1f64, 2f64, 3f64, 4f64]);
+    assert_eq!(uniform.value(550f64), 2.5);
+}
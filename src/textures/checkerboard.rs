@@ -0,0 +1,42 @@
+use crate::primitives::vec::Vector;
+use crate::textures::Texture;
+
+/// Alternates between two values in squares of the surface parameterization
+pub struct Checkerboard<T> {
+    even: T,
+    odd: T,
+    squares: f64,
+}
+
+impl<T> Checkerboard<T> {
+    /// `squares` is the number of squares per unit of u and v
+    pub fn new(even: T, odd: T, squares: f64) -> Self {
+        Self {
+            even: even,
+            odd: odd,
+            squares: squares,
+        }
+    }
+}
+
+impl<T: Copy + Sync + Send> Texture<T> for Checkerboard<T> {
+    fn value(&self, (u, v): (f64, f64), _position: Vector) -> T {
+        let parity = (u * self.squares).floor() + (v * self.squares).floor();
+        if parity.rem_euclid(2f64) == 0f64 {
+            self.even
+        } else {
+            self.odd
+        }
+    }
+}
+
+#[test]
+fn test_checkerboard() {
+    let checkerboard = Checkerboard::new(1f64, 0f64, 4f64);
+    let origin = Vector::new(0f64, 0f64, 0f64);
+    assert_eq!(checkerboard.value((0.1, 0.1), origin), 1f64);
+    assert_eq!(checkerboard.value((0.3, 0.1), origin), 0f64);
+    assert_eq!(checkerboard.value((0.3, 0.3), origin), 1f64);
+    // Negative coordinates continue the pattern
+    assert_eq!(checkerboard.value((-0.1, 0.1), origin), 0f64);
+}
@@ -0,0 +1,137 @@
+use crate::primitives::vec::{Color, Vector};
+use crate::textures::{Mix, Texture};
+
+use std::path::Path;
+
+/// How texture coordinates outside of [0, 1] are mapped back onto the image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WrapMode {
+    /// Tiles the image
+    Repeat,
+    /// Tiles the image, flipping every other tile so that the edges match
+    Mirror,
+    /// Extends the border texels
+    Clamp,
+}
+
+impl WrapMode {
+    /// Maps a texel index onto `0..size`
+    fn wrap(self, index: isize, size: usize) -> usize {
+        let size = size as isize;
+        let wrapped = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Mirror => {
+                let period = index.rem_euclid(2 * size);
+                if period < size {
+                    period
+                } else {
+                    2 * size - 1 - period
+                }
+            }
+            WrapMode::Clamp => index.clamp(0, size - 1),
+        };
+        wrapped as usize
+    }
+}
+
+/// A bilinearly filtered image, with u going to the right and v going down from the top left.
+///
+/// As a texture of scalars it yields the mean of the color channels.
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+    wrap_mode: WrapMode,
+}
+
+impl ImageTexture {
+    /// Creates a texture from `width * height` row-major texels, starting at the top left
+    pub fn new(width: usize, height: usize, texels: Vec<Color>) -> Self {
+        assert_eq!(texels.len(), width * height);
+        assert!(width > 0 && height > 0);
+        Self {
+            width: width,
+            height: height,
+            texels: texels,
+            wrap_mode: WrapMode::Repeat,
+        }
+    }
+
+    /// Loads a texture from an image file, e.g. a PNG or JPEG
+    pub fn open<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        // Undo the gamma of 2 that is applied when exporting images
+        Self::load(path, |value| (value as f64 / 255f64).powi(2))
+    }
+
+    /// Loads a texture that stores data rather than colors, e.g. a normal map, without any gamma
+    pub fn open_linear<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        Self::load(path, |value| value as f64 / 255f64)
+    }
+
+    fn load<P: AsRef<Path>>(path: P, decode: impl Fn(u8) -> f64) -> image::ImageResult<Self> {
+        let img = image::open(path)?.to_rgb();
+        let texels = img
+            .pixels()
+            .map(|p| Color::from_linear(decode(p.0[0]), decode(p.0[1]), decode(p.0[2])))
+            .collect();
+        Ok(Self::new(
+            img.width() as usize,
+            img.height() as usize,
+            texels,
+        ))
+    }
+
+    pub fn set_wrap_mode(&mut self, wrap_mode: WrapMode) {
+        self.wrap_mode = wrap_mode;
+    }
+
+    fn texel(&self, x: isize, y: isize) -> Color {
+        let x = self.wrap_mode.wrap(x, self.width);
+        let y = self.wrap_mode.wrap(y, self.height);
+        self.texels[y * self.width + x]
+    }
+}
+
+impl Texture<Color> for ImageTexture {
+    fn value(&self, (u, v): (f64, f64), _position: Vector) -> Color {
+        // Texel centers lie at half-integer coordinates
+        let x = u * self.width as f64 - 0.5;
+        let y = v * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+
+        let top = self.texel(x0, y0).mix(self.texel(x0 + 1, y0), tx);
+        let bottom = self.texel(x0, y0 + 1).mix(self.texel(x0 + 1, y0 + 1), tx);
+        top.mix(bottom, ty)
+    }
+}
+
+impl Texture<f64> for ImageTexture {
+    fn value(&self, uv: (f64, f64), position: Vector) -> f64 {
+        let color: Color = self.value(uv, position);
+        (color.r() + color.g() + color.b()) / 3f64
+    }
+}
+
+#[test]
+fn test_bilinear_filtering() {
+    let black = Color::BLACK;
+    let white = Color::from_linear(1f64, 1f64, 1f64);
+    let mut texture = ImageTexture::new(2, 1, vec![black, white]);
+    let origin = Vector::new(0f64, 0f64, 0f64);
+    let value = |texture: &ImageTexture, u: f64| Texture::<f64>::value(texture, (u, 0.5), origin);
+
+    // Texel centers reproduce the texels, in between they are blended
+    assert!((value(&texture, 0.25) - 0f64).abs() < 1e-12);
+    assert!((value(&texture, 0.75) - 1f64).abs() < 1e-12);
+    assert!((value(&texture, 0.5) - 0.5).abs() < 1e-12);
+
+    // Beyond the right edge, repeating blends back into the first texel
+    assert!((value(&texture, 1f64) - 0.5).abs() < 1e-12);
+    texture.set_wrap_mode(WrapMode::Clamp);
+    assert!((value(&texture, 1f64) - 1f64).abs() < 1e-12);
+    texture.set_wrap_mode(WrapMode::Mirror);
+    assert!((value(&texture, 1.1) - 1f64).abs() < 1e-12);
+    assert!((value(&texture, 1.75) - 0f64).abs() < 1e-12);
+}
@@ -0,0 +1,49 @@
+pub mod checkerboard;
+pub mod image_texture;
+pub mod noise;
+
+use crate::primitives::vec::{Color, Vector};
+
+/// A material parameter that varies over the surface of an object.
+///
+/// Textures are evaluated at the surface parameterization `uv` of the hit and at its world space
+/// `position`. Closures are textures, too.
+pub trait Texture<T>: Sync + Send {
+    fn value(&self, uv: (f64, f64), position: Vector) -> T;
+}
+
+impl<T, F> Texture<T> for F
+where
+    F: Fn((f64, f64), Vector) -> T + Sync + Send,
+{
+    fn value(&self, uv: (f64, f64), position: Vector) -> T {
+        self(uv, position)
+    }
+}
+
+/// The same value everywhere
+pub struct Constant<T>(pub T);
+
+impl<T: Copy + Sync + Send> Texture<T> for Constant<T> {
+    fn value(&self, _uv: (f64, f64), _position: Vector) -> T {
+        self.0
+    }
+}
+
+/// Values that textures can blend between
+pub trait Mix: Copy + Sync + Send {
+    /// Linear interpolation, `self` at `t = 0` and `other` at `t = 1`
+    fn mix(self, other: Self, t: f64) -> Self;
+}
+
+impl Mix for f64 {
+    fn mix(self, other: Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Mix for Color {
+    fn mix(self, other: Self, t: f64) -> Self {
+        self * (1f64 - t) + other * t
+    }
+}
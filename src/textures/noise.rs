@@ -0,0 +1,220 @@
+use crate::primitives::vec::Vector;
+use crate::textures::{Mix, Texture};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::f64::consts::PI;
+
+/// Ken Perlin's improved gradient noise
+pub struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    /// Different seeds give different, but reproducible, noise
+    pub fn new(seed: u64) -> Self {
+        let mut values: Vec<u8> = (0..=255).collect();
+        values.shuffle(&mut StdRng::seed_from_u64(seed));
+        let mut permutation = [0u8; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = values[i % 256];
+        }
+        Self {
+            permutation: permutation,
+        }
+    }
+
+    /// Smooth noise in about [-1, 1] with features of about unit size, zero at integer points
+    pub fn noise(&self, point: Vector) -> f64 {
+        let cell = |value: f64| (value.floor() as i64 & 255) as usize;
+        let (x, y, z) = (cell(point.x()), cell(point.y()), cell(point.z()));
+        let (fx, fy, fz) = (
+            point.x() - point.x().floor(),
+            point.y() - point.y().floor(),
+            point.z() - point.z().floor(),
+        );
+        let fade = |t: f64| t * t * t * (t * (t * 6f64 - 15f64) + 10f64);
+        let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+
+        let p = &self.permutation;
+        let hash =
+            |dx: usize, dy: usize, dz: usize| p[p[p[x + dx] as usize + y + dy] as usize + z + dz];
+        let corner = |dx: usize, dy: usize, dz: usize| {
+            gradient(
+                hash(dx, dy, dz),
+                fx - dx as f64,
+                fy - dy as f64,
+                fz - dz as f64,
+            )
+        };
+
+        let x00 = corner(0, 0, 0).mix(corner(1, 0, 0), u);
+        let x10 = corner(0, 1, 0).mix(corner(1, 1, 0), u);
+        let x01 = corner(0, 0, 1).mix(corner(1, 0, 1), u);
+        let x11 = corner(0, 1, 1).mix(corner(1, 1, 1), u);
+        x00.mix(x10, v).mix(x01.mix(x11, v), w)
+    }
+
+    /// Fractal Brownian motion: octaves of noise with doubling frequency and halving amplitude,
+    /// normalized to about [-1, 1]
+    pub fn fbm(&self, point: Vector, octaves: usize) -> f64 {
+        let mut sum = 0f64;
+        let mut total_amplitude = 0f64;
+        let mut amplitude = 1f64;
+        let mut frequency = 1f64;
+        for _ in 0..octaves.max(1) {
+            sum += amplitude * self.noise(point * frequency);
+            total_amplitude += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2f64;
+        }
+        sum / total_amplitude
+    }
+
+    /// Like [`Perlin::fbm`], but summing absolute values, which gives sharp creases in [0, 1]
+    pub fn turbulence(&self, point: Vector, octaves: usize) -> f64 {
+        let mut sum = 0f64;
+        let mut total_amplitude = 0f64;
+        let mut amplitude = 1f64;
+        let mut frequency = 1f64;
+        for _ in 0..octaves.max(1) {
+            sum += amplitude * self.noise(point * frequency).abs();
+            total_amplitude += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2f64;
+        }
+        sum / total_amplitude
+    }
+}
+
+/// The dot product of the offset with one of twelve gradients that point to cube edges
+fn gradient(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Cloudy noise blending between `low` and `high`
+pub struct Fbm<T> {
+    perlin: Perlin,
+    scale: f64,
+    octaves: usize,
+    low: T,
+    high: T,
+}
+
+impl<T> Fbm<T> {
+    /// `scale` is the frequency of the coarsest octave per world unit
+    pub fn new(seed: u64, scale: f64, octaves: usize, low: T, high: T) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale: scale,
+            octaves: octaves,
+            low: low,
+            high: high,
+        }
+    }
+}
+
+impl<T: Mix> Texture<T> for Fbm<T> {
+    fn value(&self, _uv: (f64, f64), position: Vector) -> T {
+        let noise = self.perlin.fbm(position * self.scale, self.octaves);
+        self.low
+            .mix(self.high, (0.5 + 0.5 * noise).clamp(0f64, 1f64))
+    }
+}
+
+/// Veins along the x axis, distorted by turbulence
+pub struct Marble<T> {
+    perlin: Perlin,
+    scale: f64,
+    turbulence: f64,
+    low: T,
+    high: T,
+}
+
+impl<T> Marble<T> {
+    /// `scale` is the number of veins per 2π world units, `turbulence` how strongly they are distorted
+    pub fn new(seed: u64, scale: f64, turbulence: f64, low: T, high: T) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale: scale,
+            turbulence: turbulence,
+            low: low,
+            high: high,
+        }
+    }
+}
+
+impl<T: Mix> Texture<T> for Marble<T> {
+    fn value(&self, _uv: (f64, f64), position: Vector) -> T {
+        let point = position * self.scale;
+        let phase = point.x() + self.turbulence * self.perlin.turbulence(point, 6);
+        self.low.mix(self.high, 0.5 + 0.5 * phase.sin())
+    }
+}
+
+/// Growth rings around the y axis, slightly distorted by noise
+pub struct Wood<T> {
+    perlin: Perlin,
+    ring_spacing: f64,
+    turbulence: f64,
+    low: T,
+    high: T,
+}
+
+impl<T> Wood<T> {
+    /// `ring_spacing` is the distance between rings in world units, `turbulence` their distortion
+    /// as a fraction of the spacing
+    pub fn new(seed: u64, ring_spacing: f64, turbulence: f64, low: T, high: T) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            ring_spacing: ring_spacing,
+            turbulence: turbulence,
+            low: low,
+            high: high,
+        }
+    }
+}
+
+impl<T: Mix> Texture<T> for Wood<T> {
+    fn value(&self, _uv: (f64, f64), position: Vector) -> T {
+        let point = position / self.ring_spacing;
+        let radius = (point.x() * point.x() + point.z() * point.z()).sqrt();
+        let rings = radius + self.turbulence * self.perlin.fbm(point, 4);
+        // Light early wood fading into a dark band of late wood
+        let t = (rings * 2f64 * PI).cos() * 0.5 + 0.5;
+        self.low.mix(self.high, t.powi(4))
+    }
+}
+
+#[test]
+fn test_perlin() {
+    let perlin = Perlin::new(7);
+    let mut min = f64::INFINITY;
+    let mut max = f64::NEG_INFINITY;
+    for i in 0..1000 {
+        let point = Vector::new(i as f64 * 0.37, i as f64 * 0.11 - 20f64, i as f64 * -0.23);
+        let value = perlin.noise(point);
+        min = min.min(value);
+        max = max.max(value);
+
+        // Continuous and reproducible
+        let nearby = perlin.noise(point + Vector::new(1e-6, 0f64, 0f64));
+        assert!((value - nearby).abs() < 1e-4);
+        assert_eq!(value, Perlin::new(7).noise(point));
+    }
+    assert!(min >= -1f64 && max <= 1f64);
+    assert!(max - min > 0.5);
+
+    assert_eq!(perlin.noise(Vector::new(3f64, -2f64, 5f64)), 0f64);
+    assert!(perlin.turbulence(Vector::new(0.3, 0.7, 0.1), 5) >= 0f64);
+}